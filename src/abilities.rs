@@ -1,41 +1,47 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
 use bevy::{
+    ecs::system::EntityCommands,
     prelude::{
-        Assets, Commands, Component, DespawnRecursiveExt, Entity, EventReader, GlobalTransform,
-        Query, Res, ResMut, Transform, Vec2, Vec3, With, Without,
+        Assets, BuildChildren, Commands, Component, DespawnRecursiveExt, Entity, EventReader,
+        EventWriter, GlobalTransform, Query, Res, ResMut, SpatialBundle, Transform, Vec2, Vec3,
+        With, Without,
     },
     sprite::{Sprite, SpriteBundle, SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
-    time::{Time, Timer},
+    time::Timer,
 };
 use bevy_inspector_egui::Inspectable;
 use bevy_kira_audio::{Audio, AudioChannel, AudioControl};
 use heron::{
-    CollisionEvent, CollisionLayers, CollisionShape, RigidBody, RotationConstraints, Velocity,
+    CollisionLayers, CollisionShape, PhysicMaterial, RigidBody, RotationConstraints, Velocity,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     animation::Animated,
     audio::{AudioAssets, SoundChannel},
-    damage::Hurtbox,
+    collision::{PlayerTouched, ProjectileHit},
+    combinations::{Combinations, Platform, ProjectileKind},
     destruction::DestructionTimer,
     entity::{
         block::Block,
-        goblin::{AnimationState, Patrol},
-        lava::Lava,
+        goblin::Enemy,
+        lava::{self, Lava},
         player::Player,
         Flamable,
     },
+    events::GameEvent,
     input::Controllable,
-    physics::{PhysicsLayers, PhysicsObjectBundle},
+    physics::{self, PhysicsLayers, PhysicsObjectBundle},
     state::load_game::GameAssets,
 };
 
-#[derive(Inspectable, Component, PartialEq, Eq, Clone, Copy)]
+#[derive(Inspectable, Component, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Equipment {
     Staff,
     MagicBoots,
     Cloak,
+    Amulet,
 }
 impl Display for Equipment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -43,15 +49,17 @@ impl Display for Equipment {
             Self::Staff => write!(f, "Staff"),
             Self::MagicBoots => write!(f, "Magic Boots"),
             Self::Cloak => write!(f, "Cloak of Resistance"),
+            Self::Amulet => write!(f, "Amulet"),
         }
     }
 }
 
-#[derive(Inspectable, Component, PartialEq, Eq, Clone, Copy)]
+#[derive(Inspectable, Component, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Element {
     Fire,
     Air,
     Water,
+    Earth,
 }
 impl Display for Element {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -59,6 +67,7 @@ impl Display for Element {
             Self::Fire => write!(f, "Fire"),
             Self::Air => write!(f, "Air"),
             Self::Water => write!(f, "Water"),
+            Self::Earth => write!(f, "Earth"),
         }
     }
 }
@@ -69,10 +78,18 @@ pub struct FireProjectile;
 pub struct WindProjectile;
 #[derive(Component)]
 pub struct WaterProjectile;
+#[derive(Component)]
+pub struct StonePlatform;
+/// The combination a projectile was cast with, for enemies only hurt by certain combinations
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cast(pub Equipment, pub Element);
+/// Added to the player while slamming down, removed once grounded
+#[derive(Component)]
+pub struct GroundPound;
 
 // TODO: split into multiple systems
+/// Runs in `FixedUpdateStage` so replays cast on the same step as the recording
 pub fn use_ability(
-    time: Res<Time>,
     mut commands: Commands,
     mut query: Query<(
         &mut Controllable,
@@ -81,65 +98,67 @@ pub fn use_ability(
         &TextureAtlasSprite,
     )>,
     game_assets: Res<GameAssets>,
+    combinations: Res<Combinations>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     sound_channel: Res<Audio>,
     audio_assets: Res<AudioAssets>,
+    mut game_events: EventWriter<GameEvent>,
 ) {
     for (mut controllable, transform, player, sprite) in query.iter_mut() {
-        controllable.ability_timer.tick(time.delta());
-        if controllable.ability
-            && controllable.ability_timer.finished()
-            && player.has_equipt(Equipment::Staff)
-        {
-            if player.has_infused(Element::Fire) {
-                controllable.ability_timer.reset();
-                let vel_x = if sprite.flip_x { -150.0 } else { 150.0 };
-                let texture_handle = game_assets.fireball.clone();
-                let texture_atlas =
-                    TextureAtlas::from_grid(texture_handle, Vec2::new(16.0, 16.0), 4, 1);
-                let texture_atlas_handle = texture_atlases.add(texture_atlas);
-                let mut projectile_sprite = TextureAtlasSprite::default();
-                if sprite.flip_x {
-                    projectile_sprite.flip_x = true;
-                }
-                commands
-                    .spawn()
-                    .insert_bundle(SpriteSheetBundle {
-                        texture_atlas: texture_atlas_handle,
-                        transform: Transform::from_translation(transform.translation()),
-                        sprite: projectile_sprite,
-                        ..Default::default()
-                    })
-                    .insert(Animated::new(0.05, 0, 4, false))
-                    .insert(FireProjectile)
-                    .insert(DestructionTimer(Timer::from_seconds(0.6, false)))
-                    .insert_bundle(PhysicsObjectBundle {
-                        collider: CollisionShape::Cuboid {
-                            half_extends: Vec3 {
-                                x: 2.5,
-                                y: 0.5,
-                                z: 0.0,
-                            },
-                            border_radius: Some(4.0),
-                        },
-                        rb: RigidBody::KinematicVelocityBased,
-                        rot_constraints: RotationConstraints::lock(),
-                        velocity: Velocity::from_linear(Vec3 {
-                            x: vel_x,
-                            y: 0.0,
-                            z: 0.0,
-                        }),
-                        ..Default::default()
-                    })
-                    .insert(
-                        CollisionLayers::none()
-                            .with_group(PhysicsLayers::Fireball)
-                            .with_masks([PhysicsLayers::Enemy, PhysicsLayers::Wood]),
-                    );
+        controllable
+            .ability_timer
+            .tick(Duration::from_secs_f64(1.0 / physics::STEPS_PER_SECOND));
+        if !controllable.ability || !controllable.ability_timer.finished() {
+            continue;
+        }
+        if let Some(platform) = combinations.platform(player.combination) {
+            controllable.ability_timer.reset();
+            raise_platform(
+                &mut commands,
+                &game_assets,
+                &mut texture_atlases,
+                transform.translation(),
+                sprite.flip_x,
+                platform,
+            );
+            sound_channel.play(audio_assets.explosion.clone());
+            continue;
+        }
+        let projectile = match combinations.projectile(player.combination) {
+            Some(projectile) => projectile,
+            None => continue,
+        };
+        controllable.ability_timer.reset();
+        let vel_x = if sprite.flip_x {
+            -projectile.speed
+        } else {
+            projectile.speed
+        };
+        let mut projectile_entity = commands.spawn();
+        if let (Some(equipment), Some(element)) = player.combination {
+            projectile_entity.insert(Cast(equipment, element));
+        }
+        if let Some(lifetime) = projectile.lifetime {
+            projectile_entity.insert(DestructionTimer(Timer::from_seconds(lifetime, false)));
+        }
+        match projectile.kind {
+            ProjectileKind::Fireball => {
+                insert_fireball(
+                    &mut projectile_entity,
+                    &game_assets,
+                    &mut texture_atlases,
+                    transform.translation(),
+                    Vec3::new(vel_x, 0.0, 0.0),
+                );
+                projectile_entity.insert(FireProjectile).insert(
+                    CollisionLayers::none()
+                        .with_group(PhysicsLayers::Fireball)
+                        .with_masks([PhysicsLayers::Enemy, PhysicsLayers::Wood]),
+                );
                 sound_channel.play(audio_assets.fireball.clone());
-            } else if player.has_infused(Element::Air) {
-                controllable.ability_timer.reset();
-                let vel_x = if sprite.flip_x { -50.0 } else { 50.0 };
+                game_events.send(GameEvent::FireballCast);
+            }
+            ProjectileKind::Gust => {
                 let texture_handle = game_assets.wind.clone();
                 let texture_atlas =
                     TextureAtlas::from_grid(texture_handle, Vec2::new(16.0, 16.0), 5, 1);
@@ -148,8 +167,7 @@ pub fn use_ability(
                 if sprite.flip_x {
                     projectile_sprite.flip_x = true;
                 }
-                commands
-                    .spawn()
+                projectile_entity
                     .insert_bundle(SpriteSheetBundle {
                         texture_atlas: texture_atlas_handle,
                         transform: Transform::from_translation(transform.translation()),
@@ -158,7 +176,6 @@ pub fn use_ability(
                     })
                     .insert(Animated::new(0.1, 0, 5, false))
                     .insert(WindProjectile)
-                    .insert(DestructionTimer(Timer::from_seconds(0.6, false)))
                     .insert_bundle(PhysicsObjectBundle {
                         collider: CollisionShape::Cuboid {
                             half_extends: Vec3 {
@@ -180,19 +197,17 @@ pub fn use_ability(
                     .insert(
                         CollisionLayers::none()
                             .with_group(PhysicsLayers::Wind)
-                            .with_mask(PhysicsLayers::Movable),
+                            .with_masks([PhysicsLayers::Movable, PhysicsLayers::Enemy]),
                     );
                 sound_channel.play(audio_assets.air.clone());
-            } else if player.has_infused(Element::Water) {
-                controllable.ability_timer.reset();
-                let vel_x = if sprite.flip_x { -100.0 } else { 100.0 };
+            }
+            ProjectileKind::Droplet => {
                 let texture_handle = game_assets.droplet.clone();
                 let mut projectile_sprite = Sprite::default();
                 if sprite.flip_x {
                     projectile_sprite.flip_x = true;
                 }
-                commands
-                    .spawn()
+                projectile_entity
                     .insert_bundle(SpriteBundle {
                         transform: Transform::from_translation(transform.translation()),
                         texture: texture_handle,
@@ -214,7 +229,11 @@ pub fn use_ability(
                     .insert(
                         CollisionLayers::none()
                             .with_group(PhysicsLayers::WaterDrop)
-                            .with_masks([PhysicsLayers::Terrain, PhysicsLayers::Lava]),
+                            .with_masks([
+                                PhysicsLayers::Terrain,
+                                PhysicsLayers::Lava,
+                                PhysicsLayers::Enemy,
+                            ]),
                     );
                 sound_channel.play(audio_assets.pew.clone());
             }
@@ -222,57 +241,133 @@ pub fn use_ability(
     }
 }
 
+/// Sprite and body of a fireball, shared with the fireballs enemies throw. Collision layers are
+/// left to the caller.
+pub fn insert_fireball(
+    entity: &mut EntityCommands,
+    game_assets: &GameAssets,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    translation: Vec3,
+    velocity: Vec3,
+) {
+    let texture_atlas =
+        TextureAtlas::from_grid(game_assets.fireball.clone(), Vec2::new(16.0, 16.0), 4, 1);
+    entity
+        .insert_bundle(SpriteSheetBundle {
+            texture_atlas: texture_atlases.add(texture_atlas),
+            transform: Transform::from_translation(translation),
+            sprite: TextureAtlasSprite {
+                flip_x: velocity.x < 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Animated::new(0.05, 0, 4, false))
+        .insert_bundle(PhysicsObjectBundle {
+            collider: CollisionShape::Cuboid {
+                half_extends: Vec3 {
+                    x: 2.5,
+                    y: 0.5,
+                    z: 0.0,
+                },
+                border_radius: Some(4.0),
+            },
+            rb: RigidBody::KinematicVelocityBased,
+            rot_constraints: RotationConstraints::lock(),
+            velocity: Velocity::from_linear(velocity),
+            ..Default::default()
+        });
+}
+
+/// Spawns a stone platform at the player's feet, one tile in front of them
+fn raise_platform(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    position: Vec3,
+    flip_x: bool,
+    platform: Platform,
+) {
+    let half_width = platform.width as f32 * 8.0;
+    let offset_x = half_width + 8.0;
+    let translation = Vec3::new(
+        if flip_x {
+            position.x - offset_x
+        } else {
+            position.x + offset_x
+        },
+        position.y - 16.0,
+        position.z,
+    );
+    let texture_atlas =
+        TextureAtlas::from_grid(game_assets.lava.clone(), Vec2::new(16.0, 16.0), 9, 1);
+    let texture_atlas_handle = texture_atlases.add(texture_atlas);
+    commands
+        .spawn()
+        .insert_bundle(SpatialBundle::from_transform(Transform::from_translation(
+            translation,
+        )))
+        .insert(StonePlatform)
+        .insert(CollisionShape::Cuboid {
+            half_extends: Vec3::new(half_width, 8.0, 0.0),
+            border_radius: None,
+        })
+        .insert(RigidBody::Static)
+        .insert(PhysicMaterial {
+            friction: 0.0,
+            restitution: 0.0,
+            ..Default::default()
+        })
+        .insert(CollisionLayers::all_masks::<PhysicsLayers>().with_group(PhysicsLayers::Terrain))
+        .insert(DestructionTimer(Timer::from_seconds(
+            platform.lifetime,
+            false,
+        )))
+        .with_children(|parent| {
+            for i in 0..platform.width {
+                parent.spawn_bundle(SpriteSheetBundle {
+                    texture_atlas: texture_atlas_handle.clone(),
+                    // the last frame is stone
+                    sprite: TextureAtlasSprite::new(8),
+                    transform: Transform::from_xyz(i as f32 * 16.0 - half_width + 8.0, 0.0, 0.0),
+                    ..Default::default()
+                });
+            }
+        });
+}
+
+/// Blocks landed on while ground pounding are smashed
+pub fn ground_pound_collision(
+    mut commands: Commands,
+    players: Query<(), (With<Player>, With<GroundPound>)>,
+    blocks: Query<(), With<Block>>,
+    mut touched: EventReader<PlayerTouched>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for event in touched.iter() {
+        if players.contains(event.player) && blocks.contains(event.other) {
+            commands.entity(event.other).despawn_recursive();
+            sound_channel.play(audio_assets.explosion.clone());
+        }
+    }
+}
+
 pub fn fire_projectile_collision(
     mut commands: Commands,
     fireballs: Query<Entity, (With<FireProjectile>, Without<Block>, Without<Lava>)>,
-    flamables: Query<Entity, With<Flamable>>,
-    mut goblins: Query<(&mut AnimationState, &mut Velocity, &mut Patrol)>,
-    mut collisions: EventReader<CollisionEvent>,
+    // Enemies take damage from fireballs through their `EnemyStats` instead
+    flamables: Query<Entity, (With<Flamable>, Without<Enemy>)>,
+    mut hit: EventReader<ProjectileHit>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for event in collisions.iter().filter(|e| e.is_started()) {
-        let (e1, e2) = event.rigid_body_entities();
-        if fireballs.contains(e1) {
-            // entity 1 is projectile
-            if let Ok((mut state, mut velocity, mut patrol)) = goblins.get_mut(e2) {
-                commands.entity(e1).despawn_recursive();
-                sound_channel.play(audio_assets.hurt.clone());
-                // play goblin death animation
-                *state = AnimationState::Death;
-                patrol.movement_speed = 0.0;
-                velocity.linear.x = 0.0;
-                commands
-                    .entity(e2)
-                    .remove::<Hurtbox>()
-                    .remove::<RigidBody>()
-                    .insert(DestructionTimer(Timer::from_seconds(0.6, false)));
-            } else if flamables.contains(e2) {
-                // despawn
-                commands.entity(e2).despawn_recursive();
-                commands.entity(e1).despawn_recursive();
-                sound_channel.play(audio_assets.hurt.clone());
-            }
-        } else if fireballs.contains(e2) {
-            // entity 2 is projectile
-            if let Ok((mut state, mut velocity, mut patrol)) = goblins.get_mut(e1) {
-                commands.entity(e2).despawn_recursive();
-                sound_channel.play(audio_assets.hurt.clone());
-                // play goblin death animation
-                *state = AnimationState::Death;
-                patrol.movement_speed = 0.0;
-                velocity.linear.x = 0.0;
-                commands
-                    .entity(e1)
-                    .remove::<Hurtbox>()
-                    .remove::<RigidBody>()
-                    .insert(DestructionTimer(Timer::from_seconds(0.6, false)));
-            } else if flamables.contains(e1) {
-                // despawn
-                commands.entity(e2).despawn_recursive();
-                commands.entity(e1).despawn_recursive();
-                sound_channel.play(audio_assets.hurt.clone());
-            }
+    for event in hit.iter() {
+        if fireballs.contains(event.projectile) && flamables.contains(event.target) {
+            // despawn
+            commands.entity(event.target).despawn_recursive();
+            commands.entity(event.projectile).despawn_recursive();
+            sound_channel.play(audio_assets.hurt.clone());
         }
     }
 }
@@ -283,24 +378,18 @@ pub fn wind_projectile_collision(
         (With<WindProjectile>, Without<Block>),
     >,
     mut blocks: Query<&mut Velocity, (With<Block>, Without<FireProjectile>)>,
-    mut collisions: EventReader<CollisionEvent>,
+    mut hit: EventReader<ProjectileHit>,
+    mut game_events: EventWriter<GameEvent>,
 ) {
-    for event in collisions.iter().filter(|e| e.is_started()) {
-        let (e1, e2) = event.rigid_body_entities();
-        if let Ok((projectile_velocity, mut layers)) = projectiles.get_mut(e1) {
-            // entity 1 is projectile
-            if let Ok(mut velocity) = blocks.get_mut(e2) {
-                // push
-                velocity.linear = projectile_velocity.linear;
-                *layers = layers.without_mask(PhysicsLayers::Movable);
-            }
-        } else if let Ok((projectile_velocity, mut layers)) = projectiles.get_mut(e2) {
-            // entity 2 is projectile
-            if let Ok(mut velocity) = blocks.get_mut(e1) {
-                // push
-                velocity.linear = projectile_velocity.linear;
-                *layers = layers.without_mask(PhysicsLayers::Movable);
-            }
+    for event in hit.iter() {
+        if let (Ok((projectile_velocity, mut layers)), Ok(mut velocity)) = (
+            projectiles.get_mut(event.projectile),
+            blocks.get_mut(event.target),
+        ) {
+            // push
+            velocity.linear = projectile_velocity.linear;
+            *layers = layers.without_mask(PhysicsLayers::Movable);
+            game_events.send(GameEvent::BlockPushed);
         }
     }
 }
@@ -312,44 +401,20 @@ pub fn water_projectile_collision(
         (Entity, &mut Animated, &mut RigidBody, &mut CollisionLayers),
         (With<Lava>, Without<WaterProjectile>),
     >,
-    mut collisions: EventReader<CollisionEvent>,
+    mut hit: EventReader<ProjectileHit>,
+    mut game_events: EventWriter<GameEvent>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for event in collisions.iter().filter(|e| e.is_started()) {
-        let (e1, e2) = event.rigid_body_entities();
-        if projectiles.contains(e1) {
-            // entity 1 is projectile
-            commands.entity(e1).despawn();
-            if let Ok((entity, mut animation, mut rb, mut layers)) = lava.get_mut(e2) {
-                // turn lava to stone
-                animation.start = 8;
-                animation.end = 9;
-                if *rb != RigidBody::Static {
-                    *rb = RigidBody::Static;
-                }
-                commands.entity(entity).remove::<Lava>();
-                if !layers.contains_group(PhysicsLayers::Terrain) {
-                    *layers = layers.with_group(PhysicsLayers::Terrain);
-                }
-                sound_channel.play(audio_assets.steam.clone());
-            }
-        } else if projectiles.contains(e2) {
-            // entity 2 is projectile
-            commands.entity(e2).despawn();
-            if let Ok((entity, mut animation, mut rb, mut layers)) = lava.get_mut(e1) {
-                // turn lava to stone
-                animation.start = 8;
-                animation.end = 9;
-                if *rb != RigidBody::Static {
-                    *rb = RigidBody::Static;
-                }
-                commands.entity(entity).remove::<Lava>();
-                if !layers.contains_group(PhysicsLayers::Terrain) {
-                    *layers = layers.with_group(PhysicsLayers::Terrain);
-                }
-                sound_channel.play(audio_assets.steam.clone());
-            }
+    for event in hit.iter() {
+        if !projectiles.contains(event.projectile) {
+            continue;
+        }
+        commands.entity(event.projectile).despawn();
+        if let Ok((entity, mut animation, mut rb, mut layers)) = lava.get_mut(event.target) {
+            lava::solidify(&mut commands, entity, &mut animation, &mut rb, &mut layers);
+            sound_channel.play(audio_assets.steam.clone());
+            game_events.send(GameEvent::LavaCooled(entity));
         }
     }
 }
@@ -0,0 +1,362 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::{
+    asset::{AddAsset, AssetLoader, LoadContext, LoadedAsset},
+    prelude::{
+        warn, Assets, BuildChildren, Changed, Color, Commands, Component, DespawnRecursiveExt,
+        Entity, EventReader, NodeBundle, Plugin, Query, Res, ResMut, SystemSet, TextBundle,
+    },
+    reflect::TypeUuid,
+    text::TextStyle,
+    time::{Time, Timer},
+    ui::{FlexDirection, PositionType, Style, UiRect, Val},
+    utils::BoxedFuture,
+};
+use bevy_ecs_ldtk::{ldtk::LdtkJson, EntityInstance, LdtkAsset};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    abilities::Equipment,
+    entity::player::Player,
+    events::GameEvent,
+    state::{load_game::GameAssets, State},
+    storage,
+    world::CurrentWorld,
+};
+
+const UNLOCKED_KEY: &str = "achievements.ron";
+/// Seconds each toast stays on screen
+const TOAST_SECONDS: f32 = 4.0;
+
+/// Local achievements, shared by every save slot and announced with a toast when unlocked
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_asset::<Achievements>()
+            .init_asset_loader::<AchievementsLoader>()
+            .insert_resource(UnlockedAchievements::load())
+            .init_resource::<ToastQueue>()
+            .add_system_set(SystemSet::on_enter(State::InGame).with_system(start))
+            .add_system_set(
+                // Keeps going while the ability menu is open on top of the game
+                SystemSet::on_in_stack_update(State::InGame)
+                    .with_system(track)
+                    .with_system(show_toasts),
+            )
+            .add_system_set(
+                SystemSet::on_exit(State::InGame)
+                    .with_system(cleanup)
+                    .with_system(put_away_toast),
+            )
+            // Reaching a trophy usually unlocks something just before the results are shown
+            .add_system_set(SystemSet::on_update(State::Results).with_system(show_toasts))
+            .add_system_set(SystemSet::on_exit(State::Results).with_system(put_away_toast));
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Condition {
+    /// Every goblin in the world defeated
+    DefeatAllGoblins,
+    /// Every lava tile in the world cooled to stone
+    CoolAllLava,
+    /// A trophy reached
+    CompleteWorld,
+    /// A trophy reached without dying on the way
+    CompleteWithoutDying,
+    /// A trophy reached without ever equipping this
+    CompleteWithout(Equipment),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Achievement {
+    /// Key for the unlock on disk, so it must never change once shipped
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "9a3f6d21-7b4e-4c58-a1e2-3d8f5b6c7e90"]
+pub struct Achievements {
+    pub achievements: Vec<Achievement>,
+}
+
+/// Ids of every achievement unlocked so far
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct UnlockedAchievements(pub Vec<String>);
+
+impl UnlockedAchievements {
+    fn load() -> Self {
+        let contents = match storage::read(UNLOCKED_KEY) {
+            Some(contents) => contents,
+            None => return Self::default(),
+        };
+        match ron::from_str(&contents) {
+            Ok(unlocked) => unlocked,
+            Err(e) => {
+                warn!("Failed to parse unlocked achievements: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    fn store(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                if let Err(e) = storage::write(UNLOCKED_KEY, &contents) {
+                    warn!("Failed to write unlocked achievements: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize unlocked achievements: {}", e),
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.iter().any(|unlocked| unlocked == id)
+    }
+}
+
+/// How many of the things counted by conditions a world has
+#[derive(Default, Clone, Copy, Debug)]
+pub struct WorldTotals {
+    pub goblins: usize,
+    pub lava: usize,
+}
+
+impl WorldTotals {
+    pub fn from_project(project: &LdtkJson) -> Self {
+        let mut totals = Self::default();
+        let entities = project
+            .levels
+            .iter()
+            .flat_map(|level| level.layer_instances.iter().flatten())
+            .flat_map(|layer| layer.entity_instances.iter());
+        for entity in entities {
+            match entity.identifier.as_str() {
+                "Goblin" => totals.goblins += 1,
+                "Lava" => totals.lava += 1,
+                _ => {}
+            }
+        }
+        totals
+    }
+}
+
+/// What has happened since the world was entered. Goblins and lava are tracked by their LDtk iid
+/// since both come back when the player respawns.
+#[derive(Default, Debug)]
+pub struct AchievementProgress {
+    pub died: bool,
+    pub equipped: Vec<Equipment>,
+    pub goblins: HashSet<String>,
+    pub lava: HashSet<String>,
+    pub completed: bool,
+}
+
+impl AchievementProgress {
+    pub fn is_met(&self, condition: Condition, totals: WorldTotals) -> bool {
+        match condition {
+            Condition::DefeatAllGoblins => {
+                totals.goblins > 0 && self.goblins.len() >= totals.goblins
+            }
+            Condition::CoolAllLava => totals.lava > 0 && self.lava.len() >= totals.lava,
+            Condition::CompleteWorld => self.completed,
+            Condition::CompleteWithoutDying => self.completed && !self.died,
+            Condition::CompleteWithout(equipment) => {
+                self.completed && !self.equipped.contains(&equipment)
+            }
+        }
+    }
+}
+
+/// Unlocks waiting to be shown, one toast at a time. Kept between states so none are lost when
+/// the game is left.
+#[derive(Default)]
+struct ToastQueue(VecDeque<Achievement>);
+
+#[derive(Component)]
+struct Toast {
+    timer: Timer,
+    achievement: Achievement,
+}
+
+fn start(mut commands: Commands) {
+    commands.insert_resource(AchievementProgress::default());
+}
+
+fn track(
+    mut events: EventReader<GameEvent>,
+    player: Query<&Player, Changed<Player>>,
+    mut progress: ResMut<AchievementProgress>,
+    mut unlocked: ResMut<UnlockedAchievements>,
+    mut toasts: ResMut<ToastQueue>,
+    entity_instances: Query<&EntityInstance>,
+    game_assets: Res<GameAssets>,
+    achievements: Res<Assets<Achievements>>,
+    current_world: Res<CurrentWorld>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    // Also catches equipment carried over from a save, which is never announced
+    for player in player.iter() {
+        if let Some(equipment) = player.combination.0 {
+            if !progress.equipped.contains(&equipment) {
+                progress.equipped.push(equipment);
+            }
+        }
+    }
+    let iid = |entity: &Entity| {
+        entity_instances
+            .get(*entity)
+            .map(|entity_instance| entity_instance.iid.clone())
+            .ok()
+    };
+    let mut changed = false;
+    for event in events.iter() {
+        match event {
+            GameEvent::Death(_) => progress.died = true,
+            GameEvent::EnemyKilled(entity) => {
+                // Only goblins count towards `DefeatAllGoblins`
+                match entity_instances.get(*entity) {
+                    Ok(entity_instance) if entity_instance.identifier == "Goblin" => {
+                        progress.goblins.insert(entity_instance.iid.clone());
+                    }
+                    _ => continue,
+                }
+            }
+            GameEvent::LavaCooled(entity) => {
+                if let Some(iid) = iid(entity) {
+                    progress.lava.insert(iid);
+                }
+            }
+            GameEvent::Equipped(equipment) => {
+                if !progress.equipped.contains(equipment) {
+                    progress.equipped.push(*equipment);
+                }
+            }
+            GameEvent::WorldCompleted => progress.completed = true,
+            GameEvent::FireballCast | GameEvent::BlockPushed => continue,
+        }
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+    let achievements = match achievements.get(&game_assets.achievements) {
+        Some(achievements) => achievements,
+        None => return,
+    };
+    let totals = ldtk_assets
+        .get(&current_world.0.level)
+        .map(|ldtk_asset| WorldTotals::from_project(&ldtk_asset.project))
+        .unwrap_or_default();
+    let mut newly_unlocked = false;
+    for achievement in achievements.achievements.iter() {
+        if !unlocked.contains(&achievement.id) && progress.is_met(achievement.condition, totals) {
+            unlocked.0.push(achievement.id.clone());
+            toasts.0.push_back(achievement.clone());
+            newly_unlocked = true;
+        }
+    }
+    if newly_unlocked {
+        unlocked.store();
+    }
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    mut toasts: ResMut<ToastQueue>,
+    mut shown: Query<(Entity, &mut Toast)>,
+    time: Res<Time>,
+    game_assets: Res<GameAssets>,
+) {
+    if let Ok((entity, mut toast)) = shown.get_single_mut() {
+        toast.timer.tick(time.delta());
+        if toast.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    let achievement = match toasts.0.pop_front() {
+        Some(achievement) => achievement,
+        None => return,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..Default::default()
+                },
+                padding: UiRect::all(Val::Px(8.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                format!("Achievement unlocked: {}", achievement.name),
+                TextStyle {
+                    font: game_assets.pixel_font.clone(),
+                    font_size: 14.0,
+                    color: Color::rgb(0.95, 0.83, 0.47),
+                },
+            ));
+            parent.spawn_bundle(TextBundle::from_section(
+                achievement.description.clone(),
+                TextStyle {
+                    font: game_assets.pixel_font.clone(),
+                    font_size: 10.0,
+                    color: Color::WHITE,
+                },
+            ));
+        })
+        .insert(Toast {
+            timer: Timer::from_seconds(TOAST_SECONDS, false),
+            achievement,
+        });
+}
+
+/// The screen is about to be torn down, so the toast on it goes back to the front of the queue
+/// and is shown again in full on the next screen that shows toasts
+fn put_away_toast(
+    mut commands: Commands,
+    mut toasts: ResMut<ToastQueue>,
+    query: Query<(Entity, &Toast)>,
+) {
+    for (entity, toast) in query.iter() {
+        commands.entity(entity).despawn_recursive();
+        toasts.0.push_front(toast.achievement.clone());
+    }
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<AchievementProgress>();
+}
+
+#[derive(Default)]
+pub struct AchievementsLoader;
+
+impl AssetLoader for AchievementsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let achievements = ron::de::from_bytes::<Achievements>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(achievements));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["achievements.ron"]
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::{
+    Commands, DespawnRecursiveExt, Entity, EventReader, EventWriter, GlobalTransform, Query, Res,
+    With,
+};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+use heron::{CollisionLayers, RigidBody};
+
+use crate::{
+    animation::Animated,
+    audio::{AudioAssets, SoundChannel},
+    collision::PlayerTouched,
+    combinations::{AuraKind, Combinations},
+    entity::{
+        block::Block,
+        lava::{self, Lava},
+        player::Player,
+        torch::Torch,
+        Flamable,
+    },
+    events::GameEvent,
+};
+
+/// Lights torches within reach of a fire aura
+pub fn ignite_torches(
+    player: Query<(&Player, &GlobalTransform)>,
+    mut torches: Query<(&mut Torch, &GlobalTransform)>,
+    combinations: Res<Combinations>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for (player, player_transform) in player.iter() {
+        let aura = match combinations.aura(player.combination) {
+            Some(aura) if aura.kind == AuraKind::Ignite => aura,
+            _ => continue,
+        };
+        for (mut torch, transform) in torches.iter_mut() {
+            if !torch.lit
+                && transform
+                    .translation()
+                    .truncate()
+                    .distance(player_transform.translation().truncate())
+                    <= aura.radius
+            {
+                torch.lit = true;
+                sound_channel.play(audio_assets.fireball.clone());
+            }
+        }
+    }
+}
+
+/// Wooden blocks burn up when touched
+pub fn burn_flamables(
+    mut commands: Commands,
+    player: Query<&Player>,
+    flamables: Query<(), (With<Flamable>, With<Block>)>,
+    mut touched: EventReader<PlayerTouched>,
+    combinations: Res<Combinations>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let is_igniting = |player: &Player| {
+        combinations
+            .aura(player.combination)
+            .map(|aura| aura.kind == AuraKind::Ignite)
+            .unwrap_or(false)
+    };
+    for event in touched.iter() {
+        let igniting = player.get(event.player).map(is_igniting).unwrap_or(false);
+        if igniting && flamables.contains(event.other) {
+            commands.entity(event.other).despawn_recursive();
+            sound_channel.play(audio_assets.hurt.clone());
+        }
+    }
+}
+
+pub fn extinguish_lava(
+    mut commands: Commands,
+    player: Query<(&Player, &GlobalTransform)>,
+    mut lava: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut Animated,
+            &mut RigidBody,
+            &mut CollisionLayers,
+        ),
+        With<Lava>,
+    >,
+    combinations: Res<Combinations>,
+    mut game_events: EventWriter<GameEvent>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for (player, player_transform) in player.iter() {
+        let aura = match combinations.aura(player.combination) {
+            Some(aura) if aura.kind == AuraKind::Extinguish => aura,
+            _ => continue,
+        };
+        let mut cooled = false;
+        for (entity, transform, mut animation, mut rb, mut layers) in lava.iter_mut() {
+            if transform
+                .translation()
+                .truncate()
+                .distance(player_transform.translation().truncate())
+                <= aura.radius
+            {
+                lava::solidify(&mut commands, entity, &mut animation, &mut rb, &mut layers);
+                game_events.send(GameEvent::LavaCooled(entity));
+                cooled = true;
+            }
+        }
+        if cooled {
+            sound_channel.play(audio_assets.steam.clone());
+        }
+    }
+}
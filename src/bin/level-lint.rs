@@ -0,0 +1,256 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    process::ExitCode,
+};
+
+use bevy_ecs_ldtk::{
+    ldtk::{EntityInstance, LdtkJson, Level},
+    prelude::FieldValue,
+};
+use elemental_sorcerer::entity::{fields::FieldErrors, registered::REGISTERED_ENTITIES};
+use serde::Deserialize;
+
+const WORLDS_PATH: &str = "assets/worlds.worlds.ron";
+
+/// The part of `world::Worlds` the lint needs
+#[derive(Deserialize)]
+struct WorldList {
+    worlds: Vec<WorldEntry>,
+}
+
+#[derive(Deserialize)]
+struct WorldEntry {
+    path: String,
+}
+
+/// IntGrid value of walls, see `level::WallBundle`
+const WALL: i32 = 1;
+
+struct Issue {
+    level: String,
+    entity: String,
+    iid: String,
+    message: String,
+}
+
+struct Lint<'a> {
+    level: &'a Level,
+    issues: Vec<Issue>,
+}
+
+impl<'a> Lint<'a> {
+    fn report(&mut self, entity: &EntityInstance, message: String) {
+        self.issues.push(Issue {
+            level: self.level.identifier.clone(),
+            entity: entity.identifier.clone(),
+            iid: entity.iid.clone(),
+            message,
+        });
+    }
+
+    /// Reads the fields the same way the game does when spawning the entity
+    fn entity(&mut self, entity: &EntityInstance) {
+        if !REGISTERED_ENTITIES.contains(&entity.identifier.as_str()) {
+            self.report(
+                entity,
+                "is not registered in LevelPlugin and won't spawn".to_string(),
+            );
+            return;
+        }
+        for error in FieldErrors::from(entity.clone()).errors {
+            self.report(entity, format!("field {}: {}", error.field, error.kind));
+        }
+    }
+}
+
+fn entities(level: &Level) -> impl Iterator<Item = &EntityInstance> {
+    level
+        .layer_instances
+        .iter()
+        .flatten()
+        .flat_map(|layer| layer.entity_instances.iter())
+}
+
+/// Cell coordinates in the whole world, with y going down as in LDtk
+fn world_cell(level: &Level, entity: &EntityInstance, grid_size: i32) -> (i32, i32) {
+    (
+        level.world_x / grid_size + entity.grid.x,
+        level.world_y / grid_size + entity.grid.y,
+    )
+}
+
+/// Every cell inside a level that isn't a wall
+fn open_cells(project: &LdtkJson) -> (HashSet<(i32, i32)>, i32) {
+    let mut cells = HashSet::new();
+    let mut grid_size = 16;
+    for level in &project.levels {
+        for layer in level.layer_instances.iter().flatten() {
+            if layer.identifier != "IntGrid" {
+                continue;
+            }
+            grid_size = layer.grid_size;
+            for (i, value) in layer.int_grid_csv.iter().enumerate() {
+                if *value == WALL {
+                    continue;
+                }
+                let i = i as i32;
+                cells.insert((
+                    level.world_x / grid_size + i % layer.c_wid,
+                    level.world_y / grid_size + i / layer.c_wid,
+                ));
+            }
+        }
+    }
+    (cells, grid_size)
+}
+
+/// Flood fills open cells from the player start. This ignores gravity, so it only catches trophies
+/// that are walled off or placed inside terrain.
+fn reachable_cells(open: &HashSet<(i32, i32)>, start: (i32, i32)) -> HashSet<(i32, i32)> {
+    let mut reached = HashSet::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        if !open.contains(&(x, y)) || !reached.insert((x, y)) {
+            continue;
+        }
+        queue.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+    }
+    reached
+}
+
+fn lint(project: &LdtkJson) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut orbs: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for level in &project.levels {
+        let mut lint = Lint {
+            level,
+            issues: Vec::new(),
+        };
+        for entity in entities(level) {
+            lint.entity(entity);
+            let ability = entity
+                .field_instances
+                .iter()
+                .find(|field| field.identifier == "Ability")
+                .map(|field| &field.value);
+            if let Some(FieldValue::Enum(Some(ability))) = ability {
+                orbs.entry(ability.clone())
+                    .or_default()
+                    .push((level.identifier.clone(), entity.iid.clone()));
+            }
+        }
+        issues.append(&mut lint.issues);
+    }
+
+    for (ability, placements) in orbs.iter().filter(|(_, placements)| placements.len() > 1) {
+        for (level, iid) in placements {
+            issues.push(Issue {
+                level: level.clone(),
+                entity: "Ability".to_string(),
+                iid: iid.clone(),
+                message: format!("duplicate {} orb, {} placed", ability, placements.len()),
+            });
+        }
+    }
+
+    let (open, grid_size) = open_cells(project);
+    // New games start at the StartCheckpoint if there is one, see `player::find_start`
+    let find = |identifier: &str| {
+        project.levels.iter().find_map(|level| {
+            entities(level)
+                .find(|entity| entity.identifier == identifier)
+                .map(|entity| world_cell(level, entity, grid_size))
+        })
+    };
+    let has_player = find("Player").is_some();
+    let start = find("StartCheckpoint").or_else(|| find("Player"));
+    let reached = start.map(|start| reachable_cells(&open, start));
+    let mut trophies = 0;
+    for level in &project.levels {
+        for trophy in entities(level).filter(|entity| entity.identifier == "Trophy") {
+            trophies += 1;
+            let reachable = reached
+                .as_ref()
+                .map(|reached| reached.contains(&world_cell(level, trophy, grid_size)))
+                .unwrap_or(false);
+            if !reachable {
+                issues.push(Issue {
+                    level: level.identifier.clone(),
+                    entity: trophy.identifier.clone(),
+                    iid: trophy.iid.clone(),
+                    message: "can't be reached from the start".to_string(),
+                });
+            }
+        }
+    }
+    if !has_player || trophies == 0 {
+        issues.push(Issue {
+            level: "-".to_string(),
+            entity: "-".to_string(),
+            iid: "-".to_string(),
+            message: "the project needs a Player and at least one Trophy".to_string(),
+        });
+    }
+    issues
+}
+
+/// Prints every problem in the project, returning whether there were any
+fn lint_file(path: &str) -> bool {
+    let project = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| serde_json::from_str::<LdtkJson>(&contents).map_err(|e| e.to_string()))
+    {
+        Ok(project) => project,
+        Err(e) => {
+            eprintln!("Couldn't read {}: {}", path, e);
+            return false;
+        }
+    };
+
+    let issues = lint(&project);
+    for issue in &issues {
+        println!(
+            "{} {} ({}): {}",
+            issue.level, issue.entity, issue.iid, issue.message
+        );
+    }
+    if issues.is_empty() {
+        println!("{}: no problems found", path);
+        true
+    } else {
+        println!("{}: {} problem(s) found", path, issues.len());
+        false
+    }
+}
+
+fn world_paths() -> Result<Vec<String>, String> {
+    let contents = std::fs::read_to_string(WORLDS_PATH).map_err(|e| e.to_string())?;
+    let list = ron::from_str::<WorldList>(&contents).map_err(|e| e.to_string())?;
+    Ok(list
+        .worlds
+        .into_iter()
+        .map(|world| format!("assets/{}", world.path))
+        .collect())
+}
+
+/// Checks the given LDtk project, or every world, exiting non-zero if there are problems
+fn main() -> ExitCode {
+    let paths = match std::env::args().nth(1) {
+        Some(path) => vec![path],
+        None => match world_paths() {
+            Ok(paths) => paths,
+            Err(e) => {
+                eprintln!("Couldn't read {}: {}", WORLDS_PATH, e);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    // Lint every file even after one fails
+    let results: Vec<bool> = paths.iter().map(|path| lint_file(path)).collect();
+    if results.into_iter().all(|ok| ok) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
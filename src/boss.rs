@@ -0,0 +1,608 @@
+use bevy::{
+    asset::{AddAsset, AssetLoader, LoadContext, LoadedAsset},
+    prelude::{
+        warn, Added, AssetServer, Assets, BuildChildren, Camera, Color, Commands, Component,
+        DespawnRecursiveExt, Entity, EventReader, GlobalTransform, NodeBundle, Or, Parent, Plugin,
+        Query, Res, ResMut, SystemSet, TextBundle, Transform, Vec2, Vec3, With, Without,
+    },
+    reflect::TypeUuid,
+    sprite::{Sprite, SpriteBundle, TextureAtlas, TextureAtlasSprite},
+    text::TextStyle,
+    time::{Time, Timer},
+    ui::{FlexDirection, PositionType, Size, Style, UiRect, Val},
+    utils::BoxedFuture,
+};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+use heron::{CollisionLayers, CollisionShape, RigidBody};
+use serde::Deserialize;
+
+use crate::{
+    abilities::{Cast, Element, Equipment, WaterProjectile},
+    audio::{AudioAssets, SoundChannel},
+    collision::{PlayerTouched, ProjectileHitEnemy},
+    damage::{Hurtbox, Killed},
+    destruction::DestructionTimer,
+    entity::{
+        ability::{self, Ability},
+        boss::{Boss, BossArena},
+        fire_imp,
+        goblin::AnimationState,
+        player::Player,
+    },
+    physics::{PhysicsLayers, PhysicsObjectBundle},
+    state::{load_game::GameAssets, State},
+};
+
+/// Thickness of the walls that close off the sides of an arena
+const SEAL_WIDTH: f32 = 8.0;
+const FIREBALL_SPEED: f32 = 110.0;
+
+/// Runs the fights against the bosses defined in `assets/bosses.bosses.ron`
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_asset::<Bosses>()
+            .init_asset_loader::<BossesLoader>()
+            .add_system_set(
+                SystemSet::on_update(State::InGame)
+                    .with_system(dont_spawn_if_defeated)
+                    .with_system(start_fight)
+                    .with_system(hit)
+                    .with_system(attack)
+                    .with_system(show_health)
+                    .with_system(end_fight),
+            )
+            .add_system_set(SystemSet::on_exit(State::InGame).with_system(cleanup));
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Armor {
+    /// Hits needed to break through
+    pub hits: u32,
+    pub broken_by: Vec<(Equipment, Element)>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Phase {
+    pub health: u32,
+    /// Combinations that hurt the boss once any armor is broken
+    pub vulnerable_to: Vec<(Equipment, Element)>,
+    #[serde(default)]
+    pub armor: Option<Armor>,
+    /// Seconds between fireballs thrown at the player, if the boss attacks in this phase
+    #[serde(default)]
+    pub fireball_cooldown: Option<f32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossDefinition {
+    /// Named by the `Boss` field of bosses and arenas in LDtk
+    pub id: String,
+    pub name: String,
+    /// Dropped when the boss is defeated. Once unlocked the boss is never spawned again.
+    pub reward: Ability,
+    pub phases: Vec<Phase>,
+}
+
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "5c1e8b47-2d6a-4f93-b8e0-7a4d9c3f1b26"]
+pub struct Bosses {
+    pub bosses: Vec<BossDefinition>,
+}
+
+impl Bosses {
+    pub fn get(&self, id: &str) -> Option<&BossDefinition> {
+        self.bosses.iter().find(|boss| boss.id == id)
+    }
+}
+
+/// What a projectile did to a boss
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hit {
+    Immune,
+    ArmorCracked,
+    ArmorBroken,
+    Damaged,
+    /// The boss moved on to its next phase
+    PhaseCleared,
+    Defeated,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BossHealth {
+    pub phase: usize,
+    pub health: u32,
+    pub armor: u32,
+}
+
+impl BossHealth {
+    pub fn new(definition: &BossDefinition) -> Self {
+        Self::at_phase(definition, 0)
+    }
+
+    fn at_phase(definition: &BossDefinition, phase: usize) -> Self {
+        let (health, armor) = definition
+            .phases
+            .get(phase)
+            .map(|phase| {
+                (
+                    phase.health,
+                    phase.armor.as_ref().map_or(0, |armor| armor.hits),
+                )
+            })
+            .unwrap_or_default();
+        Self {
+            phase,
+            health,
+            armor,
+        }
+    }
+
+    pub fn hit(&mut self, definition: &BossDefinition, cast: Cast) -> Hit {
+        let phase = match definition.phases.get(self.phase) {
+            Some(phase) => phase,
+            None => return Hit::Immune,
+        };
+        let combination = (cast.0, cast.1);
+        if self.armor > 0 {
+            let breaks_armor = phase
+                .armor
+                .as_ref()
+                .map_or(false, |armor| armor.broken_by.contains(&combination));
+            if !breaks_armor {
+                return Hit::Immune;
+            }
+            self.armor -= 1;
+            return if self.armor == 0 {
+                Hit::ArmorBroken
+            } else {
+                Hit::ArmorCracked
+            };
+        }
+        if !phase.vulnerable_to.contains(&combination) {
+            return Hit::Immune;
+        }
+        self.health = self.health.saturating_sub(1);
+        if self.health > 0 {
+            Hit::Damaged
+        } else if self.phase + 1 < definition.phases.len() {
+            *self = Self::at_phase(definition, self.phase + 1);
+            Hit::PhaseCleared
+        } else {
+            Hit::Defeated
+        }
+    }
+
+    /// Health left across every phase, from 1 at the start of the fight to 0
+    pub fn fraction(&self, definition: &BossDefinition) -> f32 {
+        let total: u32 = definition.phases.iter().map(|phase| phase.health).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let later: u32 = definition
+            .phases
+            .iter()
+            .skip(self.phase + 1)
+            .map(|phase| phase.health)
+            .sum();
+        (self.health + later) as f32 / total as f32
+    }
+
+    pub fn phase<'a>(&self, definition: &'a BossDefinition) -> Option<&'a Phase> {
+        definition.phases.get(self.phase)
+    }
+}
+
+/// The fight in progress, if any
+pub struct BossFight {
+    pub boss: Entity,
+    pub definition: BossDefinition,
+    pub health: BossHealth,
+    /// Where the camera sat relative to the player, restored once the fight is over
+    camera_offset: Transform,
+    attack: Option<Timer>,
+}
+
+impl BossFight {
+    fn attack_timer(&self) -> Option<Timer> {
+        self.health
+            .phase(&self.definition)
+            .and_then(|phase| phase.fireball_cooldown)
+            .map(|cooldown| Timer::from_seconds(cooldown, true))
+    }
+}
+
+/// Closes off a side of the arena during a fight
+#[derive(Component)]
+pub struct ArenaSeal;
+
+#[derive(Component)]
+struct BossHud;
+
+#[derive(Component)]
+struct HealthBar;
+
+/// Bosses whose reward was already collected stay defeated, along with their arenas
+fn dont_spawn_if_defeated(
+    mut commands: Commands,
+    bosses: Query<(Entity, &Boss), Added<Boss>>,
+    arenas: Query<(Entity, &BossArena), Added<BossArena>>,
+    player: Query<&Player>,
+    game_assets: Res<GameAssets>,
+    definitions: Res<Assets<Bosses>>,
+) {
+    let (player, definitions) = match (player.get_single(), definitions.get(&game_assets.bosses)) {
+        (Ok(player), Some(definitions)) => (player, definitions),
+        _ => return,
+    };
+    let defeated = |id: &str| {
+        definitions
+            .get(id)
+            .map_or(false, |definition| player.has_unlocked(definition.reward))
+    };
+    for (entity, boss) in bosses.iter() {
+        if defeated(&boss.id) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for (entity, arena) in arenas.iter() {
+        if defeated(&arena.boss) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn start_fight(
+    mut commands: Commands,
+    fight: Option<Res<BossFight>>,
+    mut touched: EventReader<PlayerTouched>,
+    arenas: Query<(&BossArena, &GlobalTransform)>,
+    bosses: Query<(Entity, &Boss), With<Hurtbox>>,
+    mut cameras: Query<(Entity, &mut Transform, Option<&Parent>), With<Camera>>,
+    game_assets: Res<GameAssets>,
+    definitions: Res<Assets<Bosses>>,
+) {
+    for event in touched.iter() {
+        if fight.is_some() {
+            return;
+        }
+        let (arena, arena_transform) = match arenas.get(event.other) {
+            Ok(arena) => arena,
+            Err(_) => continue,
+        };
+        let boss = match bosses.iter().find(|(_, boss)| boss.id == arena.boss) {
+            Some((boss, _)) => boss,
+            // Already defeated
+            None => continue,
+        };
+        let definition = match definitions
+            .get(&game_assets.bosses)
+            .and_then(|definitions| definitions.get(&arena.boss))
+        {
+            Some(definition) => definition.clone(),
+            None => {
+                warn!("No boss {:?} in bosses.bosses.ron", arena.boss);
+                continue;
+            }
+        };
+        let centre = arena_transform.translation();
+
+        let mut camera_offset = Transform::from_xyz(0.0, 0.0, 900.0);
+        for (camera, mut transform, parent) in cameras.iter_mut() {
+            if let Some(parent) = parent {
+                commands.entity(parent.get()).remove_children(&[camera]);
+            }
+            camera_offset = *transform;
+            transform.translation = centre.truncate().extend(camera_offset.translation.z);
+        }
+
+        for side in [-1.0, 1.0] {
+            let x = centre.x + side * (arena.size.x + SEAL_WIDTH) / 2.0;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.6, 0.3, 0.9, 0.6),
+                        custom_size: Some(Vec2::new(SEAL_WIDTH, arena.size.y)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(x, centre.y, centre.z + 1.0),
+                    ..Default::default()
+                })
+                .insert(ArenaSeal)
+                .insert_bundle(PhysicsObjectBundle {
+                    collider: CollisionShape::Cuboid {
+                        half_extends: Vec3::new(SEAL_WIDTH / 2.0, arena.size.y / 2.0, 0.0),
+                        border_radius: None,
+                    },
+                    rb: RigidBody::Static,
+                    layer: CollisionLayers::all_masks::<PhysicsLayers>()
+                        .with_group(PhysicsLayers::Terrain),
+                    ..Default::default()
+                });
+        }
+
+        spawn_hud(&mut commands, &game_assets, &definition.name);
+        let health = BossHealth::new(&definition);
+        let mut fight = BossFight {
+            boss,
+            definition,
+            health,
+            camera_offset,
+            attack: None,
+        };
+        fight.attack = fight.attack_timer();
+        commands.insert_resource(fight);
+        return;
+    }
+}
+
+fn spawn_hud(commands: &mut Commands, game_assets: &GameAssets, name: &str) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    left: Val::Percent(30.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(40.0), Val::Auto),
+                padding: UiRect::all(Val::Px(8.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..Default::default()
+        })
+        .insert(BossHud)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                name,
+                TextStyle {
+                    font: game_assets.pixel_font.clone(),
+                    font_size: 12.0,
+                    color: Color::WHITE,
+                },
+            ));
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(8.0)),
+                        margin: UiRect {
+                            top: Val::Px(6.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    color: Color::rgb(0.25, 0.05, 0.05).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            color: Color::rgb(0.85, 0.15, 0.15).into(),
+                            ..Default::default()
+                        })
+                        .insert(HealthBar);
+                });
+        });
+}
+
+fn hit(
+    mut commands: Commands,
+    fight: Option<ResMut<BossFight>>,
+    mut hits: EventReader<ProjectileHitEnemy>,
+    projectiles: Query<(Option<&Cast>, Option<&WaterProjectile>)>,
+    mut bosses: Query<(&mut AnimationState, &Transform, Option<&Parent>), With<Boss>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let mut fight = match fight {
+        Some(fight) => fight,
+        None => return,
+    };
+    for event in hits.iter() {
+        if event.enemy != fight.boss {
+            continue;
+        }
+        let (cast, is_droplet) = match projectiles.get(event.projectile) {
+            Ok((cast, droplet)) => (cast.copied(), droplet.is_some()),
+            Err(_) => continue,
+        };
+        // Droplets despawn themselves on any hit
+        if !is_droplet {
+            commands.entity(event.projectile).despawn_recursive();
+        }
+        // Like a deflected imp fireball, which burns out against the boss without hurting it
+        let cast = match cast {
+            Some(cast) => cast,
+            None => continue,
+        };
+        let BossFight {
+            definition, health, ..
+        } = &mut *fight;
+        match health.hit(definition, cast) {
+            Hit::Immune => {}
+            Hit::ArmorCracked | Hit::ArmorBroken => {
+                sound_channel.play(audio_assets.steam.clone());
+            }
+            Hit::Damaged => {
+                sound_channel.play(audio_assets.hurt.clone());
+            }
+            Hit::PhaseCleared => {
+                fight.attack = fight.attack_timer();
+                sound_channel.play(audio_assets.explosion.clone());
+            }
+            Hit::Defeated => {
+                let (mut state, transform, parent) = match bosses.get_mut(fight.boss) {
+                    Ok(boss) => boss,
+                    Err(_) => continue,
+                };
+                *state = AnimationState::Death;
+                commands
+                    .entity(fight.boss)
+                    .remove::<Hurtbox>()
+                    .remove::<RigidBody>()
+                    .insert(DestructionTimer(Timer::from_seconds(0.6, false)));
+                // Dropped into the boss's layer so it goes away with the level
+                let mut orb = commands.spawn();
+                ability::insert_orb(
+                    &mut orb,
+                    fight.definition.reward,
+                    &asset_server,
+                    &mut texture_atlases,
+                    *transform,
+                );
+                let orb = orb.id();
+                if let Some(parent) = parent {
+                    commands.entity(parent.get()).add_child(orb);
+                }
+                sound_channel.play(audio_assets.explosion.clone());
+            }
+        }
+    }
+}
+
+/// Throws fireballs at the player in phases that have a cooldown
+fn attack(
+    mut commands: Commands,
+    fight: Option<ResMut<BossFight>>,
+    mut bosses: Query<
+        (
+            &GlobalTransform,
+            &mut TextureAtlasSprite,
+            &mut AnimationState,
+        ),
+        (With<Boss>, With<Hurtbox>),
+    >,
+    player: Query<&GlobalTransform, (With<Player>, Without<Killed>)>,
+    time: Res<Time>,
+    game_assets: Res<GameAssets>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let mut fight = match fight {
+        Some(fight) => fight,
+        None => return,
+    };
+    let boss = fight.boss;
+    let timer = match &mut fight.attack {
+        Some(timer) => timer,
+        None => return,
+    };
+    let (transform, mut sprite, mut animation) = match bosses.get_mut(boss) {
+        Ok(boss) => boss,
+        Err(_) => return,
+    };
+    let player = match player.get_single() {
+        Ok(player) => player.translation(),
+        Err(_) => return,
+    };
+    timer.tick(time.delta());
+    fire_imp::finish_throw(&mut animation, timer);
+    let to_player = (player - transform.translation()).truncate();
+    sprite.flip_x = to_player.x < 0.0;
+    if !timer.just_finished() {
+        return;
+    }
+    fire_imp::throw_fireball(
+        &mut commands,
+        &game_assets,
+        &mut texture_atlases,
+        transform.translation(),
+        (to_player.normalize_or_zero() * FIREBALL_SPEED).extend(0.0),
+        &mut animation,
+    );
+    sound_channel.play(audio_assets.fireball.clone());
+}
+
+/// Keeps the health bar and the glow of the boss's armor up to date
+fn show_health(
+    fight: Option<Res<BossFight>>,
+    mut bars: Query<&mut Style, With<HealthBar>>,
+    mut bosses: Query<&mut TextureAtlasSprite, (With<Boss>, With<Hurtbox>)>,
+) {
+    let fight = match fight {
+        Some(fight) if fight.is_changed() => fight,
+        _ => return,
+    };
+    let fraction = fight.health.fraction(&fight.definition);
+    for mut style in bars.iter_mut() {
+        style.size.width = Val::Percent(fraction * 100.0);
+    }
+    if let Ok(mut sprite) = bosses.get_mut(fight.boss) {
+        sprite.color = if fight.health.armor > 0 {
+            Color::rgb(1.0, 0.55, 0.3)
+        } else {
+            Color::rgb(0.8, 0.6, 1.0)
+        };
+    }
+}
+
+/// The fight is over once the boss is defeated, or gone because the level respawned, or the
+/// player dies
+fn end_fight(
+    mut commands: Commands,
+    fight: Option<Res<BossFight>>,
+    bosses: Query<(), (With<Boss>, With<Hurtbox>)>,
+    player: Query<(Entity, Option<&Killed>), With<Player>>,
+    mut cameras: Query<(Entity, &mut Transform), With<Camera>>,
+    hud: Query<Entity, Or<(With<ArenaSeal>, With<BossHud>)>>,
+) {
+    let fight = match fight {
+        Some(fight) => fight,
+        None => return,
+    };
+    let (player, killed) = match player.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    if bosses.contains(fight.boss) && killed.is_none() {
+        return;
+    }
+    for (camera, mut transform) in cameras.iter_mut() {
+        *transform = fight.camera_offset;
+        commands.entity(player).add_child(camera);
+    }
+    for entity in hud.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<BossFight>();
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, Or<(With<ArenaSeal>, With<BossHud>)>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<BossFight>();
+}
+
+#[derive(Default)]
+pub struct BossesLoader;
+
+impl AssetLoader for BossesLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let bosses = ron::de::from_bytes::<Bosses>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(bosses));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["bosses.ron"]
+    }
+}
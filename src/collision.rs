@@ -0,0 +1,240 @@
+use bevy::prelude::{Entity, EventReader, EventWriter, GlobalTransform, Or, Query, Vec3, With};
+use heron::CollisionEvent;
+
+use crate::{
+    abilities::{FireProjectile, WaterProjectile, WindProjectile},
+    damage::{DamageSource, Hurtbox},
+    entity::{
+        ability::Ability, checkpoint::Checkpoint, fan::ForceArea, fire_imp::ImpFireball,
+        goblin::Enemy, lava::Lava, player::Player, trophy::Trophy, water::Water, ProximityText,
+    },
+    level::Spike,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Contact {
+    Started,
+    Stopped,
+}
+
+/// The player started or stopped touching something with a `ProximityText`
+pub struct PlayerProximity {
+    pub player: Entity,
+    pub target: Entity,
+    pub contact: Contact,
+}
+
+pub struct PlayerEnteredCheckpoint {
+    pub player: Entity,
+    pub checkpoint: Entity,
+}
+
+pub struct PlayerLeftCheckpoint {
+    pub player: Entity,
+    pub checkpoint: Entity,
+}
+
+pub struct PlayerReachedTrophy {
+    pub player: Entity,
+    pub trophy: Entity,
+}
+
+pub struct PlayerTouchedAbility {
+    pub player: Entity,
+    pub orb: Entity,
+}
+
+/// The player started or stopped touching something that hurts. Immunities haven't been checked
+/// yet.
+pub struct PlayerTouchedHazard {
+    pub player: Entity,
+    pub hazard: Entity,
+    pub source: DamageSource,
+    /// Where the hazard is, the player is knocked away from it
+    pub origin: Vec3,
+    pub contact: Contact,
+}
+
+/// Sent for everything the player starts touching, alongside the more specific events
+pub struct PlayerTouched {
+    pub player: Entity,
+    pub other: Entity,
+}
+
+pub struct ProjectileHitEnemy {
+    pub projectile: Entity,
+    pub enemy: Entity,
+}
+
+/// A projectile hit anything, including terrain. Sent alongside `ProjectileHitEnemy`.
+pub struct ProjectileHit {
+    pub projectile: Entity,
+    pub target: Entity,
+}
+
+/// Something started or stopped being inside a fan's air current
+pub struct ForceAreaContact {
+    pub area: Entity,
+    pub body: Entity,
+    pub contact: Contact,
+}
+
+fn contact(event: &CollisionEvent) -> (Entity, Entity, Contact) {
+    let (e1, e2) = event.rigid_body_entities();
+    let contact = if event.is_started() {
+        Contact::Started
+    } else {
+        Contact::Stopped
+    };
+    (e1, e2, contact)
+}
+
+/// Orders a pair so the entity matching `is_first` comes first
+fn order(e1: Entity, e2: Entity, is_first: impl Fn(Entity) -> bool) -> Option<(Entity, Entity)> {
+    if is_first(e1) {
+        Some((e1, e2))
+    } else if is_first(e2) {
+        Some((e2, e1))
+    } else {
+        None
+    }
+}
+
+pub fn dispatch_player(
+    mut collisions: EventReader<CollisionEvent>,
+    players: Query<(), With<Player>>,
+    others: Query<(
+        Option<&ProximityText>,
+        Option<&Checkpoint>,
+        Option<&Trophy>,
+        Option<&Ability>,
+        Option<&Hurtbox>,
+        Option<&Spike>,
+        Option<&Lava>,
+        Option<&Water>,
+        Option<&ImpFireball>,
+        &GlobalTransform,
+    )>,
+    mut proximity: EventWriter<PlayerProximity>,
+    mut entered_checkpoint: EventWriter<PlayerEnteredCheckpoint>,
+    mut left_checkpoint: EventWriter<PlayerLeftCheckpoint>,
+    mut reached_trophy: EventWriter<PlayerReachedTrophy>,
+    mut touched_ability: EventWriter<PlayerTouchedAbility>,
+    mut touched_hazard: EventWriter<PlayerTouchedHazard>,
+    mut touched: EventWriter<PlayerTouched>,
+) {
+    for event in collisions.iter() {
+        let (e1, e2, contact) = contact(event);
+        let (player, other) = match order(e1, e2, |entity| players.contains(entity)) {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let (text, checkpoint, trophy, ability, hurtbox, spike, lava, water, fire, transform) =
+            match others.get(other) {
+                Ok(other) => other,
+                Err(_) => continue,
+            };
+        if text.is_some() {
+            proximity.send(PlayerProximity {
+                player,
+                target: other,
+                contact,
+            });
+        }
+        if checkpoint.is_some() {
+            match contact {
+                Contact::Started => entered_checkpoint.send(PlayerEnteredCheckpoint {
+                    player,
+                    checkpoint: other,
+                }),
+                Contact::Stopped => left_checkpoint.send(PlayerLeftCheckpoint {
+                    player,
+                    checkpoint: other,
+                }),
+            }
+        }
+        let source = if fire.is_some() {
+            Some(DamageSource::Fire)
+        } else if lava.is_some() {
+            Some(DamageSource::Lava)
+        } else if water.is_some() {
+            Some(DamageSource::Water)
+        } else if spike.is_some() && hurtbox.is_some() {
+            Some(DamageSource::Spikes)
+        } else if hurtbox.is_some() {
+            Some(DamageSource::Enemy)
+        } else {
+            None
+        };
+        if let Some(source) = source {
+            touched_hazard.send(PlayerTouchedHazard {
+                player,
+                hazard: other,
+                source,
+                origin: transform.translation(),
+                contact,
+            });
+        }
+        if contact == Contact::Stopped {
+            continue;
+        }
+        touched.send(PlayerTouched { player, other });
+        if trophy.is_some() {
+            reached_trophy.send(PlayerReachedTrophy {
+                player,
+                trophy: other,
+            });
+        }
+        if ability.is_some() {
+            touched_ability.send(PlayerTouchedAbility { player, orb: other });
+        }
+    }
+}
+
+pub fn dispatch_projectiles(
+    mut collisions: EventReader<CollisionEvent>,
+    projectiles: Query<
+        (),
+        Or<(
+            With<FireProjectile>,
+            With<WindProjectile>,
+            With<WaterProjectile>,
+            With<ImpFireball>,
+        )>,
+    >,
+    enemies: Query<(), With<Enemy>>,
+    mut hit_enemy: EventWriter<ProjectileHitEnemy>,
+    mut hit: EventWriter<ProjectileHit>,
+) {
+    for event in collisions.iter().filter(|e| e.is_started()) {
+        let (e1, e2) = event.rigid_body_entities();
+        let (projectile, target) = match order(e1, e2, |entity| projectiles.contains(entity)) {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if enemies.contains(target) {
+            hit_enemy.send(ProjectileHitEnemy {
+                projectile,
+                enemy: target,
+            });
+        }
+        hit.send(ProjectileHit { projectile, target });
+    }
+}
+
+pub fn dispatch_force_areas(
+    mut collisions: EventReader<CollisionEvent>,
+    areas: Query<(), With<ForceArea>>,
+    mut force_area_contacts: EventWriter<ForceAreaContact>,
+) {
+    for event in collisions.iter() {
+        let (e1, e2, contact) = contact(event);
+        if let Some((area, body)) = order(e1, e2, |entity| areas.contains(entity)) {
+            force_area_contacts.send(ForceAreaContact {
+                area,
+                body,
+                contact,
+            });
+        }
+    }
+}
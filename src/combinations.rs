@@ -0,0 +1,215 @@
+use bevy::{
+    asset::{AddAsset, AssetLoader, Assets, LoadContext, LoadedAsset},
+    prelude::{Commands, Plugin, Res},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::{
+    abilities::{Element, Equipment},
+    input::{Action, KeyBindings},
+    state::load_game::GameAssets,
+};
+
+/// Loads what each `(Equipment, Element)` pair does from `assets/combinations.combos.ron`
+pub struct CombinationsPlugin;
+
+impl Plugin for CombinationsPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_asset::<Combinations>()
+            .init_asset_loader::<CombinationsLoader>();
+    }
+}
+
+/// Something in the level that hurts the player
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Hazard {
+    Lava,
+    Water,
+    Wind,
+    Spikes,
+    /// Fireballs thrown by enemies
+    Fire,
+}
+
+/// Each kind spawns a different projectile entity with its own collision behaviour
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProjectileKind {
+    Fireball,
+    Gust,
+    Droplet,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Projectile {
+    pub kind: ProjectileKind,
+    pub speed: f32,
+    /// Seconds until the projectile is despawned. Lives until it hits something if not set.
+    #[serde(default)]
+    pub lifetime: Option<f32>,
+}
+
+/// A temporary stone platform raised in front of the player
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Platform {
+    /// Width in tiles
+    pub width: u8,
+    /// Seconds until the platform crumbles
+    pub lifetime: f32,
+}
+
+/// What a passive aura does to things around the player
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AuraKind {
+    /// Lights torches in range and burns flamable blocks on contact
+    Ignite,
+    /// Cools lava in range to stone
+    Extinguish,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Aura {
+    pub kind: AuraKind,
+    pub radius: f32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Movement {
+    pub speed_multiplier: f32,
+    pub jump_multiplier: f32,
+    /// Jumping spawns an explosion
+    pub explosive_jump: bool,
+    pub double_jump: bool,
+    /// Jumping in the air slams the player down, smashing blocks underneath
+    pub ground_pound: bool,
+    /// Caps how fast the player can fall
+    pub max_fall_speed: Option<f32>,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            speed_multiplier: 1.0,
+            jump_multiplier: 1.0,
+            explosive_jump: false,
+            double_jump: false,
+            ground_pound: false,
+            max_fall_speed: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Combination {
+    pub equipment: Equipment,
+    pub element: Element,
+    pub description: String,
+    #[serde(default)]
+    pub projectile: Option<Projectile>,
+    #[serde(default)]
+    pub platform: Option<Platform>,
+    #[serde(default)]
+    pub aura: Option<Aura>,
+    #[serde(default)]
+    pub movement: Movement,
+    #[serde(default)]
+    pub immunities: Vec<Hazard>,
+}
+
+/// Loaded as an asset, then inserted as a resource when the game starts
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "5b5a4c1e-3c1f-4b8e-9f0d-6a2e8c3d7b41"]
+pub struct Combinations {
+    pub combinations: Vec<Combination>,
+}
+
+impl Combinations {
+    pub fn get(
+        &self,
+        (equipment, element): (Option<Equipment>, Option<Element>),
+    ) -> Option<&Combination> {
+        self.combinations.iter().find(|combination| {
+            Some(combination.equipment) == equipment && Some(combination.element) == element
+        })
+    }
+
+    /// What the combination does, with `{ability}` replaced by the keys bound to cast it
+    pub fn description(
+        &self,
+        combination: (Option<Equipment>, Option<Element>),
+        key_bindings: &KeyBindings,
+    ) -> String {
+        self.get(combination)
+            .map(|combination| {
+                combination
+                    .description
+                    .replace("{ability}", &key_bindings.describe(Action::Ability))
+            })
+            .unwrap_or_else(|| "No effect".to_string())
+    }
+
+    pub fn projectile(
+        &self,
+        combination: (Option<Equipment>, Option<Element>),
+    ) -> Option<&Projectile> {
+        self.get(combination)?.projectile.as_ref()
+    }
+
+    pub fn platform(&self, combination: (Option<Equipment>, Option<Element>)) -> Option<Platform> {
+        self.get(combination)?.platform
+    }
+
+    pub fn aura(&self, combination: (Option<Equipment>, Option<Element>)) -> Option<Aura> {
+        self.get(combination)?.aura
+    }
+
+    pub fn movement(&self, combination: (Option<Equipment>, Option<Element>)) -> Movement {
+        self.get(combination)
+            .map(|combination| combination.movement)
+            .unwrap_or_default()
+    }
+
+    pub fn is_immune(
+        &self,
+        combination: (Option<Equipment>, Option<Element>),
+        hazard: Hazard,
+    ) -> bool {
+        self.get(combination)
+            .map(|combination| combination.immunities.contains(&hazard))
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Default)]
+pub struct CombinationsLoader;
+
+impl AssetLoader for CombinationsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let combinations = ron::de::from_bytes::<Combinations>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(combinations));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["combos.ron"]
+    }
+}
+
+/// Copies the loaded registry into a resource so systems don't have to look up the asset
+pub fn insert_resource(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    combinations: Res<Assets<Combinations>>,
+) {
+    if let Some(combinations) = combinations.get(&game_assets.combinations) {
+        commands.insert_resource(combinations.clone());
+    }
+}
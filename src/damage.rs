@@ -1,67 +1,297 @@
 use bevy::{
     prelude::{
-        Added, Commands, Component, Entity, EventReader, Handle, Query, Res, ResMut, Transform,
-        With,
+        Added, Assets, Commands, Component, Entity, EventReader, EventWriter, GlobalTransform,
+        Handle, Local, Query, Res, ResMut, SystemLabel, Transform, Vec3, With, Without,
     },
+    sprite::TextureAtlasSprite,
     time::{Time, Timer},
 };
 use bevy_ecs_ldtk::{LdtkLevel, LevelSelection, Respawn};
 use bevy_kira_audio::{AudioChannel, AudioControl};
-use heron::{CollisionEvent, Velocity};
+use heron::Velocity;
 
 use crate::{
     audio::{AudioAssets, SoundChannel},
+    collision::{Contact, PlayerTouchedHazard},
+    combinations::{Combinations, Hazard},
     debug::DebugSettings,
-    entity::player::{AnimationState, Player},
+    entity::{
+        fields::Fields,
+        player::{AnimationState, Player},
+    },
+    events::GameEvent,
     input::Controllable,
     physics::GroundDetector,
 };
 
+/// Health the player starts with unless playing with one hit kills
+pub const PLAYER_HEALTH: u32 = 3;
+const INVULNERABLE_SECONDS: f32 = 1.0;
+const KNOCKBACK: Vec3 = Vec3::new(120.0, 150.0, 0.0);
+
+#[derive(SystemLabel)]
+pub enum DamageLabel {
+    /// Systems that send `DamageEvent`s
+    Detect,
+}
+
 #[derive(Component, Default)]
 pub struct Hurtbox;
 
+/// Holds what killed the player
 #[derive(Component)]
-pub struct Killed;
+pub struct Killed(pub DamageSource);
 
 #[derive(Component)]
 pub struct RespawnTimer(Timer);
 
+/// Only added to the player when not playing with one hit kills
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// Damage is ignored and the sprite flashes until the timer runs out
+#[derive(Component)]
+pub struct Invulnerable(Timer);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageSource {
+    Spikes,
+    Enemy,
+    Lava,
+    Water,
+    Fire,
+}
+
+impl DamageSource {
+    /// Falling into lava or water always kills, the player couldn't get out otherwise
+    pub fn is_lethal(&self) -> bool {
+        matches!(self, Self::Lava | Self::Water)
+    }
+
+    /// The hazard an equipment combination can be immune to, enemies always hurt
+    pub fn hazard(&self) -> Option<Hazard> {
+        match self {
+            Self::Spikes => Some(Hazard::Spikes),
+            Self::Lava => Some(Hazard::Lava),
+            Self::Water => Some(Hazard::Water),
+            Self::Fire => Some(Hazard::Fire),
+            Self::Enemy => None,
+        }
+    }
+}
+
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: DamageSource,
+    pub amount: u32,
+    /// Where the damage came from, the target is knocked away from this point
+    pub origin: Vec3,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    #[default]
+    Normal,
+    /// Any damage kills, as in the original game
+    OneHitKill,
+}
+
+impl Difficulty {
+    /// `--one-hit-kill` only sets the initial value, the main menu can still toggle it
+    pub fn from_args() -> Self {
+        if std::env::args().any(|arg| arg == "--one-hit-kill") {
+            Self::OneHitKill
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+/// Hurts the player when they touch a hazard, and again once they stop being invulnerable if
+/// they're still touching it
 pub fn detect(
+    player: Query<(&Player, Option<&Invulnerable>), Without<Killed>>,
+    hazards: Query<(&GlobalTransform, Option<&Hurtbox>)>,
+    mut touched: EventReader<PlayerTouchedHazard>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut touching: Local<Vec<(Entity, Entity, DamageSource)>>,
+    combinations: Res<Combinations>,
+) {
+    let mut hurt = |target: Entity, source: DamageSource, origin: Vec3| {
+        let (player, invulnerable) = match player.get(target) {
+            Ok(player) => player,
+            Err(_) => return,
+        };
+        if invulnerable.is_some() && !source.is_lethal() {
+            return;
+        }
+        if let Some(hazard) = source.hazard() {
+            if combinations.is_immune(player.combination, hazard) {
+                return;
+            }
+        }
+        damage_events.send(DamageEvent {
+            target,
+            source,
+            amount: 1,
+            origin,
+        });
+    };
+
+    // Enemies and spikes stop hurting once they lose their hurtbox
+    touching.retain(|(_, hazard, source)| match hazards.get(*hazard) {
+        Ok((_, hurtbox)) => {
+            hurtbox.is_some() || !matches!(source, DamageSource::Enemy | DamageSource::Spikes)
+        }
+        Err(_) => false,
+    });
+    for (target, hazard, source) in touching.iter() {
+        if let Ok((transform, _)) = hazards.get(*hazard) {
+            hurt(*target, *source, transform.translation());
+        }
+    }
+
+    for event in touched.iter() {
+        let contact = (event.player, event.hazard, event.source);
+        match event.contact {
+            Contact::Started => {
+                if !touching.contains(&contact) {
+                    touching.push(contact);
+                }
+                hurt(event.player, event.source, event.origin);
+            }
+            Contact::Stopped => touching.retain(|touching| *touching != contact),
+        }
+    }
+}
+
+/// Levels can force one hit kills with the `OneHitKill` field
+fn is_one_hit_kill_level(
+    level_selection: &LevelSelection,
+    level_query: &Query<&Handle<LdtkLevel>>,
+    ldtk_levels: &Assets<LdtkLevel>,
+) -> bool {
+    level_query
+        .iter()
+        .filter_map(|handle| ldtk_levels.get(handle))
+        .find(|ldtk_level| match level_selection {
+            LevelSelection::Iid(iid) => &ldtk_level.level.iid == iid,
+            LevelSelection::Identifier(identifier) => &ldtk_level.level.identifier == identifier,
+            LevelSelection::Uid(uid) => &ldtk_level.level.uid == uid,
+            LevelSelection::Index(_) => false,
+        })
+        .and_then(|ldtk_level| {
+            Fields::new(&ldtk_level.level.field_instances).optional("OneHitKill")
+        })
+        .unwrap_or(false)
+}
+
+/// Applies damage to the player, killing them when they run out of health
+pub fn apply(
     mut commands: Commands,
-    hurtboxes: Query<Entity, With<Hurtbox>>,
-    player: Query<Entity, With<Player>>,
-    mut collision_events: EventReader<CollisionEvent>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut player: Query<
+        (
+            &GlobalTransform,
+            &mut Velocity,
+            Option<&mut Health>,
+            Option<&Invulnerable>,
+        ),
+        (With<Player>, Without<Killed>),
+    >,
+    difficulty: Res<Difficulty>,
     debug_settings: Res<DebugSettings>,
+    level_selection: Res<LevelSelection>,
+    level_query: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
 ) {
-    collision_events
-        .iter()
-        .filter(|e| e.is_started())
-        .filter_map(|e| {
-            let (e1, e2) = e.rigid_body_entities();
-            if player.get(e1).is_ok() && hurtboxes.get(e2).is_ok() && !debug_settings.imortality {
-                return Some(e1);
-            } else if player.get(e2).is_ok()
-                && hurtboxes.get(e1).is_ok()
-                && !debug_settings.imortality
-            {
-                return Some(e2);
+    if debug_settings.imortality {
+        damage_events.clear();
+        return;
+    }
+    // Invulnerability is only inserted at the end of the frame, so it can't stop two hits at once
+    let mut hurt = Vec::new();
+    for event in damage_events.iter() {
+        let (transform, mut velocity, health, invulnerable) = match player.get_mut(event.target) {
+            Ok(player) => player,
+            Err(_) => continue,
+        };
+        let already_hurt = invulnerable.is_some() || hurt.contains(&event.target);
+        if already_hurt && !event.source.is_lethal() {
+            continue;
+        }
+        hurt.push(event.target);
+        let one_hit_kill = *difficulty == Difficulty::OneHitKill
+            || event.source.is_lethal()
+            || is_one_hit_kill_level(&level_selection, &level_query, &ldtk_levels);
+        let mut health = match health {
+            Some(health) if !one_hit_kill => health,
+            _ => {
+                commands.entity(event.target).insert(Killed(event.source));
+                continue;
             }
-            None
-        })
-        .for_each(|entity| {
-            commands.entity(entity).insert(Killed {});
-        });
+        };
+        health.current = health.current.saturating_sub(event.amount);
+        if health.current == 0 {
+            commands.entity(event.target).insert(Killed(event.source));
+            continue;
+        }
+        let direction = if transform.translation().x < event.origin.x {
+            -1.0
+        } else {
+            1.0
+        };
+        velocity.linear = Vec3::new(KNOCKBACK.x * direction, KNOCKBACK.y, 0.0);
+        commands
+            .entity(event.target)
+            .insert(Invulnerable(Timer::from_seconds(
+                INVULNERABLE_SECONDS,
+                false,
+            )));
+        sound_channel.play(audio_assets.hurt.clone());
+    }
+}
+
+/// Flashes the sprite while invulnerable
+pub fn invulnerability(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invulnerable, &mut TextureAtlasSprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable, mut sprite) in query.iter_mut() {
+        invulnerable.0.tick(time.delta());
+        if invulnerable.0.finished() {
+            sprite.color.set_a(1.0);
+            commands.entity(entity).remove::<Invulnerable>();
+        } else {
+            let visible = (invulnerable.0.elapsed_secs() * 10.0) as u32 % 2 == 0;
+            sprite.color.set_a(if visible { 1.0 } else { 0.3 });
+        }
+    }
 }
 
 pub fn kill(
     mut commands: Commands,
     mut player: Query<(Entity, &mut AnimationState, &mut Velocity), With<Player>>,
-    killed: Query<Entity, Added<Killed>>,
+    killed: Query<(Entity, &Killed), Added<Killed>>,
+    mut game_events: EventWriter<GameEvent>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for entity in killed.iter() {
+    for (entity, killed) in killed.iter() {
         if let Ok((player_entity, mut state, mut velocity)) = player.get_mut(entity) {
+            game_events.send(GameEvent::Death(killed.0));
             *state = AnimationState::Death;
             velocity.linear.x = 0.0;
             velocity.linear.y = 0.0;
@@ -76,13 +306,19 @@ pub fn kill(
 
 pub fn respawn(
     mut commands: Commands,
-    mut player: Query<(Entity, &mut Transform, &mut RespawnTimer, &Player)>,
+    mut player: Query<(
+        Entity,
+        &mut Transform,
+        &mut RespawnTimer,
+        &Player,
+        Option<&mut Health>,
+    )>,
     level_query: Query<Entity, With<Handle<LdtkLevel>>>,
     mut level_selection: ResMut<LevelSelection>,
     mut detectors: Query<&mut GroundDetector>,
     time: Res<Time>,
 ) {
-    for (entity, mut transform, mut timer, player) in player.iter_mut() {
+    for (entity, mut transform, mut timer, player, health) in player.iter_mut() {
         timer.0.tick(time.delta());
         if timer.0.finished() {
             commands
@@ -90,6 +326,9 @@ pub fn respawn(
                 .remove::<RespawnTimer>()
                 .remove::<Killed>()
                 .insert(Controllable::new());
+            if let Some(mut health) = health {
+                health.current = health.max;
+            }
             transform.translation = player.checkpoint;
             transform.translation.z = 7.0;
             *level_selection = player.checkpoint_level.clone();
@@ -1,10 +1,14 @@
-use bevy::prelude::{Input, KeyCode, Plugin, Res, ResMut, SystemSet};
+use bevy::prelude::{Input, Plugin, Res, ResMut, SystemSet};
 use bevy_inspector_egui::{
     plugin::InspectorWindows, Inspectable, InspectorPlugin, RegisterInspectable,
     WorldInspectorParams, WorldInspectorPlugin,
 };
 
-use crate::{entity::player::Player, input::Controllable, state::State};
+use crate::{
+    entity::player::Player,
+    input::{Action, Controllable},
+    state::State,
+};
 
 pub struct DebugPlugin;
 
@@ -26,14 +30,16 @@ pub struct DebugSettings {
     pub imortality: bool,
     pub unlock_camera: bool,
     pub unlock_all_abilities: bool,
+    /// Record input to `replay.ron` until this is turned off
+    pub record_replay: bool,
 }
 
 fn toggle_inspector(
-    input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     mut world_inspector_params: ResMut<WorldInspectorParams>,
     mut inspector_windows: ResMut<InspectorWindows>,
 ) {
-    if input.just_pressed(KeyCode::F1) {
+    if actions.just_pressed(Action::OpenInspector) {
         world_inspector_params.enabled = !world_inspector_params.enabled;
         let mut inspector_window_data = inspector_windows.window_data_mut::<DebugSettings>();
         inspector_window_data.visible = !inspector_window_data.visible;
@@ -1,35 +1,43 @@
 use std::fmt::Display;
 
 use bevy::{
+    ecs::system::EntityCommands,
     prelude::{
-        Added, AssetServer, Assets, Bundle, Commands, Component, Entity, EventReader, Handle,
-        Image, Query, Res,
+        Added, AssetServer, Assets, Bundle, Commands, Component, Entity, EventReader, EventWriter,
+        Handle, Image, Query, Res, Transform, Vec2,
     },
     sprite::{SpriteSheetBundle, TextureAtlas},
 };
 use bevy_ecs_ldtk::{
-    prelude::{FieldValue, LayerInstance, LdtkEntity, TilesetDefinition},
+    prelude::{LayerInstance, LdtkEntity, TilesetDefinition},
     EntityInstance,
 };
 use bevy_inspector_egui::Inspectable;
 use bevy_kira_audio::{AudioChannel, AudioControl};
-use heron::CollisionEvent;
+use serde::Deserialize;
 
 use crate::{
     animation::Animated,
     audio::{AudioAssets, SoundChannel},
+    collision::PlayerTouchedAbility,
     physics::PhysicsObjectBundle,
+    speedrun::SpeedrunEvent,
 };
 
-use super::player::Player;
+use super::{
+    fields::{FieldErrors, Fields, LdtkEnum},
+    player::Player,
+};
 
-#[derive(Inspectable, Component, PartialEq, Eq, Clone, Copy)]
+#[derive(Inspectable, Component, PartialEq, Eq, Clone, Copy, Debug, Deserialize)]
 pub enum Ability {
     Fire,
     Air,
     Water,
+    Earth,
     MagicBoots,
     Cloak,
+    Amulet,
 }
 
 impl Display for Ability {
@@ -38,8 +46,10 @@ impl Display for Ability {
             Ability::Fire => write!(f, "Fire"),
             Ability::Air => write!(f, "Air"),
             Ability::Water => write!(f, "Water"),
+            Ability::Earth => write!(f, "Earth"),
             Ability::MagicBoots => write!(f, "Magic Boots"),
             Ability::Cloak => write!(f, "Cloak of Resistance"),
+            Ability::Amulet => write!(f, "Amulet"),
         }
     }
 }
@@ -53,33 +63,36 @@ impl LdtkEntity for Ability {
         _: &AssetServer,
         _: &mut Assets<TextureAtlas>,
     ) -> Ability {
-        if let Some(ldtk_ability) = entity_instance
-            .field_instances
-            .iter()
-            .find(|f| f.identifier == *"Ability")
-        {
-            if let FieldValue::Enum(Some(ability)) = &ldtk_ability.value {
-                match ability.as_str() {
-                    "Fire" => Ability::Fire,
-                    "Air" => Ability::Air,
-                    "Water" => Ability::Water,
-                    "Boots" => Ability::MagicBoots,
-                    "Cloak" => Ability::Cloak,
-                    _ => panic!("Unknown ability enum variant: {}", ability),
-                }
-            } else {
-                panic!("Ability entity has no assigned ability");
-            }
-        } else {
-            panic!("Ability entity has no assigned ability");
+        // Orbs with a bad `Ability` field are replaced by `fields::report_invalid`
+        ability(&Fields::new(&entity_instance.field_instances))
+    }
+}
+
+impl LdtkEnum for Ability {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "Fire" => Some(Ability::Fire),
+            "Air" => Some(Ability::Air),
+            "Water" => Some(Ability::Water),
+            "Earth" => Some(Ability::Earth),
+            "Boots" => Some(Ability::MagicBoots),
+            "Cloak" => Some(Ability::Cloak),
+            "Amulet" => Some(Ability::Amulet),
+            _ => None,
         }
     }
 }
 
+pub fn ability(fields: &Fields) -> Ability {
+    fields.required("Ability").unwrap_or(Ability::Fire)
+}
+
 #[derive(Bundle, LdtkEntity)]
 pub struct AbilityBundle {
     #[ldtk_entity]
     ability: Ability,
+    #[from_entity_instance]
+    field_errors: FieldErrors,
     #[bundle]
     #[sprite_sheet_bundle("sprites/orb.png", 8.0, 8.0, 6, 1, 0.0, 0.0, 0)]
     sprite_sheet_bundle: SpriteSheetBundle,
@@ -90,43 +103,49 @@ pub struct AbilityBundle {
     pub physics_bundle: PhysicsObjectBundle,
 }
 
+/// Makes an orb that wasn't placed in LDtk, like the reward for defeating a boss
+pub fn insert_orb(
+    entity: &mut EntityCommands,
+    ability: Ability,
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    transform: Transform,
+) {
+    // Built the same way as orbs from the level
+    let entity_instance = EntityInstance {
+        identifier: "Ability".into(),
+        ..Default::default()
+    };
+    let texture_atlas =
+        TextureAtlas::from_grid(asset_server.load("sprites/orb.png"), Vec2::splat(8.0), 6, 1);
+    entity
+        .insert(ability)
+        .insert_bundle(SpriteSheetBundle {
+            texture_atlas: texture_atlases.add(texture_atlas),
+            transform,
+            ..Default::default()
+        })
+        .insert(Animated::from(entity_instance.clone()))
+        .insert_bundle(PhysicsObjectBundle::from(entity_instance));
+}
+
 pub fn check_near(
     mut commands: Commands,
     ability_orbs: Query<&Ability>,
-    mut player: Query<(Entity, &mut Player)>,
-    mut collisions: EventReader<CollisionEvent>,
+    mut player: Query<&mut Player>,
+    mut touched: EventReader<PlayerTouchedAbility>,
+    mut speedrun_events: EventWriter<SpeedrunEvent>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for (player_entity, mut player) in player.iter_mut() {
-        for collision in collisions.iter() {
-            if let CollisionEvent::Started(a, b) = collision {
-                if a.rigid_body_entity() == player_entity {
-                    if let Ok(ability) = ability_orbs.get(b.rigid_body_entity()) {
-                        match ability {
-                            Ability::Fire => player.unlocked_fire = true,
-                            Ability::Air => player.unlocked_air = true,
-                            Ability::MagicBoots => player.unlocked_boots = true,
-                            Ability::Water => player.unlocked_water = true,
-                            Ability::Cloak => player.unlocked_cloak = true,
-                        }
-                        commands.entity(b.rigid_body_entity()).despawn();
-                        sound_channel.play(audio_assets.collect.clone());
-                    }
-                } else if b.rigid_body_entity() == player_entity {
-                    if let Ok(ability) = ability_orbs.get(a.rigid_body_entity()) {
-                        match ability {
-                            Ability::Fire => player.unlocked_fire = true,
-                            Ability::Air => player.unlocked_air = true,
-                            Ability::MagicBoots => player.unlocked_boots = true,
-                            Ability::Water => player.unlocked_water = true,
-                            Ability::Cloak => player.unlocked_cloak = true,
-                        }
-                        commands.entity(a.rigid_body_entity()).despawn();
-                        sound_channel.play(audio_assets.collect.clone());
-                    }
-                }
-            }
+    for event in touched.iter() {
+        if let (Ok(ability), Ok(mut player)) =
+            (ability_orbs.get(event.orb), player.get_mut(event.player))
+        {
+            player.unlock(*ability);
+            commands.entity(event.orb).despawn();
+            speedrun_events.send(SpeedrunEvent::Split(ability.to_string()));
+            sound_channel.play(audio_assets.collect.clone());
         }
     }
 }
@@ -138,32 +157,8 @@ pub fn dont_spawn_if_collected(
 ) {
     for player in player.iter() {
         for (entity, ability) in query.iter() {
-            match ability {
-                Ability::Fire => {
-                    if player.unlocked_fire {
-                        commands.entity(entity).despawn();
-                    }
-                }
-                Ability::Air => {
-                    if player.unlocked_air {
-                        commands.entity(entity).despawn();
-                    }
-                }
-                Ability::Water => {
-                    if player.unlocked_water {
-                        commands.entity(entity).despawn();
-                    }
-                }
-                Ability::MagicBoots => {
-                    if player.unlocked_boots {
-                        commands.entity(entity).despawn();
-                    }
-                }
-                Ability::Cloak => {
-                    if player.unlocked_cloak {
-                        commands.entity(entity).despawn();
-                    }
-                }
+            if player.has_unlocked(*ability) {
+                commands.entity(entity).despawn();
             }
         }
     }
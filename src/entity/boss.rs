@@ -0,0 +1,84 @@
+use bevy::{
+    prelude::{Added, Bundle, Color, Component, Query, Vec2},
+    sprite::{SpriteSheetBundle, TextureAtlasSprite},
+};
+use bevy_ecs_ldtk::{EntityInstance, LdtkEntity};
+
+use crate::{animation::Animated, damage::Hurtbox, physics::PhysicsObjectBundle};
+
+use super::{
+    fields::{FieldErrors, Fields},
+    goblin::{AnimationState, Enemy},
+};
+
+/// Linked to its arena by the `Boss` field, the fight itself is run by `crate::boss`
+#[derive(Component)]
+pub struct Boss {
+    pub id: String,
+}
+
+impl From<EntityInstance> for Boss {
+    fn from(entity_instance: EntityInstance) -> Self {
+        Self {
+            id: boss_id(&Fields::new(&entity_instance.field_instances)),
+        }
+    }
+}
+
+/// The `Boss` field of bosses and arenas
+pub fn boss_id(fields: &Fields) -> String {
+    fields.required("Boss").unwrap_or_default()
+}
+
+/// A region that locks the camera and seals its sides while its boss is being fought
+#[derive(Component)]
+pub struct BossArena {
+    pub boss: String,
+    pub size: Vec2,
+}
+
+impl From<EntityInstance> for BossArena {
+    fn from(entity_instance: EntityInstance) -> Self {
+        Self {
+            boss: boss_id(&Fields::new(&entity_instance.field_instances)),
+            size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct BossBundle {
+    #[bundle]
+    #[sprite_sheet_bundle("sprites/goblin_spritesheet.png", 16.0, 16.0, 6, 5, 0.0, 0.0, 0)]
+    pub sprite_sheet_bundle: SpriteSheetBundle,
+    pub enemy: Enemy,
+    pub hurtbox: Hurtbox,
+    #[bundle]
+    #[from_entity_instance]
+    pub physics_bundle: PhysicsObjectBundle,
+    #[from_entity_instance]
+    pub animated: Animated,
+    pub state: AnimationState,
+    #[from_entity_instance]
+    pub boss: Boss,
+    #[from_entity_instance]
+    pub field_errors: FieldErrors,
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct BossArenaBundle {
+    #[bundle]
+    #[from_entity_instance]
+    pub physics_bundle: PhysicsObjectBundle,
+    #[from_entity_instance]
+    pub arena: BossArena,
+    #[from_entity_instance]
+    pub field_errors: FieldErrors,
+}
+
+pub fn grow(mut query: Query<&mut TextureAtlasSprite, Added<Boss>>) {
+    for mut sprite in query.iter_mut() {
+        sprite.custom_size = Some(Vec2::splat(32.0));
+        sprite.color = Color::rgb(0.8, 0.6, 1.0);
+    }
+}
@@ -1,21 +1,22 @@
 use bevy::{
     prelude::{
-        Added, Bundle, Children, Component, Entity, EventReader, GlobalTransform, Query, Res,
-        Transform, Visibility, With,
+        Added, Bundle, Component, EventReader, EventWriter, GlobalTransform, Query, Res, Transform,
+        With,
     },
     sprite::SpriteSheetBundle,
 };
 use bevy_ecs_ldtk::{LdtkEntity, LevelSelection};
 use bevy_kira_audio::{AudioChannel, AudioControl};
-use heron::CollisionEvent;
 
 use crate::{
     animation::Animated,
     audio::{AudioAssets, SoundChannel},
+    collision::{PlayerEnteredCheckpoint, PlayerLeftCheckpoint},
     physics::PhysicsObjectBundle,
+    save::SaveEvent,
 };
 
-use super::{player::Player, signpost::TextBox, ProximityText};
+use super::{player::Player, ProximityText};
 
 #[derive(Component, Default)]
 pub struct Checkpoint;
@@ -35,75 +36,42 @@ pub struct CheckpointBundle {
     pub physics_bundle: PhysicsObjectBundle,
 }
 
+/// Where a new game starts in a world. Optional, the Player entity's position is used without one.
+#[derive(Component, Default)]
+pub struct StartCheckpoint;
+
+#[derive(Bundle, LdtkEntity)]
+pub struct StartCheckpointBundle {
+    start_checkpoint: StartCheckpoint,
+}
+
 pub fn check_near(
-    checkpoints: Query<(&GlobalTransform, &Children), (With<ProximityText>, With<Checkpoint>)>,
-    mut player: Query<(Entity, &mut Player)>,
-    mut text: Query<&mut Visibility, With<TextBox>>,
-    mut collisions: EventReader<CollisionEvent>,
+    checkpoints: Query<&GlobalTransform, With<Checkpoint>>,
+    mut player: Query<&mut Player>,
+    mut entered: EventReader<PlayerEnteredCheckpoint>,
+    mut left: EventReader<PlayerLeftCheckpoint>,
+    mut save_events: EventWriter<SaveEvent>,
     level_selection: Res<LevelSelection>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for (player_entity, mut player) in player.iter_mut() {
-        for collision in collisions.iter() {
-            match collision {
-                CollisionEvent::Started(a, b) => {
-                    if a.rigid_body_entity() == player_entity {
-                        if let Ok((transform, children)) = checkpoints.get(b.rigid_body_entity()) {
-                            // show text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = true;
-                                }
-                            }
-                            // set checkpoint
-                            player.checkpoint = transform.translation();
-                            player.checkpoint.y += 3.0;
-                            player.checkpoint_level = level_selection.clone();
-                            player.near_checkpoint = true;
-                            sound_channel.play(audio_assets.ping.clone());
-                        }
-                    } else if b.rigid_body_entity() == player_entity {
-                        if let Ok((transform, children)) = checkpoints.get(a.rigid_body_entity()) {
-                            // show text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = true;
-                                }
-                            }
-                            // set checkpoint
-                            player.checkpoint = transform.translation();
-                            player.checkpoint.y += 3.0;
-                            player.checkpoint_level = level_selection.clone();
-                            player.near_checkpoint = true;
-                            sound_channel.play(audio_assets.ping.clone());
-                        }
-                    }
-                }
-                CollisionEvent::Stopped(a, b) => {
-                    if a.rigid_body_entity() == player_entity {
-                        if let Ok((_, children)) = checkpoints.get(b.rigid_body_entity()) {
-                            // hide text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = false;
-                                }
-                            }
-                            player.near_checkpoint = false;
-                        }
-                    } else if b.rigid_body_entity() == player_entity {
-                        if let Ok((_, children)) = checkpoints.get(a.rigid_body_entity()) {
-                            // hide text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = false;
-                                }
-                            }
-                            player.near_checkpoint = false;
-                        }
-                    }
-                }
-            }
+    for event in entered.iter() {
+        if let (Ok(transform), Ok(mut player)) = (
+            checkpoints.get(event.checkpoint),
+            player.get_mut(event.player),
+        ) {
+            // set checkpoint
+            player.checkpoint = transform.translation();
+            player.checkpoint.y += 3.0;
+            player.checkpoint_level = level_selection.clone();
+            player.near_checkpoint = true;
+            save_events.send(SaveEvent);
+            sound_channel.play(audio_assets.ping.clone());
+        }
+    }
+    for event in left.iter() {
+        if let Ok(mut player) = player.get_mut(event.player) {
+            player.near_checkpoint = false;
         }
     }
 }
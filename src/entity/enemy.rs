@@ -0,0 +1,306 @@
+use bevy::{
+    prelude::{
+        Color, Commands, Component, DespawnRecursiveExt, Entity, EventReader, EventWriter, Query,
+        Res, With, Without,
+    },
+    sprite::TextureAtlasSprite,
+    time::{Time, Timer},
+};
+use bevy_ecs_ldtk::EntityInstance;
+use bevy_kira_audio::{AudioChannel, AudioControl};
+use heron::{RigidBody, Velocity};
+
+use crate::{
+    abilities::{Element, FireProjectile, WaterProjectile, WindProjectile},
+    audio::{AudioAssets, SoundChannel},
+    collision::ProjectileHitEnemy,
+    damage::Hurtbox,
+    destruction::DestructionTimer,
+    events::GameEvent,
+};
+
+use super::{
+    fields::Fields,
+    fire_imp::Deflected,
+    goblin::{AnimationState, Patrol},
+};
+
+/// Health taken by a fireball before multipliers
+const FIREBALL_DAMAGE: f32 = 1.0;
+/// Health a droplet takes from enemies immune to fire, before the water multiplier
+const DOUSE_DAMAGE: f32 = 1.0;
+/// Speed a gust knocks an enemy back at before multipliers
+const KNOCKBACK_SPEED: f32 = 120.0;
+/// Fraction of its speed a droplet takes away before multipliers
+const SLOW: f32 = 0.5;
+const SLOW_SECONDS: f32 = 3.0;
+/// Seconds an enemy flashes, and stands still apart from any knockback, after being hit
+const FLASH_SECONDS: f32 = 0.2;
+const FLASH_COLOR: Color = Color::rgb(1.0, 0.3, 0.3);
+
+/// Health and how strongly each element affects an enemy, 0 makes it immune
+#[derive(Component, Clone, Debug)]
+pub struct EnemyStats {
+    pub health: f32,
+    pub max_health: f32,
+    pub fire: f32,
+    pub air: f32,
+    pub water: f32,
+    /// Running while the enemy flashes from a hit
+    pub flash: Option<HitFlash>,
+}
+
+impl Default for EnemyStats {
+    fn default() -> Self {
+        Self {
+            health: 2.0,
+            max_health: 2.0,
+            fire: 1.0,
+            air: 1.0,
+            water: 1.0,
+            flash: None,
+        }
+    }
+}
+
+impl From<EntityInstance> for EnemyStats {
+    fn from(entity_instance: EntityInstance) -> Self {
+        stats(
+            &Fields::new(&entity_instance.field_instances),
+            EnemyStats::base(&entity_instance.identifier),
+        )
+    }
+}
+
+/// The optional `Health`, `FireMultiplier`, `AirMultiplier` and `WaterMultiplier` fields of
+/// enemies, falling back to `default`
+pub fn stats(fields: &Fields, default: EnemyStats) -> EnemyStats {
+    let health = fields
+        .optional_if("Health", |health: &f32| *health > 0.0)
+        .unwrap_or(default.health);
+    let multiplier = |name| fields.optional_if(name, |multiplier: &f32| *multiplier >= 0.0);
+    EnemyStats {
+        health,
+        max_health: health,
+        fire: multiplier("FireMultiplier").unwrap_or(default.fire),
+        air: multiplier("AirMultiplier").unwrap_or(default.air),
+        water: multiplier("WaterMultiplier").unwrap_or(default.water),
+        flash: None,
+    }
+}
+
+impl EnemyStats {
+    /// Stats of an enemy before its fields are read
+    pub fn base(identifier: &str) -> Self {
+        match identifier {
+            // Made of fire and rooted to the spot, only water and their own fireballs hurt them
+            "FireImp" => Self {
+                health: 1.0,
+                max_health: 1.0,
+                fire: 0.0,
+                air: 0.0,
+                ..Default::default()
+            },
+            // Blown about by gusts, but water passes straight through
+            "Wisp" => Self {
+                health: 1.0,
+                max_health: 1.0,
+                water: 0.0,
+                ..Default::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    pub fn multiplier(&self, element: Element) -> f32 {
+        match element {
+            Element::Fire => self.fire,
+            Element::Air => self.air,
+            Element::Water => self.water,
+            // Nothing earthen is thrown at enemies
+            Element::Earth => 0.0,
+        }
+    }
+
+    /// Takes health from a fireball
+    pub fn burn(&mut self) {
+        self.health -= FIREBALL_DAMAGE * self.fire;
+    }
+
+    /// An imp's fireball blown back burns even enemies immune to fire, like the imp itself
+    pub fn burn_deflected(&mut self) {
+        self.health -= FIREBALL_DAMAGE * self.fire.max(1.0);
+    }
+
+    /// Water puts out enemies immune to fire, others are only slowed
+    pub fn douse(&mut self) {
+        if self.fire <= 0.0 {
+            self.health -= DOUSE_DAMAGE * self.water;
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// Speed left while slowed by water, 1 if unaffected
+    pub fn slowed_speed(&self) -> f32 {
+        (1.0 - SLOW * self.water).clamp(0.0, 1.0)
+    }
+
+    /// Enemies stand still apart from any knockback while flashing
+    pub fn is_flashing(&self) -> bool {
+        self.flash.is_some()
+    }
+}
+
+/// Stands in for the enemy's sprite colour while it flashes
+#[derive(Clone, Debug)]
+pub struct HitFlash {
+    pub timer: Timer,
+    pub color: Color,
+}
+
+/// Scales how fast an enemy moves until the timer runs out
+#[derive(Component)]
+pub struct Slowed {
+    pub timer: Timer,
+    pub speed: f32,
+}
+
+/// Speed multiplier for an enemy that may be slowed
+pub fn speed(slowed: Option<&Slowed>) -> f32 {
+    slowed.map_or(1.0, |slowed| slowed.speed)
+}
+
+pub fn take_hits(
+    mut commands: Commands,
+    mut enemies: Query<
+        (&mut EnemyStats, &mut Velocity, &mut TextureAtlasSprite),
+        (Without<FireProjectile>, Without<WindProjectile>),
+    >,
+    fireballs: Query<(), With<FireProjectile>>,
+    deflected: Query<(), With<Deflected>>,
+    gusts: Query<&Velocity, With<WindProjectile>>,
+    droplets: Query<(), With<WaterProjectile>>,
+    mut hits: EventReader<ProjectileHitEnemy>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for hit in hits.iter() {
+        let (mut stats, mut velocity, mut sprite) = match enemies.get_mut(hit.enemy) {
+            Ok(enemy) => enemy,
+            Err(_) => continue,
+        };
+        if stats.is_dead() {
+            continue;
+        }
+        let is_deflected = deflected.contains(hit.projectile);
+        let element = if fireballs.contains(hit.projectile) {
+            commands.entity(hit.projectile).despawn_recursive();
+            Element::Fire
+        } else if gusts.contains(hit.projectile) {
+            Element::Air
+        } else if droplets.contains(hit.projectile) {
+            // Droplets despawn themselves on any hit
+            Element::Water
+        } else {
+            continue;
+        };
+        if stats.multiplier(element) <= 0.0 && !is_deflected {
+            continue;
+        }
+        match element {
+            Element::Fire => {
+                if is_deflected {
+                    stats.burn_deflected();
+                } else {
+                    stats.burn();
+                }
+                sound_channel.play(audio_assets.hurt.clone());
+            }
+            Element::Air => {
+                if let Ok(gust) = gusts.get(hit.projectile) {
+                    velocity.linear.x = gust.linear.x.signum() * KNOCKBACK_SPEED * stats.air;
+                }
+                sound_channel.play(audio_assets.air.clone());
+            }
+            _ => {
+                stats.douse();
+                commands.entity(hit.enemy).insert(Slowed {
+                    timer: Timer::from_seconds(SLOW_SECONDS, false),
+                    speed: stats.slowed_speed(),
+                });
+                sound_channel.play(audio_assets.steam.clone());
+            }
+        };
+        // A flash already running keeps the colour from before it
+        let color = stats
+            .flash
+            .as_ref()
+            .map_or(sprite.color, |flash| flash.color);
+        sprite.color = FLASH_COLOR;
+        stats.flash = Some(HitFlash {
+            timer: Timer::from_seconds(FLASH_SECONDS, false),
+            color,
+        });
+    }
+}
+
+/// Ends flashes and slowdowns. Enemies stop after a flash so their AI picks a new direction.
+pub fn recover(
+    mut commands: Commands,
+    mut flashing: Query<(&mut EnemyStats, &mut TextureAtlasSprite, &mut Velocity)>,
+    mut slowed: Query<(Entity, &mut Slowed)>,
+    time: Res<Time>,
+) {
+    for (mut stats, mut sprite, mut velocity) in flashing.iter_mut() {
+        let color = match &mut stats.flash {
+            Some(flash) if flash.timer.tick(time.delta()).finished() => flash.color,
+            _ => continue,
+        };
+        sprite.color = color;
+        velocity.linear.x = 0.0;
+        stats.flash = None;
+    }
+    for (entity, mut slowed) in slowed.iter_mut() {
+        if slowed.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Slowed>();
+        }
+    }
+}
+
+/// Runs the death animation of enemies out of health once they have finished flashing
+pub fn defeat(
+    mut commands: Commands,
+    mut enemies: Query<
+        (
+            Entity,
+            &EnemyStats,
+            Option<&mut AnimationState>,
+            &mut Velocity,
+            Option<&mut Patrol>,
+        ),
+        With<Hurtbox>,
+    >,
+    mut game_events: EventWriter<GameEvent>,
+) {
+    for (entity, stats, state, mut velocity, patrol) in enemies.iter_mut() {
+        if !stats.is_dead() || stats.is_flashing() {
+            continue;
+        }
+        if let Some(mut state) = state {
+            *state = AnimationState::Death;
+        }
+        if let Some(mut patrol) = patrol {
+            patrol.movement_speed = 0.0;
+        }
+        game_events.send(GameEvent::EnemyKilled(entity));
+        velocity.linear.x = 0.0;
+        commands
+            .entity(entity)
+            .remove::<Hurtbox>()
+            .remove::<RigidBody>()
+            .insert(DestructionTimer(Timer::from_seconds(0.6, false)));
+    }
+}
@@ -2,19 +2,24 @@ use std::f32::consts::PI;
 
 use bevy::{
     prelude::{
-        warn, Added, Bundle, Component, Entity, EventReader, Query, Transform, Vec3, Without,
+        warn, Added, Bundle, Component, Entity, EventReader, Query, Res, Transform, Vec3, Without,
     },
     sprite::SpriteSheetBundle,
 };
-use bevy_ecs_ldtk::{prelude::FieldValue, EntityInstance, LdtkEntity};
-use heron::{Acceleration, CollisionEvent};
+use bevy_ecs_ldtk::{EntityInstance, LdtkEntity};
+use heron::Acceleration;
 
 use crate::{
     animation::Animated,
+    collision::{Contact, ForceAreaContact},
+    combinations::{Combinations, Hazard},
     physics::{Direction, Dynamic, PhysicsObjectBundle},
 };
 
-use super::player::Player;
+use super::{
+    fields::{FieldErrors, Fields, LdtkEnum},
+    player::Player,
+};
 
 #[derive(Component)]
 pub struct Fan {
@@ -23,34 +28,37 @@ pub struct Fan {
 impl From<EntityInstance> for Fan {
     fn from(entity_instance: EntityInstance) -> Self {
         match entity_instance.identifier.as_ref() {
-            "Fan" => {
-                let field_instances = &entity_instance.field_instances;
-                if let Some(field_instance) = field_instances
-                    .iter()
-                    .find(|f| f.identifier == *"Direction")
-                {
-                    if let FieldValue::Enum(Some(direction)) = &field_instance.value {
-                        let d = match direction.as_str() {
-                            "North" => Direction::North,
-                            "South" => Direction::South,
-                            "East" => Direction::East,
-                            "West" => Direction::West,
-                            _ => panic!("Fan entity had invalid direction"),
-                        };
-                        return Fan { direction: d };
-                    }
-                }
-                panic!("Fan entity is missing direction parameter")
-            }
+            "Fan" => Fan {
+                direction: direction(&Fields::new(&entity_instance.field_instances)),
+            },
             _ => panic!("Entity should not have Fan component"),
         }
     }
 }
 
+impl LdtkEnum for Direction {
+    fn from_variant(variant: &str) -> Option<Self> {
+        match variant {
+            "North" => Some(Direction::North),
+            "South" => Some(Direction::South),
+            "East" => Some(Direction::East),
+            "West" => Some(Direction::West),
+            _ => None,
+        }
+    }
+}
+
+/// The `Direction` field of fans and air currents
+pub fn direction(fields: &Fields) -> Direction {
+    fields.required("Direction").unwrap_or_default()
+}
+
 #[derive(Bundle, LdtkEntity)]
 pub struct FanBundle {
     #[from_entity_instance]
     fan: Fan,
+    #[from_entity_instance]
+    field_errors: FieldErrors,
     #[bundle]
     #[sprite_sheet_bundle("sprites/fan.png", 16.0, 16.0, 4, 1, 0.0, 0.0, 0)]
     pub sprite_sheet_bundle: SpriteSheetBundle,
@@ -69,28 +77,10 @@ pub struct ForceArea {
 impl From<EntityInstance> for ForceArea {
     fn from(entity_instance: EntityInstance) -> Self {
         match entity_instance.identifier.as_ref() {
-            "AirCurrent" => {
-                let field_instances = &entity_instance.field_instances;
-                if let Some(field_instance) = field_instances
-                    .iter()
-                    .find(|f| f.identifier == *"Direction")
-                {
-                    if let FieldValue::Enum(Some(direction)) = &field_instance.value {
-                        let d = match direction.as_str() {
-                            "North" => Direction::North,
-                            "South" => Direction::South,
-                            "East" => Direction::East,
-                            "West" => Direction::West,
-                            _ => panic!("Fan entity had invalid direction"),
-                        };
-                        return ForceArea {
-                            direction: d,
-                            strength: 1000.0,
-                        };
-                    }
-                }
-                panic!("AirCurrent entity is missing direction parameter")
-            }
+            "AirCurrent" => ForceArea {
+                direction: direction(&Fields::new(&entity_instance.field_instances)),
+                strength: 1000.0,
+            },
             _ => panic!("Entity should not have ForceArea component"),
         }
     }
@@ -104,6 +94,8 @@ pub struct AirCurrentBundle {
     air_current: AirCurrent,
     #[from_entity_instance]
     force_area: ForceArea,
+    #[from_entity_instance]
+    field_errors: FieldErrors,
     #[bundle]
     #[sprite_sheet_bundle("sprites/wind.png", 16.0, 16.0, 5, 1, 0.0, 0.0, 0)]
     pub sprite_sheet_bundle: SpriteSheetBundle,
@@ -117,40 +109,23 @@ pub struct AirCurrentBundle {
 pub fn check_collision(
     mut movables: Query<&mut Dynamic>,
     areas: Query<&ForceArea>,
-    mut collisions: EventReader<CollisionEvent>,
+    mut contacts: EventReader<ForceAreaContact>,
 ) {
-    for collision in collisions.iter() {
-        match collision {
-            CollisionEvent::Started(a, b) => {
-                if let Ok(area) = areas.get(a.rigid_body_entity()) {
-                    if let Ok(mut movable) = movables.get_mut(b.rigid_body_entity()) {
-                        movable.counter += 1;
-                        movable.direction = area.direction;
-                    }
-                } else if let Ok(area) = areas.get(b.rigid_body_entity()) {
-                    if let Ok(mut movable) = movables.get_mut(a.rigid_body_entity()) {
-                        movable.counter += 1;
-                        movable.direction = area.direction;
-                    }
-                }
+    for event in contacts.iter() {
+        let (area, mut movable) = match (areas.get(event.area), movables.get_mut(event.body)) {
+            (Ok(area), Ok(movable)) => (area, movable),
+            _ => continue,
+        };
+        match event.contact {
+            Contact::Started => {
+                movable.counter += 1;
+                movable.direction = area.direction;
             }
-            CollisionEvent::Stopped(a, b) => {
-                if areas.contains(a.rigid_body_entity()) {
-                    if let Ok(mut movable) = movables.get_mut(b.rigid_body_entity()) {
-                        if movable.counter > 0 {
-                            movable.counter -= 1;
-                        } else {
-                            warn!("Dynamic entity attempted to decrement collision counter that was already 0");
-                        }
-                    }
-                } else if areas.contains(b.rigid_body_entity()) {
-                    if let Ok(mut movable) = movables.get_mut(a.rigid_body_entity()) {
-                        if movable.counter > 0 {
-                            movable.counter -= 1;
-                        } else {
-                            warn!("Dynamic entity attempted to decrement collision counter that was already 0");
-                        }
-                    }
+            Contact::Stopped => {
+                if movable.counter > 0 {
+                    movable.counter -= 1;
+                } else {
+                    warn!("Dynamic entity attempted to decrement collision counter that was already 0");
                 }
             }
         }
@@ -160,12 +135,11 @@ pub fn check_collision(
 pub fn apply_force(
     mut movables: Query<(Entity, &mut Acceleration, &Dynamic)>,
     players: Query<&Player>,
+    combinations: Res<Combinations>,
 ) {
     for (entity, mut acceleration, movable) in movables.iter_mut() {
         if let Ok(player) = players.get(entity) {
-            if player.has_equipt(crate::abilities::Equipment::Cloak)
-                && player.has_infused(crate::abilities::Element::Air)
-            {
+            if combinations.is_immune(player.combination, Hazard::Wind) {
                 continue;
             }
         }
@@ -0,0 +1,281 @@
+use std::{cell::RefCell, fmt::Display};
+
+use bevy::{
+    prelude::{
+        error, Added, Assets, BuildChildren, Color, Commands, Component, DespawnRecursiveExt,
+        Entity, Handle, IVec2, Parent, Query, Res, Transform, Vec2,
+    },
+    sprite::{Sprite, SpriteBundle},
+};
+use bevy_ecs_ldtk::{ldtk::FieldInstance, prelude::FieldValue, EntityInstance, LdtkLevel};
+
+use super::{ability, boss, enemy, fan, fire_imp, goblin, torch, wisp, ProximityText};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldErrorKind {
+    Missing,
+    WrongType { expected: &'static str },
+    InvalidValue(String),
+}
+
+impl Display for FieldErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing or null"),
+            Self::WrongType { expected } => write!(f, "expected {}", expected),
+            Self::InvalidValue(value) => write!(f, "invalid value {:?}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub kind: FieldErrorKind,
+}
+
+pub trait FromField: Sized {
+    /// Returns `Ok(None)` for a null value
+    fn from_field(value: &FieldValue) -> Result<Option<Self>, FieldErrorKind>;
+}
+
+impl FromField for bool {
+    fn from_field(value: &FieldValue) -> Result<Option<Self>, FieldErrorKind> {
+        match value {
+            FieldValue::Bool(value) => Ok(Some(*value)),
+            _ => Err(FieldErrorKind::WrongType { expected: "Bool" }),
+        }
+    }
+}
+
+impl FromField for i32 {
+    fn from_field(value: &FieldValue) -> Result<Option<Self>, FieldErrorKind> {
+        match value {
+            FieldValue::Int(value) => Ok(*value),
+            _ => Err(FieldErrorKind::WrongType { expected: "Int" }),
+        }
+    }
+}
+
+impl FromField for f32 {
+    fn from_field(value: &FieldValue) -> Result<Option<Self>, FieldErrorKind> {
+        match value {
+            FieldValue::Float(value) => Ok(*value),
+            _ => Err(FieldErrorKind::WrongType { expected: "Float" }),
+        }
+    }
+}
+
+impl FromField for String {
+    fn from_field(value: &FieldValue) -> Result<Option<Self>, FieldErrorKind> {
+        match value {
+            FieldValue::String(value) => Ok(value.clone()),
+            _ => Err(FieldErrorKind::WrongType { expected: "String" }),
+        }
+    }
+}
+
+impl FromField for IVec2 {
+    fn from_field(value: &FieldValue) -> Result<Option<Self>, FieldErrorKind> {
+        match value {
+            FieldValue::Point(value) => Ok(*value),
+            _ => Err(FieldErrorKind::WrongType { expected: "Point" }),
+        }
+    }
+}
+
+/// An LDtk enum, stored in fields as the variant name
+pub trait LdtkEnum: Sized {
+    fn from_variant(variant: &str) -> Option<Self>;
+}
+
+impl<T: LdtkEnum> FromField for T {
+    fn from_field(value: &FieldValue) -> Result<Option<Self>, FieldErrorKind> {
+        match value {
+            FieldValue::Enum(Some(variant)) => T::from_variant(variant)
+                .map(Some)
+                .ok_or_else(|| FieldErrorKind::InvalidValue(variant.clone())),
+            FieldValue::Enum(None) => Ok(None),
+            _ => Err(FieldErrorKind::WrongType { expected: "Enum" }),
+        }
+    }
+}
+
+/// Reads fields by name, remembering every one that couldn't be read
+pub struct Fields<'a> {
+    fields: &'a [FieldInstance],
+    errors: RefCell<Vec<FieldError>>,
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(fields: &'a [FieldInstance]) -> Self {
+        Self {
+            fields,
+            errors: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn parse<T: FromField>(&self, name: &'static str) -> Result<Option<T>, FieldErrorKind> {
+        match self.fields.iter().find(|f| f.identifier == name) {
+            Some(field) => T::from_field(&field.value),
+            None => Err(FieldErrorKind::Missing),
+        }
+    }
+
+    fn record(&self, field: &'static str, kind: FieldErrorKind) {
+        self.errors.borrow_mut().push(FieldError { field, kind });
+    }
+
+    /// A missing, null or invalid field is recorded as an error
+    pub fn required<T: FromField>(&self, name: &'static str) -> Option<T> {
+        match self.parse(name) {
+            Ok(Some(value)) => Some(value),
+            Ok(None) => {
+                self.record(name, FieldErrorKind::Missing);
+                None
+            }
+            Err(kind) => {
+                self.record(name, kind);
+                None
+            }
+        }
+    }
+
+    /// A missing or null field is allowed, an invalid one is still recorded as an error
+    pub fn optional<T: FromField>(&self, name: &'static str) -> Option<T> {
+        match self.parse(name) {
+            Ok(value) => value,
+            Err(FieldErrorKind::Missing) => None,
+            Err(kind) => {
+                self.record(name, kind);
+                None
+            }
+        }
+    }
+
+    /// Like `optional`, but a value that doesn't pass `valid` is recorded as an error too
+    pub fn optional_if<T: FromField + Display>(
+        &self,
+        name: &'static str,
+        valid: impl Fn(&T) -> bool,
+    ) -> Option<T> {
+        match self.optional(name) {
+            Some(value) if !valid(&value) => {
+                self.record(name, FieldErrorKind::InvalidValue(value.to_string()));
+                None
+            }
+            value => value,
+        }
+    }
+
+    pub fn into_errors(self) -> Vec<FieldError> {
+        self.errors.into_inner()
+    }
+}
+
+/// Every field that couldn't be read on an LDtk entity. Empty if it was built correctly. Also run
+/// by `level-lint`, so the lint and the game agree on which fields are valid.
+#[derive(Component, Default, Debug)]
+pub struct FieldErrors {
+    pub identifier: String,
+    pub iid: String,
+    pub errors: Vec<FieldError>,
+}
+
+impl From<EntityInstance> for FieldErrors {
+    fn from(entity_instance: EntityInstance) -> Self {
+        let fields = Fields::new(&entity_instance.field_instances);
+        // Read the same fields as the components built from this entity
+        match entity_instance.identifier.as_ref() {
+            "Fan" | "AirCurrent" => {
+                fan::direction(&fields);
+            }
+            "Goblin" => {
+                goblin::patrol_fields(&fields);
+                goblin::tuning(&fields);
+                enemy::stats(&fields, Default::default());
+            }
+            "FireImp" => {
+                fire_imp::fire_imp_fields(&fields);
+                enemy::stats(&fields, Default::default());
+            }
+            "Boss" | "BossArena" => {
+                boss::boss_id(&fields);
+            }
+            "Wisp" => {
+                wisp::wisp_fields(&fields);
+                enemy::stats(&fields, Default::default());
+            }
+            "Signpost" => {
+                ProximityText::signpost(&fields);
+            }
+            "Ability" => {
+                ability::ability(&fields);
+            }
+            "Torch" => {
+                torch::lit(&fields);
+            }
+            _ => {}
+        }
+        Self {
+            identifier: entity_instance.identifier,
+            iid: entity_instance.iid,
+            errors: fields.into_errors(),
+        }
+    }
+}
+
+/// Stands in for an entity whose fields couldn't be read, holding what was logged about it
+#[derive(Component)]
+pub struct InvalidEntity(pub String);
+
+/// Logs every entity spawned this frame with bad fields and replaces it with a red placeholder
+pub fn report_invalid(
+    mut commands: Commands,
+    query: Query<(Entity, &FieldErrors, &Transform, Option<&Parent>), Added<FieldErrors>>,
+    parents: Query<&Parent>,
+    levels: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+) {
+    let mut report = String::new();
+    for (entity, field_errors, transform, parent) in query.iter() {
+        if field_errors.errors.is_empty() {
+            continue;
+        }
+        // An entity's parent is its layer, whose parent is the level
+        let level = parent
+            .and_then(|layer| parents.get(layer.get()).ok())
+            .and_then(|level| levels.get(level.get()).ok())
+            .and_then(|handle| ldtk_levels.get(handle))
+            .map(|ldtk_level| ldtk_level.level.identifier.as_str())
+            .unwrap_or("unknown level");
+        let mut lines = String::new();
+        for error in &field_errors.errors {
+            lines += &format!(
+                "\n  {} {} ({}) field {}: {}",
+                level, field_errors.identifier, field_errors.iid, error.field, error.kind
+            );
+        }
+        report += &lines;
+
+        let placeholder = commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::RED,
+                    custom_size: Some(Vec2::splat(16.0)),
+                    ..Default::default()
+                },
+                transform: *transform,
+                ..Default::default()
+            })
+            .insert(InvalidEntity(lines))
+            .id();
+        if let Some(parent) = parent {
+            commands.entity(parent.get()).add_child(placeholder);
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+    if !report.is_empty() {
+        error!("LDtk entities with invalid fields:{}", report);
+    }
+}
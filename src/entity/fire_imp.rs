@@ -0,0 +1,250 @@
+use bevy::{
+    prelude::{
+        Added, Assets, Bundle, Color, Commands, Component, DespawnRecursiveExt, EventReader,
+        GlobalTransform, Mut, Query, Res, ResMut, Vec3, With, Without,
+    },
+    sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
+    time::{Time, Timer},
+};
+use bevy_ecs_ldtk::{EntityInstance, LdtkEntity};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+use heron::{CollisionLayers, Velocity};
+
+use crate::{
+    abilities::{self, FireProjectile, WaterProjectile, WindProjectile},
+    animation::Animated,
+    audio::{AudioAssets, SoundChannel},
+    collision::ProjectileHit,
+    damage::{Hurtbox, Killed},
+    destruction::DestructionTimer,
+    level::WallTiles,
+    physics::{PhysicsLayers, PhysicsObjectBundle},
+    state::load_game::GameAssets,
+};
+
+use super::{
+    enemy::EnemyStats,
+    fields::{FieldErrors, Fields},
+    goblin::{AnimationState, Enemy},
+    player::Player,
+};
+
+const FIREBALL_SPEED: f32 = 90.0;
+/// Seconds until a thrown fireball that hit nothing burns out
+const FIREBALL_LIFETIME: f32 = 3.0;
+/// Seconds the throwing animation plays after each fireball
+const THROW_SECONDS: f32 = 0.6;
+
+/// Stands still and throws fireballs at the player whenever it can see them
+#[derive(Component)]
+pub struct FireImp {
+    /// Seconds between fireballs
+    pub cooldown: Timer,
+    /// How far away the player can be seen
+    pub range: f32,
+}
+
+impl From<EntityInstance> for FireImp {
+    fn from(entity_instance: EntityInstance) -> Self {
+        let (cooldown, range) = fire_imp_fields(&Fields::new(&entity_instance.field_instances));
+        Self {
+            cooldown: Timer::from_seconds(cooldown, true),
+            range,
+        }
+    }
+}
+
+/// The optional `Cooldown` and `Range` fields of fire imps
+pub fn fire_imp_fields(fields: &Fields) -> (f32, f32) {
+    (
+        fields
+            .optional_if("Cooldown", |cooldown: &f32| *cooldown > 0.0)
+            .unwrap_or(2.0),
+        fields.optional("Range").unwrap_or(128.0),
+    )
+}
+
+/// Thrown by a fire imp. Hurts the player unless they are resistant to fire.
+#[derive(Component)]
+pub struct ImpFireball;
+
+/// Fireballs fly through other enemies but are stopped by everything the player can throw back
+pub fn fireball_layers() -> CollisionLayers {
+    CollisionLayers::none()
+        .with_group(PhysicsLayers::Enemy)
+        .with_masks([
+            PhysicsLayers::Terrain,
+            PhysicsLayers::PlayerBody,
+            PhysicsLayers::WaterDrop,
+            PhysicsLayers::Wind,
+        ])
+}
+
+/// Throws an `ImpFireball` from `translation` and starts the throwing animation. Shared by imps
+/// and bosses.
+pub fn throw_fireball(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    translation: Vec3,
+    velocity: Vec3,
+    animation: &mut Mut<AnimationState>,
+) {
+    let mut fireball = commands.spawn();
+    abilities::insert_fireball(
+        &mut fireball,
+        game_assets,
+        texture_atlases,
+        translation,
+        velocity,
+    );
+    fireball
+        .insert(ImpFireball)
+        .insert(DestructionTimer(Timer::from_seconds(
+            FIREBALL_LIFETIME,
+            false,
+        )))
+        .insert(fireball_layers());
+    **animation = AnimationState::Attack;
+}
+
+/// Goes back to idle once the throwing animation has played, `cooldown` restarts on every throw
+pub fn finish_throw(animation: &mut Mut<AnimationState>, cooldown: &Timer) {
+    if **animation == AnimationState::Attack && cooldown.elapsed_secs() >= THROW_SECONDS {
+        **animation = AnimationState::Idle;
+    }
+}
+
+/// An imp's fireball blown back by the wind, it now burns enemies
+#[derive(Component)]
+pub struct Deflected;
+
+#[derive(Bundle, LdtkEntity)]
+pub struct FireImpBundle {
+    #[bundle]
+    #[sprite_sheet_bundle("sprites/goblin_spritesheet.png", 16.0, 16.0, 6, 5, 0.0, 0.0, 0)]
+    pub sprite_sheet_bundle: SpriteSheetBundle,
+    pub enemy: Enemy,
+    pub hurtbox: Hurtbox,
+    #[bundle]
+    #[from_entity_instance]
+    pub physics_bundle: PhysicsObjectBundle,
+    #[from_entity_instance]
+    pub animated: Animated,
+    pub state: AnimationState,
+    #[from_entity_instance]
+    pub fire_imp: FireImp,
+    #[from_entity_instance]
+    pub stats: EnemyStats,
+    #[from_entity_instance]
+    pub field_errors: FieldErrors,
+}
+
+pub fn tint(mut query: Query<&mut TextureAtlasSprite, Added<FireImp>>) {
+    for mut sprite in query.iter_mut() {
+        sprite.color = Color::rgb(1.0, 0.45, 0.35);
+    }
+}
+
+pub fn throw(
+    mut commands: Commands,
+    mut imps: Query<
+        (
+            &mut FireImp,
+            &GlobalTransform,
+            &mut TextureAtlasSprite,
+            &mut AnimationState,
+        ),
+        With<Hurtbox>,
+    >,
+    player: Query<&GlobalTransform, (With<Player>, Without<Killed>)>,
+    walls: Res<WallTiles>,
+    time: Res<Time>,
+    game_assets: Res<GameAssets>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let player = player
+        .iter()
+        .next()
+        .map(|transform| transform.translation().truncate());
+    for (mut imp, transform, mut sprite, mut animation) in imps.iter_mut() {
+        imp.cooldown.tick(time.delta());
+        finish_throw(&mut animation, &imp.cooldown);
+        let position = transform.translation().truncate();
+        let to_player = match player {
+            Some(player)
+                if player.distance(position) <= imp.range
+                    && walls.line_of_sight(position, player) =>
+            {
+                player - position
+            }
+            _ => continue,
+        };
+        sprite.flip_x = to_player.x < 0.0;
+        if !imp.cooldown.just_finished() {
+            continue;
+        }
+        throw_fireball(
+            &mut commands,
+            &game_assets,
+            &mut texture_atlases,
+            transform.translation(),
+            (to_player.normalize_or_zero() * FIREBALL_SPEED).extend(0.0),
+            &mut animation,
+        );
+        sound_channel.play(audio_assets.fireball.clone());
+    }
+}
+
+/// Fireballs are put out by water, blown back by wind and burn out against anything else
+pub fn fireball_collision(
+    mut commands: Commands,
+    mut fireballs: Query<
+        (&mut Velocity, &mut TextureAtlasSprite, &mut CollisionLayers),
+        With<ImpFireball>,
+    >,
+    droplets: Query<(), With<WaterProjectile>>,
+    gusts: Query<(), With<WindProjectile>>,
+    mut hits: EventReader<ProjectileHit>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for hit in hits.iter() {
+        // Both sides can be projectiles, in either order
+        let (fireball, other, other_is_target) = if fireballs.contains(hit.projectile) {
+            (hit.projectile, hit.target, true)
+        } else if fireballs.contains(hit.target) {
+            (hit.target, hit.projectile, false)
+        } else {
+            continue;
+        };
+        let (mut velocity, mut sprite, mut layers) = match fireballs.get_mut(fireball) {
+            Ok(fireball) => fireball,
+            Err(_) => continue,
+        };
+        if gusts.contains(other) {
+            velocity.linear = -velocity.linear;
+            sprite.flip_x = !sprite.flip_x;
+            *layers = CollisionLayers::none()
+                .with_group(PhysicsLayers::Fireball)
+                .with_masks([PhysicsLayers::Enemy, PhysicsLayers::Wood]);
+            commands
+                .entity(fireball)
+                .remove::<ImpFireball>()
+                .insert(FireProjectile)
+                .insert(Deflected);
+            sound_channel.play(audio_assets.air.clone());
+            continue;
+        }
+        if droplets.contains(other) {
+            // A droplet hitting something as the projectile despawns itself
+            if other_is_target {
+                commands.entity(other).despawn_recursive();
+            }
+            sound_channel.play(audio_assets.steam.clone());
+        }
+        commands.entity(fireball).despawn_recursive();
+    }
+}
@@ -1,22 +1,38 @@
 use bevy::{
     prelude::{
-        Added, AssetServer, Assets, Bundle, Changed, Component, Handle, IVec2, Image, Query,
-        Transform, Vec2,
+        Added, AssetServer, Assets, Bundle, Changed, Component, GlobalTransform, Handle, IVec2,
+        Image, Mut, Query, Res, Transform, Vec2, With, Without,
     },
     sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
+    time::{Time, Timer},
 };
 use bevy_ecs_ldtk::{
-    prelude::{FieldValue, LayerInstance, LdtkEntity, TilesetDefinition},
+    prelude::{LayerInstance, LdtkEntity, TilesetDefinition},
     utils::ldtk_pixel_coords_to_translation_pivoted,
     EntityInstance,
 };
 use heron::Velocity;
 
-use crate::{animation::Animated, damage::Hurtbox, physics::PhysicsObjectBundle};
+use crate::{
+    animation::Animated,
+    damage::{Hurtbox, Killed},
+    level::{WallTiles, GRID_SIZE},
+    physics::PhysicsObjectBundle,
+};
 
-use super::Flamable;
+use super::{
+    enemy::{self, EnemyStats, Slowed},
+    fields::{FieldErrors, Fields},
+    player::Player,
+    Flamable,
+};
 
 const MOVEMENT_SPEED: f32 = 20.0;
+/// How long the attack animation plays, the goblin lunges at the player meanwhile
+const ATTACK_SECONDS: f32 = 0.6;
+const LUNGE_SPEED: f32 = 80.0;
+/// Seconds a chasing goblin keeps going after losing sight of the player
+const GIVE_UP_SECONDS: f32 = 2.0;
 
 #[derive(Component, Default)]
 pub struct Enemy;
@@ -44,57 +60,117 @@ impl LdtkEntity for Patrol {
             entity_instance.pivot,
         );
 
-        let field_instances = &entity_instance.field_instances;
+        let (face_left, point) = patrol_fields(&Fields::new(&entity_instance.field_instances));
+        let points = point.map(|ldtk_point| {
+            let pixel_coords = (ldtk_point.as_vec2() + Vec2::new(0.5, 1.))
+                * Vec2::splat(layer_instance.grid_size as f32);
 
-        let face_left = if let Some(ldtk_patrol) =
-            field_instances.iter().find(|f| f.identifier == *"FaceLeft")
-        {
-            if let FieldValue::Bool(face_left) = &ldtk_patrol.value {
-                *face_left
-            } else {
-                false
-            }
-        } else {
-            false
-        };
+            let end = ldtk_pixel_coords_to_translation_pivoted(
+                pixel_coords.as_ivec2(),
+                layer_instance.c_hei * layer_instance.grid_size,
+                IVec2::new(entity_instance.width, entity_instance.height),
+                entity_instance.pivot,
+            ) - 8.0;
+            (start, end)
+        });
+        Patrol {
+            points,
+            movement_speed: MOVEMENT_SPEED,
+            face_left,
+        }
+    }
+}
 
-        if let Some(ldtk_patrol) = field_instances.iter().find(|f| f.identifier == *"Point") {
-            if let FieldValue::Point(Some(ldtk_point)) = &ldtk_patrol.value {
-                let pixel_coords = (ldtk_point.as_vec2() + Vec2::new(0.5, 1.))
-                    * Vec2::splat(layer_instance.grid_size as f32);
-
-                let end = ldtk_pixel_coords_to_translation_pivoted(
-                    pixel_coords.as_ivec2(),
-                    layer_instance.c_hei * layer_instance.grid_size,
-                    IVec2::new(entity_instance.width, entity_instance.height),
-                    entity_instance.pivot,
-                ) - 8.0;
-                Patrol {
-                    points: Some((start, end)),
-                    movement_speed: MOVEMENT_SPEED,
-                    face_left,
-                }
-            } else {
-                Patrol {
-                    points: None,
-                    movement_speed: MOVEMENT_SPEED,
-                    face_left,
-                }
-            }
-        } else {
-            Patrol {
-                points: None,
-                movement_speed: MOVEMENT_SPEED,
-                face_left,
-            }
+/// The optional `FaceLeft` and patrol `Point` fields of goblins
+pub fn patrol_fields(fields: &Fields) -> (bool, Option<IVec2>) {
+    (
+        fields.optional("FaceLeft").unwrap_or(false),
+        fields.optional("Point"),
+    )
+}
+
+/// Tuning read from optional LDtk fields, so individual goblins can be made tougher
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct GoblinTuning {
+    /// How far away the player can be seen
+    pub sight_range: f32,
+    pub chase_speed: f32,
+    /// How close the player has to be before the goblin lunges
+    pub attack_range: f32,
+    /// Seconds spent noticing the player before giving chase
+    pub alert_seconds: f32,
+    /// How far from home a goblin chases before giving up
+    pub leash: f32,
+}
+
+impl Default for GoblinTuning {
+    fn default() -> Self {
+        Self {
+            sight_range: 96.0,
+            chase_speed: 45.0,
+            attack_range: 20.0,
+            alert_seconds: 0.5,
+            leash: 160.0,
         }
     }
 }
 
+impl From<EntityInstance> for GoblinTuning {
+    fn from(entity_instance: EntityInstance) -> Self {
+        tuning(&Fields::new(&entity_instance.field_instances))
+    }
+}
+
+/// The optional `SightRange`, `ChaseSpeed`, `AttackRange`, `AlertTime` and `Leash` fields of goblins
+pub fn tuning(fields: &Fields) -> GoblinTuning {
+    let default = GoblinTuning::default();
+    GoblinTuning {
+        sight_range: fields.optional("SightRange").unwrap_or(default.sight_range),
+        chase_speed: fields.optional("ChaseSpeed").unwrap_or(default.chase_speed),
+        attack_range: fields
+            .optional("AttackRange")
+            .unwrap_or(default.attack_range),
+        alert_seconds: fields
+            .optional("AlertTime")
+            .unwrap_or(default.alert_seconds),
+        leash: fields.optional("Leash").unwrap_or(default.leash),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GoblinState {
+    /// Walking between the patrol points, or standing still without any
+    #[default]
+    Patrol,
+    /// Spotted the player and about to give chase
+    Alert,
+    Chase,
+    Attack,
+    /// Walking back home after losing the player
+    Return,
+}
+
+#[derive(Component, Default)]
+pub struct GoblinAi {
+    pub state: GoblinState,
+    /// Runs out to leave `Alert` and `Attack`, or to give up a chase while the player is hidden
+    pub timer: Timer,
+    /// Where the goblin started, in the same space as its `Transform`
+    pub home: Option<Vec2>,
+}
+
+impl GoblinAi {
+    fn enter(&mut self, state: GoblinState, seconds: f32) {
+        self.state = state;
+        self.timer = Timer::from_seconds(seconds, false);
+    }
+}
+
 #[derive(Component, Eq, PartialEq)]
 pub enum AnimationState {
     Idle,
     Walking,
+    Attack,
     Death,
 }
 impl Default for AnimationState {
@@ -119,6 +195,13 @@ pub struct GoblinBundle {
     pub state: AnimationState,
     #[ldtk_entity]
     pub patrol: Patrol,
+    #[from_entity_instance]
+    pub tuning: GoblinTuning,
+    pub ai: GoblinAi,
+    #[from_entity_instance]
+    pub stats: EnemyStats,
+    #[from_entity_instance]
+    pub field_errors: FieldErrors,
 }
 
 pub fn init_animation_state(mut query: Query<(&Patrol, &mut AnimationState), Added<Patrol>>) {
@@ -131,14 +214,28 @@ pub fn init_animation_state(mut query: Query<(&Patrol, &mut AnimationState), Add
     }
 }
 
-pub fn patrol(mut query: Query<(&Patrol, &mut Velocity, &Transform, &mut TextureAtlasSprite)>) {
-    for (patrol, mut velocity, transform, mut sprite) in query.iter_mut() {
+pub fn patrol(
+    mut query: Query<(
+        &Patrol,
+        &GoblinAi,
+        &mut Velocity,
+        &Transform,
+        &mut TextureAtlasSprite,
+        &EnemyStats,
+        Option<&Slowed>,
+    )>,
+) {
+    for (patrol, ai, mut velocity, transform, mut sprite, stats, slowed) in query.iter_mut() {
+        if ai.state != GoblinState::Patrol || stats.is_flashing() {
+            continue;
+        }
+        let speed = patrol.movement_speed * enemy::speed(slowed);
         if let Some((start, end)) = patrol.points {
             if transform.translation.x < start.x.min(end.x) || velocity.linear.x == 0.0 {
-                velocity.linear.x = patrol.movement_speed;
+                velocity.linear.x = speed;
                 sprite.flip_x = false;
             } else if transform.translation.x > start.x.max(end.x) {
-                velocity.linear.x = -patrol.movement_speed;
+                velocity.linear.x = -speed;
                 sprite.flip_x = true;
             }
         }
@@ -156,10 +253,17 @@ pub fn animation_state_update(
             AnimationState::Idle => {
                 animation.start = 18;
                 animation.end = 22;
+                animation.play_once = false;
             }
             AnimationState::Walking => {
                 animation.start = 0;
                 animation.end = 6;
+                animation.play_once = false;
+            }
+            AnimationState::Attack => {
+                animation.start = 12;
+                animation.end = 18;
+                animation.play_once = true;
             }
             AnimationState::Death => {
                 animation.start = 6;
@@ -176,3 +280,164 @@ pub fn face_direction(mut query: Query<(&mut TextureAtlasSprite, &Patrol), Added
         sprite.flip_x = patrol.face_left;
     }
 }
+
+/// Only touches the state when it changes, since that restarts the animation
+fn set_animation(animation: &mut Mut<AnimationState>, state: AnimationState) {
+    if **animation != state {
+        **animation = state;
+    }
+}
+
+/// Whether a goblin at `position` can keep walking in `direction` without hitting a wall or
+/// walking off a ledge
+fn can_walk(walls: &WallTiles, position: Vec2, direction: f32) -> bool {
+    let ahead = position + Vec2::new(direction * GRID_SIZE * 0.75, 0.0);
+    !walls.is_wall(ahead) && walls.is_wall(ahead - Vec2::new(0.0, GRID_SIZE))
+}
+
+/// Moves goblins between patrolling, noticing, chasing, attacking and heading back home
+pub fn think(
+    mut goblins: Query<
+        (
+            &mut GoblinAi,
+            &GoblinTuning,
+            &Patrol,
+            &Transform,
+            &GlobalTransform,
+            &mut Velocity,
+            &mut TextureAtlasSprite,
+            &mut AnimationState,
+            &EnemyStats,
+            Option<&Slowed>,
+        ),
+        With<Hurtbox>,
+    >,
+    player: Query<&GlobalTransform, (With<Player>, Without<Killed>)>,
+    walls: Res<WallTiles>,
+    time: Res<Time>,
+) {
+    let player = player
+        .iter()
+        .next()
+        .map(|transform| transform.translation().truncate());
+    for (
+        mut ai,
+        tuning,
+        patrol,
+        transform,
+        global_transform,
+        mut velocity,
+        mut sprite,
+        mut animation,
+        stats,
+        slowed,
+    ) in goblins.iter_mut()
+    {
+        // Stunned while flashing from a hit
+        if *animation == AnimationState::Death || stats.is_flashing() {
+            continue;
+        }
+        let speed = enemy::speed(slowed);
+        let local = transform.translation.truncate();
+        let home = *ai.home.get_or_insert(local);
+        let position = global_transform.translation().truncate();
+        let facing = if sprite.flip_x { -1.0 } else { 1.0 };
+        let to_player = player.map(|player| player - position);
+        let in_sight = |require_facing: bool| {
+            to_player.map_or(false, |to_player| {
+                to_player.length() <= tuning.sight_range
+                    && (!require_facing || to_player.x * facing >= 0.0)
+                    && walls.line_of_sight(position, position + to_player)
+            })
+        };
+        ai.timer.tick(time.delta());
+
+        match ai.state {
+            GoblinState::Patrol => {
+                if in_sight(true) {
+                    ai.enter(GoblinState::Alert, tuning.alert_seconds);
+                    velocity.linear.x = 0.0;
+                    set_animation(&mut animation, AnimationState::Idle);
+                }
+            }
+            GoblinState::Alert => {
+                if let Some(to_player) = to_player {
+                    sprite.flip_x = to_player.x < 0.0;
+                }
+                if ai.timer.finished() {
+                    if in_sight(false) {
+                        ai.enter(GoblinState::Chase, GIVE_UP_SECONDS);
+                    } else {
+                        ai.enter(GoblinState::Return, 0.0);
+                    }
+                }
+            }
+            GoblinState::Chase => {
+                let to_player = match to_player {
+                    Some(to_player) => to_player,
+                    None => {
+                        ai.enter(GoblinState::Return, 0.0);
+                        continue;
+                    }
+                };
+                if in_sight(false) {
+                    ai.timer.reset();
+                }
+                if ai.timer.finished() || local.distance(home) > tuning.leash {
+                    ai.enter(GoblinState::Return, 0.0);
+                    continue;
+                }
+                let direction = to_player.x.signum();
+                sprite.flip_x = direction < 0.0;
+                if to_player.length() <= tuning.attack_range {
+                    ai.enter(GoblinState::Attack, ATTACK_SECONDS);
+                    velocity.linear.x = direction * LUNGE_SPEED * speed;
+                    set_animation(&mut animation, AnimationState::Attack);
+                } else if can_walk(&walls, position, direction) {
+                    velocity.linear.x = direction * tuning.chase_speed * speed;
+                    set_animation(&mut animation, AnimationState::Walking);
+                } else {
+                    velocity.linear.x = 0.0;
+                    set_animation(&mut animation, AnimationState::Idle);
+                }
+            }
+            GoblinState::Attack => {
+                if !can_walk(&walls, position, velocity.linear.x.signum()) {
+                    velocity.linear.x = 0.0;
+                }
+                if ai.timer.finished() {
+                    velocity.linear.x = 0.0;
+                    ai.enter(GoblinState::Chase, GIVE_UP_SECONDS);
+                }
+            }
+            GoblinState::Return => {
+                if in_sight(true) {
+                    ai.enter(GoblinState::Alert, tuning.alert_seconds);
+                    velocity.linear.x = 0.0;
+                    set_animation(&mut animation, AnimationState::Idle);
+                    continue;
+                }
+                let offset = home.x - local.x;
+                let direction = offset.signum();
+                // Patrolling resumes from anywhere between the patrol points
+                let on_patrol = patrol.points.map_or(false, |(start, end)| {
+                    local.x >= start.x.min(end.x) && local.x <= start.x.max(end.x)
+                });
+                if offset.abs() < 2.0 || on_patrol || !can_walk(&walls, position, direction) {
+                    ai.enter(GoblinState::Patrol, 0.0);
+                    velocity.linear.x = 0.0;
+                    if patrol.points.is_some() {
+                        set_animation(&mut animation, AnimationState::Walking);
+                    } else {
+                        sprite.flip_x = patrol.face_left;
+                        set_animation(&mut animation, AnimationState::Idle);
+                    }
+                } else {
+                    velocity.linear.x = direction * patrol.movement_speed * speed;
+                    sprite.flip_x = direction < 0.0;
+                    set_animation(&mut animation, AnimationState::Walking);
+                }
+            }
+        }
+    }
+}
@@ -1,20 +1,15 @@
 use bevy::{
-    prelude::{Bundle, Commands, Component, Entity, EventReader, Query, Res, With},
+    prelude::{Bundle, Commands, Component, Entity},
     sprite::SpriteSheetBundle,
 };
 use bevy_ecs_ldtk::LdtkEntity;
-use heron::CollisionEvent;
+use heron::{CollisionLayers, RigidBody};
 
 use crate::{
-    abilities::{Element, Equipment},
     animation::Animated,
-    damage::Killed,
-    debug::DebugSettings,
-    physics::PhysicsObjectBundle,
+    physics::{PhysicsLayers, PhysicsObjectBundle},
 };
 
-use super::player::Player;
-
 #[derive(Component, Default)]
 pub struct Lava;
 
@@ -31,36 +26,22 @@ pub struct LavaBundle {
     pub animated: Animated,
 }
 
-pub fn check_collision(
-    mut commands: Commands,
-    lava: Query<Entity, With<Lava>>,
-    player: Query<&Player>,
-    mut collision_events: EventReader<CollisionEvent>,
-    debug_settings: Res<DebugSettings>,
+/// Cools a lava tile to stone that can be stood on
+pub fn solidify(
+    commands: &mut Commands,
+    entity: Entity,
+    animation: &mut Animated,
+    rb: &mut RigidBody,
+    layers: &mut CollisionLayers,
 ) {
-    collision_events
-        .iter()
-        .filter(|e| e.is_started())
-        .filter_map(|e| {
-            let (e1, e2) = e.rigid_body_entities();
-            if let Ok(player) = player.get(e1) {
-                if lava.get(e2).is_ok()
-                    && !debug_settings.imortality
-                    && !(player.has_equipt(Equipment::Cloak) && player.has_infused(Element::Fire))
-                {
-                    return Some(e1);
-                }
-            } else if let Ok(player) = player.get(e2) {
-                if lava.get(e1).is_ok()
-                    && !debug_settings.imortality
-                    && !(player.has_equipt(Equipment::Cloak) && player.has_infused(Element::Fire))
-                {
-                    return Some(e2);
-                }
-            }
-            None
-        })
-        .for_each(|entity| {
-            commands.entity(entity).insert(Killed {});
-        });
+    // the last frame is stone
+    animation.start = 8;
+    animation.end = 9;
+    if *rb != RigidBody::Static {
+        *rb = RigidBody::Static;
+    }
+    commands.entity(entity).remove::<Lava>();
+    if !layers.contains_group(PhysicsLayers::Terrain) {
+        *layers = layers.with_group(PhysicsLayers::Terrain);
+    }
 }
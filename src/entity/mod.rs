@@ -1,24 +1,33 @@
-use bevy::prelude::{Color, Component, Vec3};
-use bevy_ecs_ldtk::{prelude::FieldValue, EntityInstance};
+use bevy::prelude::{Children, Color, Component, EventReader, Query, Vec3, Visibility, With};
+use bevy_ecs_ldtk::EntityInstance;
 use bevy_inspector_egui::Inspectable;
 use heron::{CollisionLayers, CollisionShape, PhysicMaterial, RigidBody, RotationConstraints};
 
 use crate::{
     animation::Animated,
+    collision::{Contact, PlayerProximity},
     physics::{PhysicsLayers, PhysicsObjectBundle},
 };
 
+use self::{fields::Fields, signpost::TextBox};
+
 pub mod ability;
 pub mod block;
+pub mod boss;
 pub mod checkpoint;
+pub mod enemy;
 pub mod fan;
+pub mod fields;
+pub mod fire_imp;
 pub mod goblin;
 pub mod lava;
 pub mod player;
+pub mod registered;
 pub mod signpost;
 pub mod torch;
 pub mod trophy;
 pub mod water;
+pub mod wisp;
 
 #[derive(Component, Default)]
 pub struct Flamable;
@@ -28,8 +37,8 @@ impl From<EntityInstance> for Animated {
         match entity_instance.identifier.as_ref() {
             "Player" => Animated::new(0.1, 0, 1, false),
             "Checkpoint" => Animated::new(0.1, 0, 9, false),
-            "Goblin" => Animated::new(0.1, 18, 22, false),
-            "Ability" => Animated::new(0.1, 0, 6, false),
+            "Goblin" | "FireImp" | "Boss" => Animated::new(0.1, 18, 22, false),
+            "Ability" | "Wisp" => Animated::new(0.1, 0, 6, false),
             "Lava" => Animated::new(0.3, 0, 8, false),
             "Water" => Animated::new(0.15, 0, 8, false),
             "Fan" => Animated::new(0.1, 0, 4, false),
@@ -82,7 +91,7 @@ impl From<EntityInstance> for PhysicsObjectBundle {
                     .with_group(PhysicsLayers::Interactable),
                 ..Default::default()
             },
-            "Goblin" => PhysicsObjectBundle {
+            "Goblin" | "FireImp" => PhysicsObjectBundle {
                 collider: CollisionShape::Cuboid {
                     half_extends: Vec3 {
                         x: 6.0,
@@ -97,6 +106,50 @@ impl From<EntityInstance> for PhysicsObjectBundle {
                     .with_group(PhysicsLayers::Enemy),
                 ..Default::default()
             },
+            "Boss" => PhysicsObjectBundle {
+                collider: CollisionShape::Cuboid {
+                    half_extends: Vec3 {
+                        x: 12.0,
+                        y: 14.0,
+                        z: 1.0,
+                    },
+                    border_radius: None,
+                },
+                rb: RigidBody::KinematicVelocityBased,
+                layer: CollisionLayers::all_masks::<PhysicsLayers>()
+                    .without_mask(PhysicsLayers::PlayerGroundDetector)
+                    .with_group(PhysicsLayers::Enemy),
+                ..Default::default()
+            },
+            // Covers the whole region drawn in LDtk
+            "BossArena" => PhysicsObjectBundle {
+                collider: CollisionShape::Cuboid {
+                    half_extends: Vec3::new(
+                        entity_instance.width as f32 / 2.0,
+                        entity_instance.height as f32 / 2.0,
+                        0.0,
+                    ),
+                    border_radius: None,
+                },
+                rb: RigidBody::Sensor,
+                layer: CollisionLayers::all_masks::<PhysicsLayers>()
+                    .with_group(PhysicsLayers::Interactable),
+                ..Default::default()
+            },
+            "Wisp" => PhysicsObjectBundle {
+                collider: CollisionShape::Sphere { radius: 4.0 },
+                rb: RigidBody::Dynamic,
+                material: PhysicMaterial {
+                    friction: 0.0,
+                    density: 10.0,
+                    restitution: 0.0,
+                },
+                rot_constraints: RotationConstraints::lock(),
+                layer: CollisionLayers::all_masks::<PhysicsLayers>()
+                    .without_mask(PhysicsLayers::PlayerGroundDetector)
+                    .with_group(PhysicsLayers::Enemy),
+                ..Default::default()
+            },
             "Block" => PhysicsObjectBundle {
                 collider: CollisionShape::Cuboid {
                     half_extends: Vec3::splat(8.0),
@@ -197,40 +250,7 @@ pub struct ProximityText {
 impl From<EntityInstance> for ProximityText {
     fn from(entity_instance: EntityInstance) -> Self {
         match entity_instance.identifier.as_ref() {
-            "Signpost" => {
-                let mut width = 250.0;
-                let field_instances = &entity_instance.field_instances;
-                if let Some(field_instance) =
-                    field_instances.iter().find(|f| f.identifier == *"Text")
-                {
-                    if let FieldValue::String(Some(text)) = &field_instance.value {
-                        if let Some(field_instance) =
-                            field_instances.iter().find(|f| f.identifier == *"Width")
-                        {
-                            if let FieldValue::Float(Some(val)) = &field_instance.value {
-                                width = *val;
-                            }
-                        }
-                        ProximityText {
-                            text: text.into(),
-                            width,
-                            color: Color::rgb(0.58, 0.345, 0.282),
-                        }
-                    } else {
-                        ProximityText {
-                            text: "Error".into(),
-                            width,
-                            color: Color::RED,
-                        }
-                    }
-                } else {
-                    ProximityText {
-                        text: "Error".into(),
-                        width,
-                        color: Color::RED,
-                    }
-                }
-            }
+            "Signpost" => ProximityText::signpost(&Fields::new(&entity_instance.field_instances)),
             "Checkpoint" => ProximityText {
                 text: "Checkpoint saved.\nPress <down> to interact".into(),
                 width: 165.0,
@@ -245,3 +265,39 @@ impl From<EntityInstance> for ProximityText {
         }
     }
 }
+
+impl ProximityText {
+    /// Built from the `Text` and optional `Width` fields
+    pub fn signpost(fields: &Fields) -> Self {
+        let width = fields.optional("Width").unwrap_or(250.0);
+        match fields.required::<String>("Text") {
+            Some(text) => ProximityText {
+                text,
+                width,
+                color: Color::rgb(0.58, 0.345, 0.282),
+            },
+            None => ProximityText {
+                text: "Error".into(),
+                width,
+                color: Color::RED,
+            },
+        }
+    }
+}
+
+/// Shows the text of signposts, checkpoints and trophies while the player is touching them
+pub fn show_proximity_text(
+    mut events: EventReader<PlayerProximity>,
+    targets: Query<&Children, With<ProximityText>>,
+    mut text: Query<&mut Visibility, With<TextBox>>,
+) {
+    for event in events.iter() {
+        if let Ok(children) = targets.get(event.target) {
+            for child in children.iter() {
+                if let Ok(mut visibility) = text.get_mut(*child) {
+                    visibility.is_visible = event.contact == Contact::Started;
+                }
+            }
+        }
+    }
+}
@@ -1,21 +1,35 @@
+use std::collections::HashSet;
+
 use bevy::{
     prelude::{
-        Added, BuildChildren, Bundle, Camera, Changed, Commands, Component, Entity, Query, Res,
-        Transform, Vec3, With,
+        Added, Assets, BuildChildren, Bundle, Camera, Changed, Commands, Component, Entity,
+        EventReader, EventWriter, Handle, Local, Query, Res, ResMut, Transform, Vec2, Vec3, With,
+        Without,
     },
     sprite::{SpriteSheetBundle, TextureAtlasSprite},
 };
-use bevy_ecs_ldtk::{EntityInstance, GridCoords, LdtkEntity, LevelSelection, Worldly};
+use bevy_ecs_ldtk::{
+    ldtk::{LdtkJson, Level},
+    EntityInstance, GridCoords, LdtkAsset, LdtkEntity, LdtkLevel, LevelEvent, LevelSelection,
+    Worldly,
+};
 use bevy_inspector_egui::Inspectable;
 use heron::Acceleration;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     abilities::{Element, Equipment},
     animation::Animated,
+    damage::{Difficulty, Health, PLAYER_HEALTH},
     input::Controllable,
+    level::HotReload,
     physics::{Dynamic, PhysicsObjectBundle},
+    save::{PendingLoad, SaveEvent, WorldProgress},
+    world::CurrentWorld,
 };
 
+use super::ability::Ability;
+
 impl From<EntityInstance> for Controllable {
     fn from(_: EntityInstance) -> Self {
         Controllable::new()
@@ -30,8 +44,10 @@ pub struct Player {
     pub unlocked_fire: bool,
     pub unlocked_air: bool,
     pub unlocked_water: bool,
+    pub unlocked_earth: bool,
     pub unlocked_boots: bool,
     pub unlocked_cloak: bool,
+    pub unlocked_amulet: bool,
     pub combination: (Option<Equipment>, Option<Element>),
     pub near_checkpoint: bool,
 }
@@ -54,54 +70,32 @@ impl Player {
         false
     }
 
-    pub fn get_combination_description(&self) -> &str {
-        match self.combination {
-            (Some(Equipment::Staff), Some(Element::Fire)) => "<x> to cast Fireball",
-            (Some(Equipment::Staff), Some(Element::Air)) => "<x> to cast a gust of wind",
-            (Some(Equipment::Staff), Some(Element::Water)) => "<x> to summon water",
-            (Some(Equipment::MagicBoots), Some(Element::Fire)) => {
-                "Jump higher with an explosive kick"
-            }
-            (Some(Equipment::MagicBoots), Some(Element::Air)) => "Double jump",
-            (Some(Equipment::MagicBoots), Some(Element::Water)) => {
-                "Flow like water (movement speed up)"
-            }
-            (Some(Equipment::Cloak), Some(Element::Fire)) => "Lava resistance",
-            (Some(Equipment::Cloak), Some(Element::Air)) => "Wind resistance",
-            (Some(Equipment::Cloak), Some(Element::Water)) => "Water resistance",
-            _ => "No effect",
-        }
-    }
-
-    /// Get the number of equipment unlocked (including staff)
-    pub fn num_equipment(&self) -> u8 {
-        let mut count = 1;
-        if self.unlocked_boots {
-            count += 1;
-        }
-        if self.unlocked_cloak {
-            count += 1;
+    pub fn has_unlocked(&self, ability: Ability) -> bool {
+        match ability {
+            Ability::Fire => self.unlocked_fire,
+            Ability::Air => self.unlocked_air,
+            Ability::Water => self.unlocked_water,
+            Ability::Earth => self.unlocked_earth,
+            Ability::MagicBoots => self.unlocked_boots,
+            Ability::Cloak => self.unlocked_cloak,
+            Ability::Amulet => self.unlocked_amulet,
         }
-        count
     }
 
-    /// Get the number of elements unlocked
-    pub fn num_elements(&self) -> u8 {
-        let mut count = 0;
-        if self.unlocked_fire {
-            count += 1;
+    pub fn unlock(&mut self, ability: Ability) {
+        match ability {
+            Ability::Fire => self.unlocked_fire = true,
+            Ability::Air => self.unlocked_air = true,
+            Ability::Water => self.unlocked_water = true,
+            Ability::Earth => self.unlocked_earth = true,
+            Ability::MagicBoots => self.unlocked_boots = true,
+            Ability::Cloak => self.unlocked_cloak = true,
+            Ability::Amulet => self.unlocked_amulet = true,
         }
-        if self.unlocked_air {
-            count += 1;
-        }
-        if self.unlocked_water {
-            count += 1;
-        }
-        count
     }
 }
 
-#[derive(Component, Eq, PartialEq)]
+#[derive(Component, Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum AnimationState {
     Idle,
     Walking,
@@ -172,19 +166,147 @@ pub fn animation_state_update(
 
 pub fn set_spawn(
     mut commands: Commands,
-    level_selection: Res<LevelSelection>,
+    mut level_selection: ResMut<LevelSelection>,
+    pending_load: Option<Res<PendingLoad>>,
+    hot_reload: Option<Res<HotReload>>,
+    progress: Res<WorldProgress>,
+    current_world: Res<CurrentWorld>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    difficulty: Res<Difficulty>,
     mut query: Query<(Entity, &mut Player, &mut Transform), Added<Player>>,
     camera: Query<Entity, With<Camera>>,
 ) {
     for (entity, mut player, mut transform) in query.iter_mut() {
-        // Note: for some reason player transform is wrong when this system runs so I've hard coded
-        // it for now
-        player.checkpoint.x = -1064.0;
-        player.checkpoint.y = 776.0;
-        player.checkpoint_level = level_selection.clone();
+        if let Some(hot_reload) = &hot_reload {
+            // The level was edited during development so carry on where the player was
+            hot_reload.player.apply(&mut player);
+            transform.translation = hot_reload.translation;
+            commands.remove_resource::<HotReload>();
+        } else {
+            if let Some(pending_load) = &pending_load {
+                pending_load.0.apply_unlocks(&mut player);
+                commands.remove_resource::<PendingLoad>();
+            }
+            if let Some(world_save) = progress.0.get(&current_world.0.id) {
+                // Continuing this world so start from its last checkpoint
+                world_save.apply(&mut player);
+                transform.translation = player.checkpoint;
+                *level_selection = player.checkpoint_level.clone();
+            } else {
+                // New to this world, `move_to_start` places the player once the start level is in
+                // place
+                if let Some((level, _)) = ldtk_assets
+                    .get(&current_world.0.level)
+                    .and_then(|ldtk_asset| find_start(&ldtk_asset.project))
+                {
+                    *level_selection = LevelSelection::Iid(level.iid.clone());
+                }
+                commands.entity(entity).insert(AwaitingStart);
+            }
+        }
         transform.translation.z = 7.0;
+        if *difficulty == Difficulty::Normal {
+            commands.entity(entity).insert(Health::new(PLAYER_HEALTH));
+        }
         for camera in camera.iter() {
             commands.entity(entity).add_child(camera);
         }
     }
 }
+
+/// A player in a world they haven't started yet, waiting for the start level to be placed
+#[derive(Component)]
+pub struct AwaitingStart;
+
+fn find_entity<'a>(
+    project: &'a LdtkJson,
+    identifier: &str,
+) -> Option<(&'a Level, &'a EntityInstance)> {
+    project.levels.iter().find_map(|level| {
+        level
+            .layer_instances
+            .iter()
+            .flatten()
+            .flat_map(|layer| layer.entity_instances.iter())
+            .find(|entity| entity.identifier == identifier)
+            .map(|entity| (level, entity))
+    })
+}
+
+/// The level holding the Player entity, which has to be spawned for the player to exist
+pub fn find_player(project: &LdtkJson) -> Option<(&Level, &EntityInstance)> {
+    find_entity(project, "Player")
+}
+
+/// Where a new game starts: the world's StartCheckpoint, or the Player entity without one
+pub fn find_start(project: &LdtkJson) -> Option<(&Level, &EntityInstance)> {
+    find_entity(project, "StartCheckpoint").or_else(|| find_player(project))
+}
+
+/// Centre of an entity relative to its level's origin, with y going up
+fn position_in_level(level: &Level, entity: &EntityInstance) -> Vec2 {
+    let size = Vec2::new(entity.width as f32, entity.height as f32);
+    Vec2::new(
+        entity.px.x as f32 + size.x * (0.5 - entity.pivot.x),
+        level.px_hei as f32 - entity.px.y as f32 - size.y * (0.5 - entity.pivot.y),
+    )
+}
+
+/// Levels only have their final translation after `LevelEvent::Transformed`, so new players are
+/// moved to the start from here rather than when they spawn
+pub fn move_to_start(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    mut transformed: Local<HashSet<String>>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut save_events: EventWriter<SaveEvent>,
+    current_world: Res<CurrentWorld>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    levels: Query<(&Handle<LdtkLevel>, &Transform), Without<Player>>,
+    mut query: Query<(Entity, &mut Player, &mut Transform), With<AwaitingStart>>,
+) {
+    for event in level_events.iter() {
+        match event {
+            LevelEvent::Transformed(iid) => {
+                transformed.insert(iid.clone());
+            }
+            LevelEvent::Despawned(iid) => {
+                transformed.remove(iid);
+            }
+            _ => {}
+        }
+    }
+    if query.is_empty() {
+        return;
+    }
+    let (start_level, start) = match ldtk_assets
+        .get(&current_world.0.level)
+        .and_then(|ldtk_asset| find_start(&ldtk_asset.project))
+    {
+        Some(start) => start,
+        None => return,
+    };
+    if !transformed.contains(&start_level.iid) {
+        return;
+    }
+    let level_transform = match levels.iter().find(|(handle, _)| {
+        ldtk_levels
+            .get(handle)
+            .map(|ldtk_level| ldtk_level.level.iid == start_level.iid)
+            .unwrap_or(false)
+    }) {
+        Some((_, transform)) => transform,
+        None => return,
+    };
+    let position = level_transform.translation.truncate() + position_in_level(start_level, start);
+    for (entity, mut player, mut transform) in query.iter_mut() {
+        transform.translation = position.extend(7.0);
+        player.checkpoint = transform.translation;
+        player.checkpoint_level = LevelSelection::Iid(start_level.iid.clone());
+        *level_selection = player.checkpoint_level.clone();
+        commands.entity(entity).remove::<AwaitingStart>();
+        // Write the new game straight away so the slot shows up as used
+        save_events.send(SaveEvent);
+    }
+}
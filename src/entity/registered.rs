@@ -0,0 +1,21 @@
+/// Identifiers of the LDtk entities registered in `level::LevelPlugin`, also read by `level-lint`
+pub const REGISTERED_ENTITIES: &[&str] = &[
+    "Player",
+    "Checkpoint",
+    "StartCheckpoint",
+    "Signpost",
+    "Ability",
+    "Goblin",
+    "FireImp",
+    "Wisp",
+    "Boss",
+    "BossArena",
+    "Block",
+    "WoodBlock",
+    "Lava",
+    "Water",
+    "Torch",
+    "Fan",
+    "AirCurrent",
+    "Trophy",
+];
@@ -1,17 +1,16 @@
 use bevy::{
     prelude::{
-        shape::Quad, Added, Assets, BuildChildren, Bundle, Children, Color, Commands, Component,
-        Entity, EventReader, Mesh, Query, Res, ResMut, Transform, Vec2, Vec3, Visibility, With,
+        shape::Quad, Added, Assets, BuildChildren, Bundle, Color, Commands, Component, Entity,
+        Mesh, Query, Res, ResMut, Transform, Vec2, Vec3, Visibility,
     },
     sprite::{ColorMaterial, ColorMesh2dBundle, SpriteBundle},
     text::{Text, Text2dBundle, TextAlignment},
 };
 use bevy_ecs_ldtk::LdtkEntity;
-use heron::CollisionEvent;
 
 use crate::{physics::PhysicsObjectBundle, state::load_game::GameAssets};
 
-use super::{player::Player, ProximityText};
+use super::{fields::FieldErrors, ProximityText};
 
 #[derive(Component, Default)]
 pub struct TextBox;
@@ -26,6 +25,8 @@ pub struct SignpostBundle {
     sprite_bundle: SpriteBundle,
     #[from_entity_instance]
     text: ProximityText,
+    #[from_entity_instance]
+    field_errors: FieldErrors,
     #[bundle]
     #[from_entity_instance]
     pub physics_bundle: PhysicsObjectBundle,
@@ -79,60 +80,3 @@ pub fn spawn_text(
         });
     }
 }
-
-pub fn check_near(
-    signposts: Query<&Children, (With<ProximityText>, With<Signpost>)>,
-    player: Query<Entity, With<Player>>,
-    mut text: Query<&mut Visibility, With<TextBox>>,
-    mut collisions: EventReader<CollisionEvent>,
-) {
-    for player_entity in player.iter() {
-        for collision in collisions.iter() {
-            // for (entity, children) in signposts.iter_mut() {
-            match collision {
-                CollisionEvent::Started(a, b) => {
-                    if a.rigid_body_entity() == player_entity {
-                        if let Ok(children) = signposts.get(b.rigid_body_entity()) {
-                            // show text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = true;
-                                }
-                            }
-                        }
-                    } else if b.rigid_body_entity() == player_entity {
-                        if let Ok(children) = signposts.get(a.rigid_body_entity()) {
-                            // show text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = true;
-                                }
-                            }
-                        }
-                    }
-                }
-                CollisionEvent::Stopped(a, b) => {
-                    if a.rigid_body_entity() == player_entity {
-                        if let Ok(children) = signposts.get(b.rigid_body_entity()) {
-                            // hide text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = false;
-                                }
-                            }
-                        }
-                    } else if b.rigid_body_entity() == player_entity {
-                        if let Ok(children) = signposts.get(a.rigid_body_entity()) {
-                            // hide text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = false;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-}
@@ -1,17 +1,37 @@
 use bevy::{
-    prelude::{Added, Bundle, Component, Query, Transform},
-    sprite::SpriteSheetBundle,
+    prelude::{Added, Bundle, Changed, Color, Component, Query, Transform},
+    sprite::{SpriteSheetBundle, TextureAtlasSprite},
 };
-use bevy_ecs_ldtk::LdtkEntity;
+use bevy_ecs_ldtk::{EntityInstance, LdtkEntity};
 
 use crate::animation::Animated;
 
-#[derive(Default, Component)]
-pub struct Torch;
+use super::fields::{FieldErrors, Fields};
+
+#[derive(Component)]
+pub struct Torch {
+    pub lit: bool,
+}
+
+impl From<EntityInstance> for Torch {
+    fn from(entity_instance: EntityInstance) -> Self {
+        Self {
+            lit: lit(&Fields::new(&entity_instance.field_instances)),
+        }
+    }
+}
+
+/// Torches placed before the `Lit` field existed are lit
+pub fn lit(fields: &Fields) -> bool {
+    fields.optional("Lit").unwrap_or(true)
+}
 
 #[derive(Bundle, LdtkEntity)]
 pub struct TorchBundle {
+    #[from_entity_instance]
     torch: Torch,
+    #[from_entity_instance]
+    field_errors: FieldErrors,
     #[bundle]
     #[sprite_sheet_bundle("sprites/torch.png", 8.0, 24.0, 12, 1, 0.0, 0.0, 0)]
     sprite_sheet_bundle: SpriteSheetBundle,
@@ -24,3 +44,19 @@ pub fn offset(mut query: Query<&mut Transform, Added<Torch>>) {
         transform.translation.y += 4.0;
     }
 }
+
+/// Unlit torches are dimmed and don't flicker
+pub fn update_lit(
+    mut query: Query<(&Torch, &mut Animated, &mut TextureAtlasSprite), Changed<Torch>>,
+) {
+    for (torch, mut animation, mut sprite) in query.iter_mut() {
+        if torch.lit {
+            animation.end = 12;
+            sprite.color = Color::WHITE;
+        } else {
+            animation.end = 1;
+            sprite.index = 0;
+            sprite.color = Color::rgb(0.3, 0.3, 0.3);
+        }
+    }
+}
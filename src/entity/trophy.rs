@@ -1,13 +1,20 @@
 use bevy::{
-    prelude::{Bundle, Children, Component, Entity, EventReader, Query, Visibility, With},
+    prelude::{Bundle, Commands, Component, EventReader, EventWriter, Res, ResMut},
     sprite::SpriteBundle,
 };
 use bevy_ecs_ldtk::LdtkEntity;
-use heron::CollisionEvent;
 
-use crate::physics::PhysicsObjectBundle;
+use crate::{
+    collision::PlayerReachedTrophy,
+    events::GameEvent,
+    physics::PhysicsObjectBundle,
+    save::{SaveEvent, WorldProgress},
+    speedrun::SpeedrunEvent,
+    state::results::ResultsTimer,
+    world::CurrentWorld,
+};
 
-use super::{player::Player, signpost::TextBox, ProximityText};
+use super::ProximityText;
 
 #[derive(Component, Default)]
 pub struct Trophy;
@@ -25,57 +32,27 @@ pub struct TrophyBundle {
     pub physics_bundle: PhysicsObjectBundle,
 }
 
-pub fn check_near(
-    trophies: Query<&Children, (With<ProximityText>, With<Trophy>)>,
-    player: Query<Entity, With<Player>>,
-    mut text: Query<&mut Visibility, With<TextBox>>,
-    mut collisions: EventReader<CollisionEvent>,
+/// Reaching a trophy completes the world it is in and leads to the results screen
+pub fn complete_world(
+    mut commands: Commands,
+    mut reached: EventReader<PlayerReachedTrophy>,
+    current_world: Res<CurrentWorld>,
+    mut progress: ResMut<WorldProgress>,
+    mut save_events: EventWriter<SaveEvent>,
+    mut game_events: EventWriter<GameEvent>,
+    mut speedrun_events: EventWriter<SpeedrunEvent>,
+    results_timer: Option<Res<ResultsTimer>>,
 ) {
-    for player_entity in player.iter() {
-        for collision in collisions.iter() {
-            match collision {
-                CollisionEvent::Started(a, b) => {
-                    if a.rigid_body_entity() == player_entity {
-                        if let Ok(children) = trophies.get(b.rigid_body_entity()) {
-                            // show text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = true;
-                                }
-                            }
-                        }
-                    } else if b.rigid_body_entity() == player_entity {
-                        if let Ok(children) = trophies.get(a.rigid_body_entity()) {
-                            // show text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = true;
-                                }
-                            }
-                        }
-                    }
-                }
-                CollisionEvent::Stopped(a, b) => {
-                    if a.rigid_body_entity() == player_entity {
-                        if let Ok(children) = trophies.get(b.rigid_body_entity()) {
-                            // hide text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = false;
-                                }
-                            }
-                        }
-                    } else if b.rigid_body_entity() == player_entity {
-                        if let Ok(children) = trophies.get(a.rigid_body_entity()) {
-                            // hide text
-                            for child in children.iter() {
-                                if let Ok(mut visibility) = text.get_mut(*child) {
-                                    visibility.is_visible = false;
-                                }
-                            }
-                        }
-                    }
-                }
+    for _ in reached.iter() {
+        speedrun_events.send(SpeedrunEvent::Finished);
+        if results_timer.is_none() {
+            commands.insert_resource(ResultsTimer::default());
+            game_events.send(GameEvent::WorldCompleted);
+        }
+        if let Some(world) = progress.0.get_mut(&current_world.0.id) {
+            if !world.completed {
+                world.completed = true;
+                save_events.send(SaveEvent);
             }
         }
     }
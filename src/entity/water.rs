@@ -1,19 +1,10 @@
 use bevy::{
-    prelude::{Bundle, Commands, Component, Entity, EventReader, Query, Res, With},
+    prelude::{Bundle, Component},
     sprite::SpriteSheetBundle,
 };
 use bevy_ecs_ldtk::LdtkEntity;
-use heron::CollisionEvent;
 
-use crate::{
-    abilities::{Element, Equipment},
-    animation::Animated,
-    damage::Killed,
-    debug::DebugSettings,
-    physics::PhysicsObjectBundle,
-};
-
-use super::player::Player;
+use crate::{animation::Animated, physics::PhysicsObjectBundle};
 
 #[derive(Component, Default)]
 pub struct Water;
@@ -30,37 +21,3 @@ pub struct WaterBundle {
     #[from_entity_instance]
     pub animated: Animated,
 }
-
-pub fn check_collision(
-    mut commands: Commands,
-    water: Query<Entity, With<Water>>,
-    player: Query<&Player>,
-    mut collision_events: EventReader<CollisionEvent>,
-    debug_settings: Res<DebugSettings>,
-) {
-    collision_events
-        .iter()
-        .filter(|e| e.is_started())
-        .filter_map(|e| {
-            let (e1, e2) = e.rigid_body_entities();
-            if let Ok(player) = player.get(e1) {
-                if water.get(e2).is_ok()
-                    && !debug_settings.imortality
-                    && !(player.has_equipt(Equipment::Cloak) && player.has_infused(Element::Water))
-                {
-                    return Some(e1);
-                }
-            } else if let Ok(player) = player.get(e2) {
-                if water.get(e1).is_ok()
-                    && !debug_settings.imortality
-                    && !(player.has_equipt(Equipment::Cloak) && player.has_infused(Element::Water))
-                {
-                    return Some(e2);
-                }
-            }
-            None
-        })
-        .for_each(|entity| {
-            commands.entity(entity).insert(Killed {});
-        });
-}
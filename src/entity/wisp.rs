@@ -0,0 +1,115 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::{Added, Bundle, Color, Component, Query, Transform, Vec2, With},
+    sprite::{SpriteSheetBundle, TextureAtlasSprite},
+};
+use bevy_ecs_ldtk::{EntityInstance, LdtkEntity};
+use heron::{Acceleration, Velocity};
+
+use crate::{
+    animation::Animated,
+    damage::Hurtbox,
+    level::GRAVITY,
+    physics::{self, Dynamic, PhysicsObjectBundle},
+};
+
+use super::{
+    enemy::EnemyStats,
+    fields::{FieldErrors, Fields},
+    goblin::Enemy,
+};
+
+/// How quickly a wisp steers back onto its path, per second
+const RESPONSE: f32 = 3.0;
+const MAX_SPEED: f32 = 40.0;
+
+/// Floats in a figure of eight around where it was placed, fans can blow it off its path
+#[derive(Component)]
+pub struct Wisp {
+    /// How far the wisp sways to either side
+    pub range: f32,
+    /// How far the wisp bobs up and down
+    pub height: f32,
+    /// Seconds for one full sway
+    pub period: f32,
+    /// Where the wisp was placed, in the same space as its `Transform`
+    pub home: Option<Vec2>,
+    /// Seconds since the wisp spawned
+    pub time: f32,
+}
+
+impl From<EntityInstance> for Wisp {
+    fn from(entity_instance: EntityInstance) -> Self {
+        let (range, height, period) = wisp_fields(&Fields::new(&entity_instance.field_instances));
+        Self {
+            range,
+            height,
+            period,
+            home: None,
+            time: 0.0,
+        }
+    }
+}
+
+/// The optional `Range`, `Height` and `Period` fields of wisps
+pub fn wisp_fields(fields: &Fields) -> (f32, f32, f32) {
+    (
+        fields.optional("Range").unwrap_or(32.0),
+        fields.optional("Height").unwrap_or(8.0),
+        fields
+            .optional_if("Period", |period: &f32| *period > 0.0)
+            .unwrap_or(4.0),
+    )
+}
+
+impl Wisp {
+    /// Offset from home along the figure of eight at `time`
+    pub fn path(&self, time: f32) -> Vec2 {
+        let angle = time / self.period * TAU;
+        Vec2::new(self.range * angle.sin(), self.height * (2.0 * angle).sin())
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct WispBundle {
+    #[bundle]
+    #[sprite_sheet_bundle("sprites/orb.png", 8.0, 8.0, 6, 1, 0.0, 0.0, 0)]
+    pub sprite_sheet_bundle: SpriteSheetBundle,
+    pub enemy: Enemy,
+    pub hurtbox: Hurtbox,
+    #[bundle]
+    #[from_entity_instance]
+    pub physics_bundle: PhysicsObjectBundle,
+    #[from_entity_instance]
+    pub animated: Animated,
+    pub acceleration: Acceleration,
+    pub dynamic: Dynamic,
+    #[from_entity_instance]
+    pub wisp: Wisp,
+    #[from_entity_instance]
+    pub stats: EnemyStats,
+    #[from_entity_instance]
+    pub field_errors: FieldErrors,
+}
+
+pub fn tint(mut query: Query<&mut TextureAtlasSprite, Added<Wisp>>) {
+    for mut sprite in query.iter_mut() {
+        sprite.color = Color::rgba(0.7, 0.9, 1.0, 0.85);
+    }
+}
+
+/// Steers wisps along their path. Only nudges the velocity so forces from fans still win. Runs in
+/// `FixedUpdateStage` so the gravity it cancels out is exactly what heron adds each step.
+pub fn fly(mut wisps: Query<(&mut Wisp, &Transform, &mut Velocity), With<Hurtbox>>) {
+    let delta = (1.0 / physics::STEPS_PER_SECOND) as f32;
+    for (mut wisp, transform, mut velocity) in wisps.iter_mut() {
+        let position = transform.translation.truncate();
+        let home = *wisp.home.get_or_insert(position);
+        wisp.time += delta;
+        let target = home + wisp.path(wisp.time);
+        let desired = ((target - position) * RESPONSE).clamp_length_max(MAX_SPEED);
+        let steering = (desired - velocity.linear.truncate()) * (RESPONSE * delta).min(1.0);
+        velocity.linear += steering.extend(0.0) - GRAVITY * delta;
+    }
+}
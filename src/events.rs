@@ -0,0 +1,18 @@
+use bevy::prelude::Entity;
+
+use crate::{abilities::Equipment, damage::DamageSource};
+
+/// Gameplay moments that statistics and achievements both listen for
+pub enum GameEvent {
+    Death(DamageSource),
+    FireballCast,
+    /// Any enemy ran out of health, goblins or not
+    EnemyKilled(Entity),
+    /// A lava tile was cooled to stone, by a water projectile or an aura
+    LavaCooled(Entity),
+    BlockPushed,
+    /// Sent when the ability menu is closed with a piece of equipment on
+    Equipped(Equipment),
+    /// The player reached a trophy
+    WorldCompleted,
+}
@@ -0,0 +1,295 @@
+use bevy::{
+    prelude::{
+        info, warn, Assets, Color, Commands, Component, DespawnRecursiveExt, Entity, Query, Res,
+        ResMut, SystemSet, Transform, Vec2, Visibility, With, Without,
+    },
+    sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::Animated,
+    entity::player::{AnimationState, Player},
+    speedrun::{Speedrun, SpeedrunSettings},
+    state::{load_game::GameAssets, State},
+    storage,
+    world::CurrentWorld,
+};
+
+/// Bump this whenever `Ghost` changes shape so old ghosts aren't misread
+const GHOST_VERSION: u32 = 1;
+/// Drawn just behind the player
+const GHOST_Z: f32 = 6.0;
+/// Seconds between recorded frames, positions in between are interpolated on playback
+const SAMPLE_SECONDS: f64 = 1.0 / 20.0;
+
+/// Records every timed run and races a translucent ghost of the personal best
+pub struct GhostPlugin;
+
+impl bevy::prelude::Plugin for GhostPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.insert_resource(GhostOverride::from_args())
+            .add_system_set(SystemSet::on_enter(State::InGame).with_system(start))
+            .add_system_set(SystemSet::on_update(State::InGame).with_system(record))
+            .add_system_set(SystemSet::on_in_stack_update(State::InGame).with_system(follow))
+            .add_system_set(SystemSet::on_exit(State::InGame).with_system(cleanup));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct GhostFrame {
+    /// Seconds since the start of the run
+    pub time: f64,
+    pub position: [f32; 2],
+    pub flip_x: bool,
+    pub animation: AnimationState,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ghost {
+    pub version: u32,
+    /// Id of the world the run was in
+    pub world: String,
+    pub total: f64,
+    pub frames: Vec<GhostFrame>,
+}
+
+impl Ghost {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let ghost = ron::from_str::<Ghost>(contents).map_err(|e| e.to_string())?;
+        if ghost.version != GHOST_VERSION {
+            return Err(format!(
+                "ghost has version {} (expected {})",
+                ghost.version, GHOST_VERSION
+            ));
+        }
+        Ok(ghost)
+    }
+
+    /// The last frame recorded at or before `time`
+    pub fn frame_at(&self, time: f64) -> Option<&GhostFrame> {
+        let next = self.frames.partition_point(|frame| frame.time <= time);
+        next.checked_sub(1).map(|index| &self.frames[index])
+    }
+
+    /// Position at `time`, interpolated between the recorded frames around it
+    pub fn position_at(&self, time: f64) -> Option<Vec2> {
+        let next = self.frames.partition_point(|frame| frame.time <= time);
+        let previous = &self.frames[next.checked_sub(1)?];
+        let position = Vec2::from_array(previous.position);
+        match self.frames.get(next) {
+            Some(next) if next.time > previous.time => {
+                let t = (time - previous.time) / (next.time - previous.time);
+                Some(position.lerp(Vec2::from_array(next.position), t as f32))
+            }
+            _ => Some(position),
+        }
+    }
+}
+
+fn ghost_key(world: &str) -> String {
+    format!("ghost_{}.ron", world)
+}
+
+fn load(world: &str) -> Option<Ghost> {
+    let contents = storage::read(&ghost_key(world))?;
+    match Ghost::parse(&contents) {
+        Ok(ghost) => Some(ghost),
+        Err(e) => {
+            warn!("Failed to load ghost for {}: {}", world, e);
+            None
+        }
+    }
+}
+
+fn store(ghost: &Ghost) {
+    // Not pretty printed since a long run still records 20 frames for every second of it
+    match ron::to_string(ghost) {
+        Ok(contents) => {
+            if let Err(e) = storage::write(&ghost_key(&ghost.world), &contents) {
+                warn!("Failed to write ghost: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize ghost: {}", e),
+    }
+}
+
+/// A ghost passed on the command line, raced instead of the personal best
+pub struct GhostOverride(pub Option<Ghost>);
+
+impl GhostOverride {
+    /// Looks for `--ghost <path>` on the command line
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_args() -> Self {
+        let mut args = std::env::args().skip_while(|arg| arg != "--ghost").skip(1);
+        let path = match args.next() {
+            Some(path) => path,
+            None => return Self(None),
+        };
+        match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Ghost::parse(&contents))
+        {
+            Ok(ghost) => {
+                info!("Racing ghost {} ({:.2}s)", path, ghost.total);
+                Self(Some(ghost))
+            }
+            Err(e) => {
+                warn!("Failed to load ghost {}: {}", path, e);
+                Self(None)
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn from_args() -> Self {
+        Self(None)
+    }
+}
+
+/// The run being recorded and the ghost being raced. Only exists while the timer is enabled.
+pub struct GhostRecorder {
+    pub frames: Vec<GhostFrame>,
+    pub racing: Option<Ghost>,
+    /// Set once the run has finished and been dealt with
+    done: bool,
+}
+
+#[derive(Component)]
+pub struct GhostSprite;
+
+fn start(
+    mut commands: Commands,
+    settings: Res<SpeedrunSettings>,
+    ghost_override: Res<GhostOverride>,
+    current_world: Res<CurrentWorld>,
+    game_assets: Res<GameAssets>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    let world = &current_world.0.id;
+    let racing = match &ghost_override.0 {
+        Some(ghost) if &ghost.world == world => Some(ghost.clone()),
+        Some(ghost) => {
+            warn!(
+                "Ghost is for world {} but {} is being played, racing the personal best instead",
+                ghost.world, world
+            );
+            load(world)
+        }
+        None => load(world),
+    };
+    if racing.is_some() {
+        let texture_atlas =
+            TextureAtlas::from_grid(game_assets.herochar.clone(), Vec2::new(16.0, 16.0), 8, 15);
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: texture_atlases.add(texture_atlas),
+                sprite: TextureAtlasSprite {
+                    index: 40,
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.4),
+                    ..Default::default()
+                },
+                visibility: Visibility { is_visible: false },
+                ..Default::default()
+            })
+            .insert(Animated::new(0.1, 40, 44, false))
+            .insert(AnimationState::default())
+            .insert(GhostSprite);
+    }
+    commands.insert_resource(GhostRecorder {
+        frames: Vec::new(),
+        racing,
+        done: false,
+    });
+}
+
+fn record(
+    recorder: Option<ResMut<GhostRecorder>>,
+    speedrun: Option<Res<Speedrun>>,
+    current_world: Res<CurrentWorld>,
+    player: Query<(&Transform, &TextureAtlasSprite, &AnimationState), With<Player>>,
+) {
+    let (mut recorder, speedrun) = match (recorder, speedrun) {
+        (Some(recorder), Some(speedrun)) => (recorder, speedrun),
+        _ => return,
+    };
+    if recorder.done {
+        return;
+    }
+    if speedrun.finished {
+        recorder.done = true;
+        if speedrun.is_personal_best() {
+            store(&Ghost {
+                version: GHOST_VERSION,
+                world: current_world.0.id.clone(),
+                total: speedrun.elapsed,
+                frames: std::mem::take(&mut recorder.frames),
+            });
+        }
+        return;
+    }
+    let due = recorder
+        .frames
+        .last()
+        .map_or(true, |last| speedrun.elapsed - last.time >= SAMPLE_SECONDS);
+    if !due {
+        return;
+    }
+    for (transform, sprite, animation) in player.iter() {
+        recorder.frames.push(GhostFrame {
+            time: speedrun.elapsed,
+            position: transform.translation.truncate().to_array(),
+            flip_x: sprite.flip_x,
+            animation: *animation,
+        });
+    }
+}
+
+fn follow(
+    recorder: Option<Res<GhostRecorder>>,
+    speedrun: Option<Res<Speedrun>>,
+    mut ghost: Query<
+        (
+            &mut Transform,
+            &mut TextureAtlasSprite,
+            &mut AnimationState,
+            &mut Visibility,
+        ),
+        (With<GhostSprite>, Without<Player>),
+    >,
+) {
+    let (recorder, speedrun) = match (recorder, speedrun) {
+        (Some(recorder), Some(speedrun)) => (recorder, speedrun),
+        _ => return,
+    };
+    let racing = match &recorder.racing {
+        Some(racing) => racing,
+        None => return,
+    };
+    let (frame, position) = match (
+        racing.frame_at(speedrun.elapsed),
+        racing.position_at(speedrun.elapsed),
+    ) {
+        (Some(frame), Some(position)) => (frame, position),
+        _ => return,
+    };
+    for (mut transform, mut sprite, mut animation, mut visibility) in ghost.iter_mut() {
+        transform.translation = position.extend(GHOST_Z);
+        sprite.flip_x = frame.flip_x;
+        // Only set on change, the animation restarts whenever the state is touched
+        if *animation != frame.animation {
+            *animation = frame.animation;
+        }
+        visibility.is_visible = true;
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<GhostSprite>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<GhostRecorder>();
+}
@@ -1,17 +1,329 @@
+use std::{collections::HashMap, fmt::Display};
+
 use bevy::{
     ecs::prelude::*,
-    input::{keyboard::KeyCode, mouse::MouseWheel, Input},
-    prelude::EventReader,
+    input::{
+        gamepad::{
+            Gamepad, GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, GamepadEvent,
+            GamepadEventType, Gamepads,
+        },
+        keyboard::KeyCode,
+        mouse::MouseWheel,
+        Axis, Input, InputSystem,
+    },
+    prelude::{info, warn, CoreStage, EventReader, Plugin},
     render::camera::{Camera, OrthographicProjection},
     time::Timer,
 };
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
+
+use crate::{debug::DebugSettings, replay::ReplayState, storage};
+
+const KEY_BINDINGS_KEY: &str = "keybindings.ron";
+/// How far a stick has to be pushed before it counts as a direction
+const STICK_THRESHOLD: f32 = 0.5;
+
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.insert_resource(KeyBindings::load())
+            .insert_resource(Input::<Action>::default())
+            .insert_resource(Input::<MenuAction>::default())
+            .insert_resource(ActiveGamepad::default())
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_connections
+                    .label(InputLabel::GamepadConnections)
+                    .after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_actions.after(InputLabel::GamepadConnections),
+            );
+    }
+}
 
-use crate::debug::DebugSettings;
+/// Everything the player can do. Systems read `Input<Action>` instead of raw key codes so the
+/// controls can be remapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Ability,
+    Interact,
+    Restart,
+    OpenInspector,
+}
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Ability,
+        Action::Interact,
+        Action::Restart,
+        Action::OpenInspector,
+    ];
+}
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::MoveLeft => write!(f, "Move left"),
+            Action::MoveRight => write!(f, "Move right"),
+            Action::Jump => write!(f, "Jump"),
+            Action::Ability => write!(f, "Use ability"),
+            Action::Interact => write!(f, "Interact"),
+            Action::Restart => write!(f, "Restart"),
+            Action::OpenInspector => write!(f, "Open inspector"),
+        }
+    }
+}
+
+/// Navigation in menus. Kept separate from `Action` so menus work the same no matter how the
+/// gameplay controls are bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+}
+
+/// The gamepad used for input, if one is connected
+#[derive(Default)]
+pub struct ActiveGamepad(pub Option<Gamepad>);
+
+/// Which keys trigger each action. Persisted so players on other keyboard layouts only have to
+/// set this up once.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyBindings(pub HashMap<Action, Vec<KeyCode>>);
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self(HashMap::from([
+            (Action::MoveLeft, vec![KeyCode::Left]),
+            (Action::MoveRight, vec![KeyCode::Right]),
+            (Action::Jump, vec![KeyCode::Up, KeyCode::Z]),
+            (Action::Ability, vec![KeyCode::X]),
+            (Action::Interact, vec![KeyCode::Down]),
+            (Action::Restart, vec![KeyCode::R]),
+            (Action::OpenInspector, vec![KeyCode::F1]),
+        ]))
+    }
+}
+
+impl KeyBindings {
+    pub fn load() -> Self {
+        storage::read(KEY_BINDINGS_KEY)
+            .and_then(|contents| match ron::from_str::<KeyBindings>(&contents) {
+                Ok(bindings) => Some(bindings),
+                Err(e) => {
+                    warn!("Failed to parse key bindings, using defaults: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                if let Err(e) = storage::write(KEY_BINDINGS_KEY, &contents) {
+                    warn!("Failed to write key bindings: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize key bindings: {}", e),
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Human readable list of the keys bound to an action, e.g. `<Up/Z>`
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .keys(action)
+            .iter()
+            .map(|key| format!("{:?}", key).to_lowercase())
+            .collect();
+        format!("<{}>", keys.join("/"))
+    }
+}
+
+/// Keeps track of which gamepad to use as gamepads are plugged in and removed
+fn gamepad_connections(
+    mut events: EventReader<GamepadEvent>,
+    gamepads: Res<Gamepads>,
+    mut active_gamepad: ResMut<ActiveGamepad>,
+) {
+    for GamepadEvent(gamepad, event_type) in events.iter() {
+        match event_type {
+            GamepadEventType::Connected => {
+                if active_gamepad.0.is_none() {
+                    info!("Using gamepad {:?}", gamepad);
+                    active_gamepad.0 = Some(*gamepad);
+                }
+            }
+            GamepadEventType::Disconnected => {
+                if active_gamepad.0 == Some(*gamepad) {
+                    // Fall back to any other gamepad that is still connected
+                    active_gamepad.0 = gamepads.iter().find(|g| *g != gamepad).copied();
+                    if let Some(gamepad) = active_gamepad.0 {
+                        info!("Switched to gamepad {:?}", gamepad);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Snapshot of the active gamepad for one frame
+struct GamepadState {
+    buttons: Vec<GamepadButtonType>,
+    stick_x: f32,
+    stick_y: f32,
+}
+impl GamepadState {
+    fn read(
+        gamepad: Option<Gamepad>,
+        buttons: &Input<GamepadButton>,
+        axes: &Axis<GamepadAxis>,
+    ) -> Self {
+        let gamepad = match gamepad {
+            Some(gamepad) => gamepad,
+            None => {
+                return Self {
+                    buttons: Vec::new(),
+                    stick_x: 0.0,
+                    stick_y: 0.0,
+                }
+            }
+        };
+        let axis = |axis_type| axes.get(GamepadAxis(gamepad, axis_type)).unwrap_or(0.0);
+        Self {
+            buttons: buttons
+                .get_pressed()
+                .filter(|button| button.0 == gamepad)
+                .map(|button| button.1)
+                .collect(),
+            // Some drivers report the d-pad as an axis rather than buttons
+            stick_x: axis(GamepadAxisType::LeftStickX) + axis(GamepadAxisType::DPadX),
+            stick_y: axis(GamepadAxisType::LeftStickY) + axis(GamepadAxisType::DPadY),
+        }
+    }
+
+    fn pressed(&self, button: GamepadButtonType) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    fn left(&self) -> bool {
+        self.pressed(GamepadButtonType::DPadLeft) || self.stick_x < -STICK_THRESHOLD
+    }
+
+    fn right(&self) -> bool {
+        self.pressed(GamepadButtonType::DPadRight) || self.stick_x > STICK_THRESHOLD
+    }
+
+    fn up(&self) -> bool {
+        self.pressed(GamepadButtonType::DPadUp) || self.stick_y > STICK_THRESHOLD
+    }
+
+    fn down(&self) -> bool {
+        self.pressed(GamepadButtonType::DPadDown) || self.stick_y < -STICK_THRESHOLD
+    }
+
+    fn action_pressed(&self, action: Action) -> bool {
+        match action {
+            Action::MoveLeft => self.left(),
+            Action::MoveRight => self.right(),
+            Action::Jump => self.pressed(GamepadButtonType::South),
+            Action::Ability => {
+                self.pressed(GamepadButtonType::West)
+                    || self.pressed(GamepadButtonType::RightTrigger)
+            }
+            Action::Interact => {
+                self.pressed(GamepadButtonType::North) || self.pressed(GamepadButtonType::DPadDown)
+            }
+            Action::Restart => self.pressed(GamepadButtonType::Select),
+            Action::OpenInspector => false,
+        }
+    }
+
+    fn menu_action_pressed(&self, action: MenuAction) -> bool {
+        match action {
+            MenuAction::Up => self.up(),
+            MenuAction::Down => self.down(),
+            MenuAction::Left => self.left(),
+            MenuAction::Right => self.right(),
+            MenuAction::Confirm => self.pressed(GamepadButtonType::South),
+            MenuAction::Back => self.pressed(GamepadButtonType::East),
+        }
+    }
+}
+
+fn menu_keys(action: MenuAction) -> &'static [KeyCode] {
+    match action {
+        MenuAction::Up => &[KeyCode::Up],
+        MenuAction::Down => &[KeyCode::Down],
+        MenuAction::Left => &[KeyCode::Left],
+        MenuAction::Right => &[KeyCode::Right],
+        MenuAction::Confirm => &[KeyCode::Z, KeyCode::Return, KeyCode::Space],
+        MenuAction::Back => &[KeyCode::X, KeyCode::Escape],
+    }
+}
+
+/// Translates raw keyboard and gamepad input into actions using the current bindings
+fn update_actions(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    active_gamepad: Res<ActiveGamepad>,
+    bindings: Res<KeyBindings>,
+    mut actions: ResMut<Input<Action>>,
+    mut menu_actions: ResMut<Input<MenuAction>>,
+) {
+    let gamepad = GamepadState::read(active_gamepad.0, &gamepad_buttons, &gamepad_axes);
+
+    actions.clear();
+    for action in Action::ALL {
+        if keyboard_input.any_pressed(bindings.keys(action).iter().copied())
+            || gamepad.action_pressed(action)
+        {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+
+    menu_actions.clear();
+    for action in [
+        MenuAction::Up,
+        MenuAction::Down,
+        MenuAction::Left,
+        MenuAction::Right,
+        MenuAction::Confirm,
+        MenuAction::Back,
+    ] {
+        if keyboard_input.any_pressed(menu_keys(action).iter().copied())
+            || gamepad.menu_action_pressed(action)
+        {
+            menu_actions.press(action);
+        } else {
+            menu_actions.release(action);
+        }
+    }
+}
 
 #[derive(PartialEq, Eq, Debug, Hash, Clone, SystemLabel)]
 pub enum InputLabel {
     ControllableUpdate,
+    GamepadConnections,
 }
 
 #[derive(Component, Debug, Inspectable)]
@@ -50,42 +362,26 @@ impl Controllable {
 }
 
 pub fn system(
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     debug_settings: Res<DebugSettings>,
+    replay_state: Res<ReplayState>,
     mut query: Query<&mut Controllable>,
     mut mouse_wheel_events: EventReader<MouseWheel>,
     mut camera_query: Query<(&mut OrthographicProjection, &Camera)>,
 ) {
     for mut c in query.iter_mut() {
-        if keyboard_input.pressed(KeyCode::Left) {
-            c.left = true;
-        } else {
-            c.left = false;
-        }
-
-        if keyboard_input.pressed(KeyCode::Right) {
-            c.right = true;
-        } else {
-            c.right = false;
-        }
-
-        if keyboard_input.just_pressed(KeyCode::Up) || keyboard_input.just_pressed(KeyCode::Z) {
-            c.jumping = true;
-        } else {
-            c.jumping = false;
-        }
-
-        if keyboard_input.pressed(KeyCode::X) {
-            c.ability = true;
-        } else {
-            c.ability = false;
-        }
-
-        if keyboard_input.just_pressed(KeyCode::Down) {
-            c.interacting = true;
-        } else {
+        if replay_state.is_playing() {
+            // The replay sets everything else on each movement step
             c.interacting = false;
+            continue;
         }
+        c.left = actions.pressed(Action::MoveLeft);
+        c.right = actions.pressed(Action::MoveRight);
+        // Movement runs on a fixed step which might not happen this frame, so hold on to the
+        // jump until it does
+        c.jumping |= actions.just_pressed(Action::Jump);
+        c.ability = actions.pressed(Action::Ability);
+        c.interacting = actions.just_pressed(Action::Interact);
     }
 
     for event in mouse_wheel_events.iter() {
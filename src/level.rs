@@ -1,13 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
 use bevy::prelude::{
-    Added, AssetServer, Assets, BuildChildren, Bundle, Commands, Component, Entity, EventReader,
-    GlobalTransform, Handle, Image, Input, KeyCode, Parent, Plugin, Query, Res, ResMut, SystemSet,
-    Transform, Vec2, Vec3, With, Without,
+    warn, Added, AssetServer, Assets, BuildChildren, Bundle, Changed, Commands, Component, Entity,
+    EventReader, EventWriter, GlobalTransform, Handle, IVec2, Image, Input, Parent, Plugin, Query,
+    RemovedComponents, Res, ResMut, SystemSet, Transform, Vec2, Vec3, With, Without,
 };
 use bevy_ecs_ldtk::{
     prelude::{LayerInstance, RegisterLdtkObjects},
-    GridCoords, LdtkIntCell, LdtkLevel, LdtkSettings, LevelEvent, LevelSelection,
+    EntityInstance, GridCoords, LdtkIntCell, LdtkLevel, LdtkSettings, LevelEvent, LevelSelection,
     LevelSpawnBehavior, Respawn,
 };
 use heron::{CollisionLayers, CollisionShape, Gravity, PhysicMaterial, PhysicsTime, RigidBody};
@@ -17,20 +17,39 @@ use crate::{
     entity::{
         ability::AbilityBundle,
         block::{BlockBundle, WoodBlockBundle},
-        checkpoint::CheckpointBundle,
+        boss::{BossArenaBundle, BossBundle},
+        checkpoint::{CheckpointBundle, StartCheckpointBundle},
         fan::{AirCurrentBundle, FanBundle},
+        fields,
+        fire_imp::FireImpBundle,
         goblin::GoblinBundle,
         lava::LavaBundle,
-        player::{Player, PlayerBundle},
+        player::{AwaitingStart, Player, PlayerBundle},
+        registered::REGISTERED_ENTITIES,
         signpost::SignpostBundle,
         torch::TorchBundle,
         trophy::TrophyBundle,
         water::WaterBundle,
+        wisp::WispBundle,
     },
+    input::Action,
     physics::{Dynamic, GroundDetector, PhysicsLayers},
+    save::SaveData,
+    speedrun::SpeedrunEvent,
     state::State,
 };
 
+#[cfg(feature = "dev")]
+use crate::world::CurrentWorld;
+#[cfg(feature = "dev")]
+use bevy::prelude::{info, AssetEvent, Camera, Children, CoreStage};
+#[cfg(feature = "dev")]
+use bevy_ecs_ldtk::LdtkAsset;
+
+/// Size of a tile in every layer of the LDtk project
+pub const GRID_SIZE: f32 = 16.0;
+pub const GRAVITY: Vec3 = Vec3::new(0.0, -500.0, 0.0);
+
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
@@ -42,12 +61,16 @@ impl Plugin for LevelPlugin {
                 },
                 ..Default::default()
             })
-            .insert_resource(Gravity::from(Vec3::new(0.0, -500.0, 0.0)))
+            .insert_resource(Gravity::from(GRAVITY))
+            .init_resource::<WallTiles>()
             .add_startup_system(prevent_asset_unloading)
             .add_system_set(
                 SystemSet::on_update(State::InGame)
                     .with_system(spawn_wall_collision)
+                    .with_system(update_wall_tiles)
                     .with_system(spawn_spike_collision)
+                    .with_system(fields::report_invalid)
+                    .with_system(warn_unregistered)
                     .with_system(update_level_selection)
                     .with_system(pause_physics_during_load)
                     .with_system(restart_level),
@@ -56,9 +79,14 @@ impl Plugin for LevelPlugin {
             .register_ldtk_int_cell::<SpikeBundle>(2)
             .register_ldtk_entity::<PlayerBundle>("Player")
             .register_ldtk_entity::<CheckpointBundle>("Checkpoint")
+            .register_ldtk_entity::<StartCheckpointBundle>("StartCheckpoint")
             .register_ldtk_entity::<SignpostBundle>("Signpost")
             .register_ldtk_entity::<AbilityBundle>("Ability")
             .register_ldtk_entity::<GoblinBundle>("Goblin")
+            .register_ldtk_entity::<FireImpBundle>("FireImp")
+            .register_ldtk_entity::<WispBundle>("Wisp")
+            .register_ldtk_entity::<BossBundle>("Boss")
+            .register_ldtk_entity::<BossArenaBundle>("BossArena")
             .register_ldtk_entity::<BlockBundle>("Block")
             .register_ldtk_entity::<WoodBlockBundle>("WoodBlock")
             .register_ldtk_entity::<LavaBundle>("Lava")
@@ -67,15 +95,72 @@ impl Plugin for LevelPlugin {
             .register_ldtk_entity::<FanBundle>("Fan")
             .register_ldtk_entity::<AirCurrentBundle>("AirCurrent")
             .register_ldtk_entity::<TrophyBundle>("Trophy");
+        // Runs before bevy_ecs_ldtk sees the modified project
+        #[cfg(feature = "dev")]
+        app.add_system_to_stage(CoreStage::First, prepare_hot_reload);
+    }
+}
+
+/// Entities without a registered bundle are spawned as bare transforms, so nothing shows up
+fn warn_unregistered(query: Query<&EntityInstance, Added<EntityInstance>>) {
+    for entity_instance in query.iter() {
+        if !REGISTERED_ENTITIES.contains(&entity_instance.identifier.as_str()) {
+            warn!(
+                "LDtk entity {} ({}) is not registered in LevelPlugin",
+                entity_instance.identifier, entity_instance.iid
+            );
+        }
+    }
+}
+
+/// Player state carried over when the LDtk project is reloaded. Applied when the player respawns.
+pub struct HotReload {
+    pub player: SaveData,
+    pub translation: Vec3,
+}
+
+/// bevy_ecs_ldtk respawns the whole world when the project changes on disk, player included. This
+/// remembers where the player was and keeps the camera out of the despawn.
+#[cfg(feature = "dev")]
+fn prepare_hot_reload(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<LdtkAsset>>,
+    current_world: Option<Res<CurrentWorld>>,
+    player: Query<(Entity, &Player, &Transform, Option<&Children>)>,
+    cameras: Query<(), With<Camera>>,
+) {
+    let current_world = match current_world {
+        Some(current_world) => current_world,
+        None => return,
+    };
+    let modified = events.iter().any(|event| {
+        matches!(event, AssetEvent::Modified { handle } if *handle == current_world.0.level)
+    });
+    if !modified {
+        return;
+    }
+    for (entity, player, transform, children) in player.iter() {
+        info!("{} changed on disk, reloading", current_world.0.path);
+        commands.insert_resource(HotReload {
+            player: SaveData::snapshot(player, &current_world.0.id),
+            translation: transform.translation,
+        });
+        for child in children.iter().flat_map(|children| children.iter()) {
+            if cameras.contains(*child) {
+                commands.entity(entity).remove_children(&[*child]);
+            }
+        }
     }
 }
 
 /// This function was copied from the example in bevy_ecs_ldtk. All credit goes to the author
 fn update_level_selection(
     level_query: Query<(&Handle<LdtkLevel>, &Transform), Without<Player>>,
-    player_query: Query<&Transform, With<Player>>,
+    // A player waiting for the start level isn't in its real position yet
+    player_query: Query<&Transform, (With<Player>, Without<AwaitingStart>)>,
     mut level_selection: ResMut<LevelSelection>,
     ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut speedrun_events: EventWriter<SpeedrunEvent>,
 ) {
     for (level_handle, level_transform) in level_query.iter() {
         if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
@@ -87,13 +172,21 @@ fn update_level_selection(
                 ),
             };
 
+            let is_selected = matches!(
+                &*level_selection,
+                LevelSelection::Iid(iid) if *iid == ldtk_level.level.iid
+            );
             for player_transform in player_query.iter() {
                 if player_transform.translation.x < level_bounds.max.x
                     && player_transform.translation.x > level_bounds.min.x
                     && player_transform.translation.y < level_bounds.max.y
                     && player_transform.translation.y > level_bounds.min.y
+                    && !is_selected
                 {
                     *level_selection = LevelSelection::Iid(ldtk_level.level.iid.clone());
+                    // Moving into another level splits the speedrun timer
+                    let identifier = ldtk_level.level.identifier.clone();
+                    speedrun_events.send(SpeedrunEvent::Split(identifier));
                 }
             }
         }
@@ -101,13 +194,52 @@ fn update_level_selection(
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
-struct Wall;
+pub struct Wall;
 
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 struct WallBundle {
     wall: Wall,
 }
 
+/// Every wall tile in the loaded levels, by world grid position. Wall colliders are merged into
+/// rectangles, so this is what enemies look at to find walls and ledges.
+#[derive(Default)]
+pub struct WallTiles(pub HashSet<IVec2>);
+
+impl WallTiles {
+    fn tile(position: Vec2) -> IVec2 {
+        (position / GRID_SIZE).floor().as_ivec2()
+    }
+
+    pub fn is_wall(&self, position: Vec2) -> bool {
+        self.0.contains(&Self::tile(position))
+    }
+
+    /// Whether a straight line between the two points crosses no wall tiles
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let steps = (from.distance(to) / (GRID_SIZE / 4.0)).ceil() as usize;
+        (0..=steps).all(|step| {
+            let position = from.lerp(to, step as f32 / steps.max(1) as f32);
+            !self.is_wall(position)
+        })
+    }
+}
+
+fn update_wall_tiles(
+    walls: Query<&GlobalTransform, With<Wall>>,
+    changed: Query<(), (With<Wall>, Changed<GlobalTransform>)>,
+    removed: RemovedComponents<Wall>,
+    mut wall_tiles: ResMut<WallTiles>,
+) {
+    if changed.is_empty() && removed.iter().next().is_none() {
+        return;
+    }
+    wall_tiles.0 = walls
+        .iter()
+        .map(|transform| WallTiles::tile(transform.translation().truncate()))
+        .collect();
+}
+
 /// This function was copied from the example in bevy_ecs_ldtk. All credit goes to the author
 /// Spawns heron collisions for the walls of a level
 ///
@@ -303,9 +435,9 @@ fn restart_level(
     mut level_selection: ResMut<LevelSelection>,
     mut detectors: Query<&mut GroundDetector>,
     mut movables: Query<&mut Dynamic>,
-    input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
 ) {
-    if input.just_pressed(KeyCode::R) {
+    if actions.just_pressed(Action::Restart) {
         for (mut transform, player) in player_query.iter_mut() {
             transform.translation = player.checkpoint;
             transform.translation.z = 7.0;
@@ -336,7 +468,7 @@ fn prevent_asset_unloading(mut commands: Commands, asset_server: Res<AssetServer
 }
 
 #[derive(Component, Default)]
-struct Spike;
+pub struct Spike;
 
 #[derive(Bundle, LdtkIntCell)]
 struct SpikeBundle {
@@ -460,7 +592,8 @@ fn spawn_spike_collision(
                                         .with_mask(PhysicsLayers::PlayerBody),
                                 )
                                 .insert(GlobalTransform::default())
-                                .insert(Hurtbox);
+                                .insert(Hurtbox)
+                                .insert(Spike);
                         }
                     }
                 });
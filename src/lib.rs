@@ -0,0 +1,28 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub mod abilities;
+pub mod achievements;
+pub mod animation;
+pub mod audio;
+pub mod aura;
+pub mod boss;
+pub mod camera;
+pub mod collision;
+pub mod combinations;
+pub mod damage;
+pub mod debug;
+pub mod destruction;
+pub mod entity;
+pub mod events;
+pub mod ghost;
+pub mod input;
+pub mod level;
+pub mod physics;
+pub mod replay;
+pub mod save;
+pub mod speedrun;
+pub mod state;
+pub mod stats;
+pub mod storage;
+#[cfg(test)]
+mod tests;
+pub mod world;
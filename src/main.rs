@@ -1,5 +1,5 @@
-#![allow(clippy::type_complexity, clippy::too_many_arguments)]
-use audio::{MusicChannel, SoundChannel};
+#[cfg(feature = "dev")]
+use bevy::asset::AssetServerSettings;
 use bevy::{
     prelude::{App, ClearColor, Color},
     render::texture::ImageSettings,
@@ -9,41 +9,68 @@ use bevy::{
 
 use bevy_ecs_ldtk::LdtkPlugin;
 use bevy_kira_audio::{AudioApp, AudioPlugin};
-use debug::DebugPlugin;
-
-use heron::PhysicsPlugin;
-use level::LevelPlugin;
-use state::{ability_menu::AbilityMenuPlugin, game::GamePlugin, main_menu::MainMenuPlugin, State};
-
-mod abilities;
-mod animation;
-mod audio;
-mod camera;
-mod damage;
-mod debug;
-mod destruction;
-mod entity;
-mod input;
-mod level;
-mod physics;
-mod state;
+use elemental_sorcerer::{
+    achievements::AchievementsPlugin,
+    audio::{MusicChannel, SoundChannel},
+    boss::BossPlugin,
+    combinations::CombinationsPlugin,
+    debug::DebugPlugin,
+    ghost::GhostPlugin,
+    input::InputMapPlugin,
+    level::LevelPlugin,
+    physics,
+    replay::ReplayPlugin,
+    save::SavePlugin,
+    speedrun::SpeedrunPlugin,
+    state::{
+        ability_menu::AbilityMenuPlugin, controls::ControlsMenuPlugin, game::GamePlugin,
+        level_select::LevelSelectPlugin, main_menu::MainMenuPlugin, results::ResultsPlugin,
+        slot_select::SlotSelectPlugin, State,
+    },
+    stats::StatsPlugin,
+    world::WorldsPlugin,
+};
+use heron::{PhysicsPlugin, PhysicsSteps};
 
 fn main() {
     // When building for WASM, print panics to the browser console
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
-    App::new()
-        .insert_resource(ImageSettings::default_nearest())
+    let mut app = App::new();
+    // Pick up changes to assets, levels saved from LDtk in particular, without restarting
+    #[cfg(feature = "dev")]
+    app.insert_resource(AssetServerSettings {
+        watch_for_changes: true,
+        ..Default::default()
+    });
+    app.insert_resource(ImageSettings::default_nearest())
         .add_state(State::LoadMenu)
         .add_plugins(DefaultPlugins)
         .add_plugin(MainMenuPlugin)
+        .add_plugin(SlotSelectPlugin)
+        .add_plugin(LevelSelectPlugin)
+        .add_plugin(ControlsMenuPlugin)
         .add_plugin(AbilityMenuPlugin)
+        .add_plugin(ResultsPlugin)
         .add_plugin(GamePlugin)
+        .add_plugin(CombinationsPlugin)
+        .add_plugin(WorldsPlugin)
+        .add_plugin(InputMapPlugin)
         .add_plugin(LevelPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(SpeedrunPlugin)
+        .add_plugin(GhostPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(AchievementsPlugin)
+        .add_plugin(BossPlugin)
         .add_plugin(DebugPlugin)
         .add_plugin(LdtkPlugin)
         .add_plugin(PhysicsPlugin::default())
+        .insert_resource(PhysicsSteps::from_steps_per_seconds(
+            physics::STEPS_PER_SECOND as f32,
+        ))
         .add_plugin(AudioPlugin)
         .add_audio_channel::<MusicChannel>()
         .add_audio_channel::<SoundChannel>()
@@ -1,24 +1,26 @@
 use std::time::Duration;
 
 use bevy::{
+    ecs::schedule::ShouldRun,
     prelude::{
         Added, Assets, BuildChildren, Bundle, Children, Commands, Component, Entity, EventReader,
-        GlobalTransform, Query, Res, ResMut, SystemLabel, Transform, Vec2, Vec3,
+        GlobalTransform, Query, Res, ResMut, StageLabel, SystemLabel, Transform, Vec2, Vec3,
     },
     sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
-    time::{Time, Timer},
+    time::Timer,
 };
 
 use bevy_kira_audio::{AudioChannel, AudioControl};
 use heron::{
-    CollisionEvent, CollisionLayers, CollisionShape, PhysicMaterial, PhysicsLayer, RigidBody,
-    RotationConstraints, Velocity,
+    CollisionEvent, CollisionLayers, CollisionShape, PhysicMaterial, PhysicsLayer, PhysicsSteps,
+    RigidBody, RotationConstraints, Velocity,
 };
 
 use crate::{
-    abilities::{Element, Equipment},
+    abilities::GroundPound,
     animation::Animated,
     audio::{AudioAssets, SoundChannel},
+    combinations::Combinations,
     debug::DebugSettings,
     destruction::DestructionTimer,
     entity::player::{AnimationState, Player},
@@ -26,6 +28,23 @@ use crate::{
     state::load_game::GameAssets,
 };
 
+/// Player movement and the physics engine both step at this rate so replays reproduce exactly
+pub const STEPS_PER_SECOND: f64 = 60.0;
+
+/// Runs after `CoreStage::Update` on every frame heron steps, see `on_physics_step`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct FixedUpdateStage;
+
+/// Run criteria of `FixedUpdateStage`. Follows heron's own step timer instead of keeping a second
+/// clock, so every physics step sees exactly one movement step.
+pub fn on_physics_step(physics_steps: Res<PhysicsSteps>) -> ShouldRun {
+    if physics_steps.is_step_frame() {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
 #[derive(SystemLabel)]
 pub enum PhysicsLabel {
     HandleControllables,
@@ -88,34 +107,38 @@ pub struct Dynamic {
     pub direction: Direction,
 }
 
-/// This system handles the movement of controllable entities
+/// This system handles the movement of controllable entities. It runs in `FixedUpdateStage`.
 pub fn handle_controllables(
-    time: Res<Time>,
     mut commands: Commands,
     mut query: Query<(
+        Entity,
         &mut Velocity,
-        &Controllable,
+        &mut Controllable,
         &Player,
         &mut AnimationState,
         &mut TextureAtlasSprite,
         &Transform,
         &Children,
+        Option<&GroundPound>,
     )>,
     mut ground_detectors: Query<&mut GroundDetector>,
     game_assets: Res<GameAssets>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     debug_settings: Res<DebugSettings>,
+    combinations: Res<Combinations>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
     for (
+        entity,
         mut velocity,
-        controllable,
+        mut controllable,
         player,
         mut animation,
         mut texture_atlas,
         transform,
         children,
+        ground_pound,
     ) in query.iter_mut()
     {
         let Controllable {
@@ -127,14 +150,19 @@ pub fn handle_controllables(
             acceleration,
             ..
         } = *controllable;
+        let delta_seconds = (1.0 / STEPS_PER_SECOND) as f32;
+        let movement = combinations.movement(player.combination);
 
         // get the ground detector to see if the player is grounded
         for &child in children.iter() {
             if let Ok(mut detector) = ground_detectors.get_mut(child) {
+                // Ticked here rather than in `check_grounded` so it counts steps, not frames
+                detector
+                    .coyote_timer
+                    .tick(Duration::from_secs_f32(delta_seconds));
                 if jumping && (!detector.coyote_timer.finished() || debug_settings.flying) {
-                    if player.has_equipt(Equipment::MagicBoots) && player.has_infused(Element::Fire)
-                    {
-                        velocity.linear.y = jump_velocity * 1.3;
+                    velocity.linear.y = jump_velocity * movement.jump_multiplier;
+                    if movement.explosive_jump {
                         let texture_handle = game_assets.explosion.clone();
                         let texture_atlas =
                             TextureAtlas::from_grid(texture_handle, Vec2::new(32.0, 32.0), 10, 1);
@@ -152,16 +180,11 @@ pub fn handle_controllables(
                             .insert(DestructionTimer(Timer::from_seconds(0.5, false)));
                         sound_channel.play(audio_assets.explosion.clone());
                     } else {
-                        velocity.linear.y = jump_velocity;
                         sound_channel.play(audio_assets.jump.clone());
                     }
                     // run out the timer
                     detector.coyote_timer.tick(Duration::from_secs(10.0 as u64));
-                } else if jumping
-                    && detector.has_double_jump
-                    && player.has_equipt(Equipment::MagicBoots)
-                    && player.has_infused(Element::Air)
-                {
+                } else if jumping && detector.has_double_jump && movement.double_jump {
                     // Double jump
                     velocity.linear.y = jump_velocity;
                     detector.has_double_jump = false;
@@ -181,6 +204,17 @@ pub fn handle_controllables(
                         .insert(Animated::new(0.1, 0, 3, true))
                         .insert(DestructionTimer(Timer::from_seconds(0.3, false)));
                     sound_channel.play(audio_assets.air.clone());
+                } else if jumping
+                    && movement.ground_pound
+                    && ground_pound.is_none()
+                    && !detector.is_grounded
+                {
+                    velocity.linear.y = -jump_velocity * 1.5;
+                    commands.entity(entity).insert(GroundPound);
+                    sound_channel.play(audio_assets.air.clone());
+                }
+                if detector.is_grounded && ground_pound.is_some() {
+                    commands.entity(entity).remove::<GroundPound>();
                 }
 
                 let acceleration = if detector.is_grounded {
@@ -189,28 +223,22 @@ pub fn handle_controllables(
                     acceleration / 2.0
                 };
 
-                let max_speed = if player.has_equipt(Equipment::MagicBoots)
-                    && player.has_infused(Element::Water)
-                {
-                    max_speed * 2.0
-                } else {
-                    max_speed
-                };
+                let max_speed = max_speed * movement.speed_multiplier;
 
                 if right && !left {
                     if velocity.linear.x < max_speed {
-                        let delta = acceleration * time.delta_seconds();
+                        let delta = acceleration * delta_seconds;
                         velocity.linear.x += delta;
                     }
                     texture_atlas.flip_x = false;
                 } else if left && !right {
                     if velocity.linear.x > -max_speed {
-                        let delta = acceleration * time.delta_seconds();
+                        let delta = acceleration * delta_seconds;
                         velocity.linear.x -= delta;
                     }
                     texture_atlas.flip_x = true;
                 } else if velocity.linear.x != 0.0 {
-                    let delta = acceleration * time.delta_seconds();
+                    let delta = acceleration * delta_seconds;
 
                     if velocity.linear.x < 0.0 {
                         velocity.linear.x = (velocity.linear.x + delta).min(0.0)
@@ -235,6 +263,11 @@ pub fn handle_controllables(
                 }
             }
         }
+        if let Some(max_fall_speed) = movement.max_fall_speed {
+            velocity.linear.y = velocity.linear.y.max(-max_fall_speed);
+        }
+        // Jumps are held by `input::system` until a step has used them
+        controllable.jumping = false;
     }
 }
 
@@ -282,12 +315,10 @@ pub fn add_ground_sensor(mut commands: Commands, query: Query<Entity, Added<Play
 }
 
 pub fn check_grounded(
-    time: Res<Time>,
     mut detectors: Query<(Entity, &mut GroundDetector)>,
     mut collisions: EventReader<CollisionEvent>,
 ) {
     for (entity, mut ground_detector) in detectors.iter_mut() {
-        ground_detector.coyote_timer.tick(time.delta());
         for collision in collisions.iter() {
             match collision {
                 CollisionEvent::Started(a, b) => {
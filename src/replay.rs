@@ -0,0 +1,286 @@
+use bevy::prelude::{
+    info, warn, ParallelSystemDescriptorCoercion, Plugin, Query, Res, ResMut, SystemSet, Transform,
+    Vec3,
+};
+use bevy_ecs_ldtk::LevelSelection;
+use heron::Velocity;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    abilities::{Element, Equipment},
+    debug::DebugSettings,
+    entity::player::Player,
+    input::{Controllable, InputLabel},
+    save::SaveData,
+    state::State,
+    storage,
+    world::CurrentWorld,
+};
+
+/// Bump this whenever `Replay` changes shape so old recordings aren't misread
+const REPLAY_VERSION: u32 = 2;
+const REPLAY_KEY: &str = "replay.ron";
+
+/// Records player input and plays it back in place of the keyboard, to reproduce bugs
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.insert_resource(ReplayState::from_args())
+            .add_system_set(
+                SystemSet::on_update(State::InGame)
+                    .with_system(toggle_recording.after(InputLabel::ControllableUpdate)),
+            )
+            .add_system_set(SystemSet::on_exit(State::InGame).with_system(stop));
+    }
+}
+
+/// Everything that is read from the player's `Controllable` and `Player` each step
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ReplayFrame {
+    pub left: bool,
+    pub right: bool,
+    pub jumping: bool,
+    pub ability: bool,
+    pub interacting: bool,
+    /// Changed through the ability menu, which the replay can't drive
+    pub combination: (Option<Equipment>, Option<Element>),
+}
+
+impl ReplayFrame {
+    fn read(controllable: &Controllable, player: &Player) -> Self {
+        Self {
+            left: controllable.left,
+            right: controllable.right,
+            jumping: controllable.jumping,
+            ability: controllable.ability,
+            interacting: controllable.interacting,
+            combination: player.combination,
+        }
+    }
+
+    fn write(&self, controllable: &mut Controllable, player: &mut Player) {
+        controllable.left = self.left;
+        controllable.right = self.right;
+        controllable.jumping = self.jumping;
+        controllable.ability = self.ability;
+        controllable.interacting = self.interacting;
+        player.combination = self.combination;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Replay {
+    pub version: u32,
+    pub start: [f32; 3],
+    pub start_velocity: [f32; 3],
+    pub player: SaveData,
+    /// Only steps where the input changed are stored, paired with the step they happened on
+    pub frames: Vec<(u32, ReplayFrame)>,
+    /// Number of steps recorded
+    pub length: u32,
+}
+
+impl Replay {
+    fn new(player: &Player, world: &str, transform: &Transform, velocity: &Velocity) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            start: transform.translation.to_array(),
+            start_velocity: velocity.linear.to_array(),
+            player: SaveData::snapshot(player, world),
+            frames: Vec::new(),
+            length: 0,
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let replay = ron::from_str::<Replay>(contents).map_err(|e| e.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "replay has version {} (expected {})",
+                replay.version, REPLAY_VERSION
+            ));
+        }
+        Ok(replay)
+    }
+}
+
+pub enum ReplayState {
+    Idle,
+    Recording {
+        replay: Replay,
+        step: u32,
+        /// `interacting` only lasts a single frame so it's held here until the next step
+        interacted: bool,
+    },
+    Playing {
+        replay: Replay,
+        step: u32,
+        cursor: usize,
+        current: ReplayFrame,
+        started: bool,
+    },
+}
+
+impl ReplayState {
+    pub fn playing(replay: Replay) -> Self {
+        Self::Playing {
+            replay,
+            step: 0,
+            cursor: 0,
+            current: ReplayFrame::default(),
+            started: false,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self, Self::Playing { .. })
+    }
+
+    /// Looks for `--replay <path>` on the command line
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_args() -> Self {
+        let mut args = std::env::args().skip_while(|arg| arg != "--replay").skip(1);
+        let path = match args.next() {
+            Some(path) => path,
+            None => return Self::Idle,
+        };
+        match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| Replay::parse(&contents))
+        {
+            Ok(replay) => {
+                info!("Playing replay {} ({} steps)", path, replay.length);
+                Self::playing(replay)
+            }
+            Err(e) => {
+                warn!("Failed to load replay {}: {}", path, e);
+                Self::Idle
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn from_args() -> Self {
+        Self::Idle
+    }
+}
+
+fn save(replay: &Replay) {
+    match ron::ser::to_string_pretty(replay, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => match storage::write(REPLAY_KEY, &contents) {
+            Ok(()) => info!("Saved replay of {} steps to {}", replay.length, REPLAY_KEY),
+            Err(e) => warn!("Failed to write replay: {}", e),
+        },
+        Err(e) => warn!("Failed to serialize replay: {}", e),
+    }
+}
+
+/// Starts and stops recording when the debug setting is changed, and holds on to interactions
+/// until the next step records them
+pub fn toggle_recording(
+    mut replay_state: ResMut<ReplayState>,
+    debug_settings: Res<DebugSettings>,
+    current_world: Res<CurrentWorld>,
+    query: Query<(&Controllable, &Player, &Transform, &Velocity)>,
+) {
+    match &mut *replay_state {
+        ReplayState::Idle if debug_settings.record_replay => {
+            if let Some((_, player, transform, velocity)) = query.iter().next() {
+                info!("Recording replay");
+                *replay_state = ReplayState::Recording {
+                    replay: Replay::new(player, &current_world.0.id, transform, velocity),
+                    step: 0,
+                    interacted: false,
+                };
+            }
+        }
+        ReplayState::Recording { replay, .. } if !debug_settings.record_replay => {
+            save(replay);
+            *replay_state = ReplayState::Idle;
+        }
+        ReplayState::Recording { interacted, .. } => {
+            for (controllable, ..) in query.iter() {
+                *interacted |= controllable.interacting;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Runs on the fixed movement step, before the player is moved. Records the current input or
+/// replaces it with the recorded one.
+pub fn step(
+    mut replay_state: ResMut<ReplayState>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut query: Query<(
+        &mut Controllable,
+        &mut Player,
+        &mut Transform,
+        &mut Velocity,
+    )>,
+) {
+    let mut finished = false;
+    match &mut *replay_state {
+        ReplayState::Idle => {}
+        ReplayState::Recording {
+            replay,
+            step,
+            interacted,
+        } => {
+            for (controllable, player, ..) in query.iter() {
+                let mut frame = ReplayFrame::read(controllable, player);
+                frame.interacting |= *interacted;
+                if replay.frames.last().map(|(_, last)| *last) != Some(frame) {
+                    replay.frames.push((*step, frame));
+                }
+            }
+            *interacted = false;
+            *step += 1;
+            replay.length = *step;
+        }
+        ReplayState::Playing {
+            replay,
+            step,
+            cursor,
+            current,
+            started,
+        } => {
+            for (mut controllable, mut player, mut transform, mut velocity) in query.iter_mut() {
+                if !*started {
+                    replay.player.apply(&mut player);
+                    transform.translation = Vec3::from_array(replay.start);
+                    velocity.linear = Vec3::from_array(replay.start_velocity);
+                    *level_selection = player.checkpoint_level.clone();
+                    *started = true;
+                }
+                while let Some((frame_step, frame)) = replay.frames.get(*cursor) {
+                    if *frame_step > *step {
+                        break;
+                    }
+                    *current = *frame;
+                    *cursor += 1;
+                }
+                current.write(&mut controllable, &mut player);
+                // Interactions only last one step, the same as when they were recorded
+                current.interacting = false;
+            }
+            if *started {
+                *step += 1;
+                finished = *step >= replay.length;
+            }
+        }
+    }
+    if finished {
+        info!("Replay finished");
+        *replay_state = ReplayState::Idle;
+    }
+}
+
+fn stop(mut replay_state: ResMut<ReplayState>, mut debug_settings: ResMut<DebugSettings>) {
+    if let ReplayState::Recording { replay, .. } = &*replay_state {
+        save(replay);
+    }
+    *replay_state = ReplayState::Idle;
+    debug_settings.record_replay = false;
+}
@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::{
+        warn, Assets, Commands, EventReader, Handle, Plugin, Query, Res, ResMut, SystemSet, Vec3,
+    },
+    time::Time,
+};
+use bevy_ecs_ldtk::{LdtkLevel, LevelSelection};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    abilities::{Element, Equipment},
+    entity::player::Player,
+    state::State,
+    storage,
+    world::CurrentWorld,
+};
+
+/// Bump this whenever `SaveData` changes shape so old saves aren't misread
+pub const SAVE_VERSION: u32 = 3;
+pub const NUM_SLOTS: usize = 3;
+const LAST_SLOT_KEY: &str = "last_slot";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_event::<SaveEvent>().add_system_set(
+            SystemSet::on_update(State::InGame)
+                .with_system(write_on_checkpoint)
+                .with_system(track_play_time),
+        );
+    }
+}
+
+/// Sent whenever the player's progress should be written to disk
+pub struct SaveEvent;
+
+/// Inserted by the main menu when continuing a game. Applied to the player when it spawns.
+pub struct PendingLoad(pub SaveData);
+
+/// The save slot the current game is written to
+pub struct CurrentSlot(pub usize);
+
+/// Progress through every world in the current slot, keyed by world id
+#[derive(Default)]
+pub struct WorldProgress(pub HashMap<String, WorldSave>);
+
+/// Total time spent in game for the current slot, in seconds
+#[derive(Default)]
+pub struct PlayTime(pub f64);
+
+/// Mirror of `LevelSelection` that can be serialized
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum SavedLevel {
+    Identifier(String),
+    Index(usize),
+    Iid(String),
+    Uid(i32),
+}
+impl From<&LevelSelection> for SavedLevel {
+    fn from(selection: &LevelSelection) -> Self {
+        match selection {
+            LevelSelection::Identifier(identifier) => Self::Identifier(identifier.clone()),
+            LevelSelection::Index(index) => Self::Index(*index),
+            LevelSelection::Iid(iid) => Self::Iid(iid.clone()),
+            LevelSelection::Uid(uid) => Self::Uid(*uid),
+        }
+    }
+}
+impl From<&SavedLevel> for LevelSelection {
+    fn from(level: &SavedLevel) -> Self {
+        match level {
+            SavedLevel::Identifier(identifier) => Self::Identifier(identifier.clone()),
+            SavedLevel::Index(index) => Self::Index(*index),
+            SavedLevel::Iid(iid) => Self::Iid(iid.clone()),
+            SavedLevel::Uid(uid) => Self::Uid(*uid),
+        }
+    }
+}
+
+/// Progress through one world. Worlds that haven't been started have no entry.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorldSave {
+    pub checkpoint: [f32; 3],
+    pub checkpoint_level: SavedLevel,
+    /// Identifier of the checkpoint level, used for display in the slot picker
+    pub level_identifier: String,
+    /// Set once the world's trophy has been reached
+    pub completed: bool,
+}
+
+impl WorldSave {
+    pub fn from_player(player: &Player, level_identifier: String, completed: bool) -> Self {
+        Self {
+            checkpoint: player.checkpoint.to_array(),
+            checkpoint_level: (&player.checkpoint_level).into(),
+            level_identifier,
+            completed,
+        }
+    }
+
+    /// Move the player's checkpoint to this world's
+    pub fn apply(&self, player: &mut Player) {
+        player.checkpoint = Vec3::from_array(self.checkpoint);
+        player.checkpoint_level = (&self.checkpoint_level).into();
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveData {
+    pub version: u32,
+    /// Id of the world that was played last
+    pub world: String,
+    /// Keyed by world id
+    pub worlds: HashMap<String, WorldSave>,
+    pub unlocked_fire: bool,
+    pub unlocked_air: bool,
+    pub unlocked_water: bool,
+    pub unlocked_earth: bool,
+    pub unlocked_boots: bool,
+    pub unlocked_cloak: bool,
+    pub unlocked_amulet: bool,
+    pub combination: (Option<Equipment>, Option<Element>),
+    pub play_time: f64,
+}
+
+impl SaveData {
+    pub fn from_player(
+        player: &Player,
+        world: String,
+        worlds: HashMap<String, WorldSave>,
+        play_time: f64,
+    ) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            world,
+            worlds,
+            unlocked_fire: player.unlocked_fire,
+            unlocked_air: player.unlocked_air,
+            unlocked_water: player.unlocked_water,
+            unlocked_earth: player.unlocked_earth,
+            unlocked_boots: player.unlocked_boots,
+            unlocked_cloak: player.unlocked_cloak,
+            unlocked_amulet: player.unlocked_amulet,
+            combination: player.combination,
+            play_time,
+        }
+    }
+
+    /// The player's current state in a single world, without touching the save slot
+    pub fn snapshot(player: &Player, world: &str) -> Self {
+        let worlds = HashMap::from([(
+            world.to_string(),
+            WorldSave::from_player(player, String::new(), false),
+        )]);
+        Self::from_player(player, world.to_string(), worlds, 0.0)
+    }
+
+    /// Copy the saved unlocks onto a freshly spawned player
+    pub fn apply_unlocks(&self, player: &mut Player) {
+        player.unlocked_fire = self.unlocked_fire;
+        player.unlocked_air = self.unlocked_air;
+        player.unlocked_water = self.unlocked_water;
+        player.unlocked_earth = self.unlocked_earth;
+        player.unlocked_boots = self.unlocked_boots;
+        player.unlocked_cloak = self.unlocked_cloak;
+        player.unlocked_amulet = self.unlocked_amulet;
+        player.combination = self.combination;
+    }
+
+    /// Copy the saved progress onto a freshly spawned player, starting from the checkpoint in the
+    /// last played world
+    pub fn apply(&self, player: &mut Player) {
+        self.apply_unlocks(player);
+        if let Some(world) = self.worlds.get(&self.world) {
+            world.apply(player);
+        }
+    }
+
+    /// Names of everything that has been unlocked, for display in the slot picker
+    pub fn unlocked_abilities(&self) -> Vec<&'static str> {
+        let mut unlocked = Vec::new();
+        if self.unlocked_fire {
+            unlocked.push("Fire");
+        }
+        if self.unlocked_air {
+            unlocked.push("Air");
+        }
+        if self.unlocked_water {
+            unlocked.push("Water");
+        }
+        if self.unlocked_earth {
+            unlocked.push("Earth");
+        }
+        if self.unlocked_boots {
+            unlocked.push("Boots");
+        }
+        if self.unlocked_cloak {
+            unlocked.push("Cloak");
+        }
+        if self.unlocked_amulet {
+            unlocked.push("Amulet");
+        }
+        unlocked
+    }
+}
+
+fn slot_key(slot: usize) -> String {
+    format!("save_{}.ron", slot)
+}
+
+/// Read the save file for a slot. Returns `None` if the slot is empty or can't be used.
+pub fn load(slot: usize) -> Option<SaveData> {
+    let contents = storage::read(&slot_key(slot))?;
+    match ron::from_str::<SaveData>(&contents) {
+        Ok(data) if data.version == SAVE_VERSION => Some(data),
+        Ok(data) => {
+            warn!(
+                "Ignoring save slot {} with version {} (expected {})",
+                slot, data.version, SAVE_VERSION
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Failed to parse save slot {}: {}", slot, e);
+            None
+        }
+    }
+}
+
+pub fn store(slot: usize, data: &SaveData) {
+    match ron::ser::to_string_pretty(data, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => {
+            if let Err(e) = storage::write(&slot_key(slot), &contents) {
+                warn!("Failed to write save slot {}: {}", slot, e);
+            }
+            if let Err(e) = storage::write(LAST_SLOT_KEY, &slot.to_string()) {
+                warn!("Failed to record last used save slot: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize save data: {}", e),
+    }
+}
+
+pub fn delete(slot: usize) {
+    if let Err(e) = storage::remove(&slot_key(slot)) {
+        warn!("Failed to delete save slot {}: {}", slot, e);
+    }
+}
+
+/// The most recently saved slot, if it still has a usable save
+pub fn last_slot() -> Option<(usize, SaveData)> {
+    let slot = storage::read(LAST_SLOT_KEY)?.trim().parse().ok()?;
+    load(slot).map(|data| (slot, data))
+}
+
+/// Set up the resources needed to start playing in a slot. Passing `None` starts a new game.
+pub fn start_game(commands: &mut Commands, slot: usize, data: Option<SaveData>) {
+    commands.insert_resource(CurrentSlot(slot));
+    match data {
+        Some(data) => {
+            commands.insert_resource(PlayTime(data.play_time));
+            commands.insert_resource(WorldProgress(data.worlds.clone()));
+            commands.insert_resource(PendingLoad(data));
+        }
+        None => {
+            commands.insert_resource(PlayTime::default());
+            commands.insert_resource(WorldProgress::default());
+            commands.remove_resource::<PendingLoad>();
+        }
+    }
+}
+
+fn write_on_checkpoint(
+    mut events: EventReader<SaveEvent>,
+    player: Query<&Player>,
+    slot: Option<Res<CurrentSlot>>,
+    play_time: Res<PlayTime>,
+    current_world: Res<CurrentWorld>,
+    mut progress: ResMut<WorldProgress>,
+    level_query: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+) {
+    if events.iter().last().is_none() {
+        return;
+    }
+    let slot = match slot {
+        Some(slot) => slot.0,
+        None => {
+            warn!("Tried to save without a save slot selected");
+            return;
+        }
+    };
+    for player in player.iter() {
+        let level_identifier = level_query
+            .iter()
+            .filter_map(|handle| ldtk_levels.get(handle))
+            .find(|ldtk_level| match &player.checkpoint_level {
+                LevelSelection::Iid(iid) => &ldtk_level.level.iid == iid,
+                LevelSelection::Identifier(identifier) => {
+                    &ldtk_level.level.identifier == identifier
+                }
+                LevelSelection::Uid(uid) => &ldtk_level.level.uid == uid,
+                LevelSelection::Index(_) => false,
+            })
+            .map(|ldtk_level| ldtk_level.level.identifier.clone())
+            .unwrap_or_default();
+        let world = &current_world.0.id;
+        let completed = progress
+            .0
+            .get(world)
+            .map(|world| world.completed)
+            .unwrap_or(false);
+        progress.0.insert(
+            world.clone(),
+            WorldSave::from_player(player, level_identifier, completed),
+        );
+        store(
+            slot,
+            &SaveData::from_player(player, world.clone(), progress.0.clone(), play_time.0),
+        );
+    }
+}
+
+fn track_play_time(time: Res<Time>, mut play_time: ResMut<PlayTime>) {
+    play_time.0 += time.delta_seconds_f64();
+}
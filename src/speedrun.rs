@@ -0,0 +1,279 @@
+use bevy::{
+    prelude::{
+        warn, BuildChildren, Children, Color, Commands, Component, DespawnRecursiveExt, Entity,
+        EventReader, NodeBundle, Plugin, Query, Res, ResMut, SystemSet, TextBundle, With,
+    },
+    text::{Text, TextStyle},
+    time::Time,
+    ui::{PositionType, Style, UiRect, Val},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::{load_game::GameAssets, State},
+    storage,
+    world::CurrentWorld,
+};
+
+/// Number of splits shown in the overlay, older ones scroll off the top
+const VISIBLE_SPLITS: usize = 6;
+
+/// Times runs from entering a world to reaching a trophy, split on levels and ability orbs
+pub struct SpeedrunPlugin;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.insert_resource(SpeedrunSettings::from_args())
+            .add_system_set(SystemSet::on_enter(State::InGame).with_system(start))
+            .add_system_set(
+                // Keeps running while the ability menu is open on top of the game
+                SystemSet::on_in_stack_update(State::InGame)
+                    .with_system(tick)
+                    .with_system(update_overlay),
+            )
+            .add_system_set(SystemSet::on_exit(State::InGame).with_system(cleanup));
+    }
+}
+
+pub struct SpeedrunSettings {
+    pub enabled: bool,
+}
+
+impl SpeedrunSettings {
+    /// `--speedrun` only sets the initial value, the main menu can still toggle it
+    pub fn from_args() -> Self {
+        Self {
+            enabled: std::env::args().any(|arg| arg == "--speedrun"),
+        }
+    }
+}
+
+/// Sent by the systems that detect the moments a run is split on
+pub enum SpeedrunEvent {
+    Split(String),
+    Finished,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Split {
+    pub name: String,
+    /// Seconds since the start of the run
+    pub time: f64,
+}
+
+/// A finished run, stored as the personal best for a world
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Run {
+    pub splits: Vec<Split>,
+    pub total: f64,
+}
+
+/// The run in progress. Only exists while the timer is enabled.
+pub struct Speedrun {
+    pub elapsed: f64,
+    pub splits: Vec<Split>,
+    pub finished: bool,
+    /// Personal best at the start of the run
+    pub best: Option<Run>,
+}
+
+impl Speedrun {
+    /// Difference to the same split in the personal best, negative when ahead. Splits are
+    /// matched by name so taking a different route doesn't throw the comparison off. Levels can
+    /// be entered more than once, so the n-th visit is compared with the n-th visit.
+    pub fn delta(&self, index: usize) -> Option<f64> {
+        let split = self.splits.get(index)?;
+        let visit = self.splits[..index]
+            .iter()
+            .filter(|previous| previous.name == split.name)
+            .count();
+        let best = self
+            .best
+            .as_ref()?
+            .splits
+            .iter()
+            .filter(|best| best.name == split.name)
+            .nth(visit)?;
+        Some(split.time - best.time)
+    }
+
+    /// Whether the run has finished faster than the personal best it was compared against
+    pub fn is_personal_best(&self) -> bool {
+        self.finished
+            && self
+                .best
+                .as_ref()
+                .map(|best| self.elapsed < best.total)
+                .unwrap_or(true)
+    }
+}
+
+fn best_key(world: &str) -> String {
+    format!("speedrun_{}.ron", world)
+}
+
+fn load_best(world: &str) -> Option<Run> {
+    let contents = storage::read(&best_key(world))?;
+    match ron::from_str::<Run>(&contents) {
+        Ok(run) => Some(run),
+        Err(e) => {
+            warn!("Failed to parse personal best for {}: {}", world, e);
+            None
+        }
+    }
+}
+
+fn store_best(world: &str, run: &Run) {
+    match ron::ser::to_string_pretty(run, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => {
+            if let Err(e) = storage::write(&best_key(world), &contents) {
+                warn!("Failed to write personal best: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize personal best: {}", e),
+    }
+}
+
+/// `m:ss.cc`
+fn format_time(seconds: f64) -> String {
+    let centis = (seconds * 100.0) as u64;
+    format!(
+        "{}:{:02}.{:02}",
+        centis / 6000,
+        (centis / 100) % 60,
+        centis % 100
+    )
+}
+
+fn format_delta(delta: f64) -> String {
+    let sign = if delta < 0.0 { '-' } else { '+' };
+    format!("{}{:.2}", sign, delta.abs())
+}
+
+#[derive(Component)]
+struct SpeedrunOverlay;
+
+fn start(
+    mut commands: Commands,
+    settings: Res<SpeedrunSettings>,
+    current_world: Res<CurrentWorld>,
+    game_assets: Res<GameAssets>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    commands.insert_resource(Speedrun {
+        elapsed: 0.0,
+        splits: Vec::new(),
+        finished: false,
+        best: load_best(&current_world.0.id),
+    });
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..Default::default()
+                },
+                padding: UiRect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..Default::default()
+        })
+        .insert(SpeedrunOverlay)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: game_assets.pixel_font.clone(),
+                    font_size: 12.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn tick(
+    time: Res<Time>,
+    speedrun: Option<ResMut<Speedrun>>,
+    mut events: EventReader<SpeedrunEvent>,
+    current_world: Res<CurrentWorld>,
+) {
+    let mut speedrun = match speedrun {
+        Some(speedrun) => speedrun,
+        None => {
+            events.clear();
+            return;
+        }
+    };
+    if speedrun.finished {
+        events.clear();
+        return;
+    }
+    speedrun.elapsed += time.delta_seconds_f64();
+    for event in events.iter() {
+        let time = speedrun.elapsed;
+        match event {
+            SpeedrunEvent::Split(name) => speedrun.splits.push(Split {
+                name: name.clone(),
+                time,
+            }),
+            SpeedrunEvent::Finished => {
+                speedrun.splits.push(Split {
+                    name: "Trophy".to_string(),
+                    time,
+                });
+                speedrun.finished = true;
+                if speedrun.is_personal_best() {
+                    store_best(
+                        &current_world.0.id,
+                        &Run {
+                            splits: speedrun.splits.clone(),
+                            total: time,
+                        },
+                    );
+                }
+                break;
+            }
+        }
+    }
+}
+
+fn update_overlay(
+    speedrun: Option<Res<Speedrun>>,
+    overlay: Query<&Children, With<SpeedrunOverlay>>,
+    mut text: Query<&mut Text>,
+) {
+    let speedrun = match speedrun {
+        Some(speedrun) => speedrun,
+        None => return,
+    };
+    let mut value = format_time(speedrun.elapsed);
+    if let Some(best) = &speedrun.best {
+        value += &format!("\nBest {}", format_time(best.total));
+    }
+    let first = speedrun.splits.len().saturating_sub(VISIBLE_SPLITS);
+    for (index, split) in speedrun.splits.iter().enumerate().skip(first) {
+        value += &format!("\n{:<12} {}", split.name, format_time(split.time));
+        if let Some(delta) = speedrun.delta(index) {
+            value += &format!(" {}", format_delta(delta));
+        }
+    }
+    for children in overlay.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = text.get_mut(*child) {
+                text.sections[0].value = value.clone();
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<SpeedrunOverlay>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Speedrun>();
+}
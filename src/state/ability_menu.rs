@@ -1,8 +1,8 @@
 use bevy::{
     prelude::{
         BuildChildren, Button, ButtonBundle, Changed, Color, Commands, Component,
-        DespawnRecursiveExt, Entity, Input, KeyCode, NodeBundle, Plugin, Query, Res, ResMut,
-        SystemSet, TextBundle, With, Without,
+        DespawnRecursiveExt, Entity, EventWriter, Input, KeyCode, NodeBundle, Plugin, Query, Res,
+        ResMut, SystemSet, TextBundle, With, Without,
     },
     text::{Text, TextStyle},
     ui::{
@@ -15,9 +15,11 @@ use heron::PhysicsTime;
 use crate::{
     abilities::{Element, Equipment},
     audio::{AudioAssets, SoundChannel},
+    combinations::Combinations,
     debug::DebugSettings,
     entity::player::Player,
-    input::Controllable,
+    events::GameEvent,
+    input::{Action, Controllable, KeyBindings, MenuAction},
 };
 
 use super::{load_game::GameAssets, State};
@@ -33,7 +35,7 @@ impl Plugin for AbilityMenuPlugin {
                     .with_system(trigger_leave)
                     .with_system(button_interaction_system)
                     .with_system(button_mouse_select)
-                    .with_system(button_keyboard_select)
+                    .with_system(button_navigation_select)
                     .with_system(update_text),
             );
     }
@@ -80,15 +82,22 @@ fn trigger_enter(
 fn setup(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
+    combinations: Res<Combinations>,
     player: Query<&Player>,
     debug_settings: Res<DebugSettings>,
+    key_bindings: Res<KeyBindings>,
     mut input: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<Action>>,
+    mut menu_actions: ResMut<Input<MenuAction>>,
 ) {
     let player = player
         .get_single()
         .expect("There should only be one player");
 
-    input.clear(); // clear any `just_pressed` events that may be left over from previous state
+    // clear any `just_pressed` events that may be left over from previous state
+    input.clear();
+    actions.clear();
+    menu_actions.clear();
 
     commands.insert_resource(AbilityMenuState::default());
 
@@ -124,7 +133,7 @@ fn setup(
             parent
                 .spawn_bundle(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Px(1000.0), Val::Px(500.0)),
+                        size: Size::new(Val::Px(1000.0), Val::Px(585.0)),
                         justify_content: JustifyContent::FlexStart,
                         align_items: AlignItems::Center,
                         flex_direction: FlexDirection::ColumnReverse,
@@ -266,6 +275,29 @@ fn setup(
                                                 ));
                                             });
                                     }
+                                    // Amulet button
+                                    if player.unlocked_amulet || debug_settings.unlock_all_abilities
+                                    {
+                                        parent
+                                            .spawn_bundle(ButtonBundle {
+                                                style: button_style.clone(),
+                                                color: Color::rgb(0.15, 0.15, 0.15).into(),
+                                                image: game_assets.button.clone().into(),
+                                                ..Default::default()
+                                            })
+                                            .insert(Equipment::Amulet)
+                                            .insert(BtnGridPos::new(3, 0))
+                                            .with_children(|parent| {
+                                                parent.spawn_bundle(TextBundle::from_section(
+                                                    "Amulet",
+                                                    TextStyle {
+                                                        font: game_assets.pixel_font.clone(),
+                                                        font_size: 20.0,
+                                                        color: Color::WHITE,
+                                                    },
+                                                ));
+                                            });
+                                    }
                                 });
 
                             // Element buttons wrapper
@@ -347,7 +379,7 @@ fn setup(
                                     {
                                         parent
                                             .spawn_bundle(ButtonBundle {
-                                                style: button_style,
+                                                style: button_style.clone(),
                                                 color: Color::rgb(0.15, 0.15, 0.15).into(),
                                                 image: game_assets.button.clone().into(),
                                                 ..Default::default()
@@ -365,6 +397,30 @@ fn setup(
                                                 ));
                                             });
                                     }
+
+                                    // Earth button
+                                    if player.unlocked_earth || debug_settings.unlock_all_abilities
+                                    {
+                                        parent
+                                            .spawn_bundle(ButtonBundle {
+                                                style: button_style,
+                                                color: Color::rgb(0.15, 0.15, 0.15).into(),
+                                                image: game_assets.button.clone().into(),
+                                                ..Default::default()
+                                            })
+                                            .insert(Element::Earth)
+                                            .insert(BtnGridPos::new(3, 1))
+                                            .with_children(|parent| {
+                                                parent.spawn_bundle(TextBundle::from_section(
+                                                    "Earth",
+                                                    TextStyle {
+                                                        font: game_assets.pixel_font.clone(),
+                                                        font_size: 20.0,
+                                                        color: Color::WHITE,
+                                                    },
+                                                ));
+                                            });
+                                    }
                                 });
                         });
 
@@ -411,7 +467,7 @@ fn setup(
                         .with_children(|parent| {
                             parent
                                 .spawn_bundle(TextBundle::from_section(
-                                    player.get_combination_description(),
+                                    combinations.description(player.combination, &key_bindings),
                                     TextStyle {
                                         font: game_assets.pixel_font.clone(),
                                         font_size: 20.0,
@@ -440,7 +496,10 @@ fn setup(
                         })
                         .with_children(|parent| {
                             parent.spawn_bundle(TextBundle::from_section(
-                                "Use arrow keys & <z> to select. Press <x> when done",
+                                format!(
+                                    "Use arrow keys & <z> (or d-pad & A) to select. Press {} when done",
+                                    key_bindings.describe(Action::Ability)
+                                ),
                                 TextStyle {
                                     font: game_assets.pixel_font.clone(),
                                     font_size: 15.0,
@@ -541,54 +600,60 @@ fn button_mouse_select(
     }
 }
 
-fn button_keyboard_select(
+fn button_navigation_select(
     element_button_query: Query<(&Element, &BtnGridPos)>,
     equipment_button_query: Query<(&Equipment, &BtnGridPos)>,
     mut state: ResMut<AbilityMenuState>,
     mut player_query: Query<&mut Player>,
-    keyboard_input: Res<Input<KeyCode>>,
+    menu_actions: Res<Input<MenuAction>>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
     let mut player = player_query
         .get_single_mut()
         .expect("There should only be one player");
-    if keyboard_input.just_pressed(KeyCode::Down) {
+    // Rows of the buttons in each column, which leave out anything not unlocked yet
+    let mut columns = [Vec::new(), Vec::new()];
+    for (_, grid_pos) in equipment_button_query.iter() {
+        columns[0].push(grid_pos.row);
+    }
+    for (_, grid_pos) in element_button_query.iter() {
+        columns[1].push(grid_pos.row);
+    }
+    for rows in columns.iter_mut() {
+        rows.sort_unstable();
+    }
+    let rows = &columns[state.selected_pos.col as usize];
+    let index = rows.iter().position(|row| *row == state.selected_pos.row);
+    if menu_actions.just_pressed(MenuAction::Down) && !rows.is_empty() {
         sound_channel.play(audio_assets.blip1.clone());
-        state.selected_pos.row += 1;
-        if state.selected_pos.col == 0 {
-            if state.selected_pos.row >= player.num_equipment() {
-                state.selected_pos.row = 0;
-            }
-        } else if state.selected_pos.col == 1 && state.selected_pos.row >= player.num_elements() {
-            state.selected_pos.row = 0;
-        }
+        let index = index.map_or(0, |index| (index + 1) % rows.len());
+        state.selected_pos.row = rows[index];
     }
-    if keyboard_input.just_pressed(KeyCode::Up) {
+    if menu_actions.just_pressed(MenuAction::Up) && !rows.is_empty() {
         sound_channel.play(audio_assets.blip1.clone());
-        if state.selected_pos.col == 0 {
-            if state.selected_pos.row == 0 {
-                state.selected_pos.row = player.num_equipment() - 1;
-            } else {
-                state.selected_pos.row -= 1;
-            }
-        } else if state.selected_pos.col == 1 && state.selected_pos.row == 0 {
-            state.selected_pos.row = player.num_elements() - 1;
-        } else {
-            state.selected_pos.row -= 1;
-        }
+        let index = index.map_or(0, |index| (index + rows.len() - 1) % rows.len());
+        state.selected_pos.row = rows[index];
     }
-    if (keyboard_input.just_pressed(KeyCode::Left) || keyboard_input.just_pressed(KeyCode::Right))
-        && player.num_elements() != 0
+    let other_col = 1 - state.selected_pos.col;
+    let other_rows = &columns[other_col as usize];
+    if (menu_actions.just_pressed(MenuAction::Left) || menu_actions.just_pressed(MenuAction::Right))
+        && !other_rows.is_empty()
     {
         sound_channel.play(audio_assets.blip1.clone());
-        if state.selected_pos.col == 0 {
-            state.selected_pos.col = 1;
-        } else {
-            state.selected_pos.col = 0;
-        }
+        // The columns can have a different number of rows, so stay as close to this one as possible
+        let row = state.selected_pos.row;
+        state.selected_pos = BtnGridPos::new(
+            other_rows
+                .iter()
+                .rev()
+                .find(|other| **other <= row)
+                .copied()
+                .unwrap_or(other_rows[0]),
+            other_col,
+        );
     }
-    if keyboard_input.just_pressed(KeyCode::Z) {
+    if menu_actions.just_pressed(MenuAction::Confirm) {
         sound_channel.play(audio_assets.blip2.clone());
         for (element, grid_pos) in element_button_query.iter() {
             if *grid_pos == state.selected_pos {
@@ -607,6 +672,8 @@ fn button_keyboard_select(
 
 fn update_text(
     player_query: Query<&Player, Changed<Player>>,
+    combinations: Res<Combinations>,
+    key_bindings: Res<KeyBindings>,
     mut slot_1: Query<
         &mut Text,
         (
@@ -648,7 +715,7 @@ fn update_text(
             }
         }
         for mut text in combined.iter_mut() {
-            text.sections[0].value = player.get_combination_description().to_string();
+            text.sections[0].value = combinations.description(player.combination, &key_bindings);
         }
     }
 }
@@ -656,17 +723,28 @@ fn update_text(
 fn trigger_leave(
     mut commands: Commands,
     mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut actions: ResMut<Input<Action>>,
+    mut menu_actions: ResMut<Input<MenuAction>>,
     mut app_state: ResMut<bevy::prelude::State<State>>,
     mut physics_time: ResMut<PhysicsTime>,
     root_node: Query<Entity, With<UiRootNode>>,
+    player: Query<&Player>,
+    mut game_events: EventWriter<GameEvent>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::X) {
+    if actions.just_pressed(Action::Ability) || menu_actions.just_pressed(MenuAction::Back) {
         physics_time.set_scale(1.0);
+        for player in player.iter() {
+            if let Some(equipment) = player.combination.0 {
+                game_events.send(GameEvent::Equipped(equipment));
+            }
+        }
         commands.remove_resource::<AbilityMenuState>();
         for entity in root_node.iter() {
             commands.entity(entity).despawn_recursive();
         }
         keyboard_input.clear();
+        actions.clear();
+        menu_actions.clear();
         app_state.pop().unwrap();
     }
 }
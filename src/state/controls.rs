@@ -0,0 +1,301 @@
+use bevy::{
+    prelude::{
+        BuildChildren, Button, ButtonBundle, Changed, Color, Commands, Component,
+        DespawnRecursiveExt, Entity, Input, KeyCode, NodeBundle, ParallelSystemDescriptorCoercion,
+        Plugin, Query, Res, ResMut, SystemSet, TextBundle, With,
+    },
+    text::{Text, TextStyle},
+    ui::{AlignItems, FlexDirection, Interaction, JustifyContent, Size, Style, UiRect, Val},
+};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+
+use crate::{
+    audio::{AudioAssets, SoundChannel},
+    input::{Action, KeyBindings, MenuAction},
+};
+
+use super::{
+    load_menu::MenuAssets,
+    menu::{self, FocusIndex, MenuFocus},
+    State,
+};
+
+pub struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system_set(SystemSet::on_enter(State::Controls).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(State::Controls)
+                    .with_system(button_system)
+                    .with_system(menu::button_color_system)
+                    .with_system(rebind.after(button_system))
+                    .with_system(update_text),
+            )
+            .add_system_set(SystemSet::on_exit(State::Controls).with_system(cleanup));
+    }
+}
+
+#[derive(Component)]
+struct ControlsMenu;
+
+#[derive(Component, Clone, Copy)]
+enum ControlsButton {
+    Rebind(Action),
+    Reset,
+    Back,
+}
+
+/// Text showing the keys currently bound to an action
+#[derive(Component)]
+struct BindingText(Action);
+
+/// The action waiting for a key press, if any
+#[derive(Default)]
+struct Rebinding(Option<Action>);
+
+fn setup(mut commands: Commands, menu_assets: Res<MenuAssets>, key_bindings: Res<KeyBindings>) {
+    commands.insert_resource(Rebinding::default());
+    commands.insert_resource(MenuFocus::default());
+
+    let button_style = Style {
+        size: Size::new(Val::Px(195.0), Val::Px(40.0)),
+        margin: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..Default::default()
+    };
+    let text_style = TextStyle {
+        font: menu_assets.pixel_font.clone(),
+        font_size: 15.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                padding: UiRect::all(Val::Px(20.)),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            image: menu_assets.background.clone().into(),
+            ..Default::default()
+        })
+        .insert(ControlsMenu)
+        .with_children(|parent| {
+            // Bindings wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(700.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        flex_direction: FlexDirection::ColumnReverse,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        "Select an action then press a key to rebind it",
+                        text_style.clone(),
+                    ));
+                    for (index, action) in Action::ALL.into_iter().enumerate() {
+                        // Action row
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(100.0), Val::Auto),
+                                    justify_content: JustifyContent::SpaceBetween,
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                color: Color::NONE.into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(ButtonBundle {
+                                        style: button_style.clone(),
+                                        image: menu_assets.button.clone().into(),
+                                        color: Color::rgb(0.15, 0.15, 0.15).into(),
+                                        ..Default::default()
+                                    })
+                                    .insert(ControlsButton::Rebind(action))
+                                    .insert(FocusIndex(index))
+                                    .with_children(|parent| {
+                                        parent.spawn_bundle(TextBundle::from_section(
+                                            action.to_string(),
+                                            text_style.clone(),
+                                        ));
+                                    });
+                                parent
+                                    .spawn_bundle(TextBundle::from_section(
+                                        key_bindings.describe(action),
+                                        text_style.clone(),
+                                    ))
+                                    .insert(BindingText(action));
+                            });
+                    }
+                });
+            // Buttons wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(700.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(ButtonBundle {
+                            style: button_style.clone(),
+                            image: menu_assets.button.clone().into(),
+                            color: Color::rgb(0.15, 0.15, 0.15).into(),
+                            ..Default::default()
+                        })
+                        .insert(ControlsButton::Reset)
+                        .insert(FocusIndex(Action::ALL.len()))
+                        .with_children(|parent| {
+                            parent.spawn_bundle(TextBundle::from_section(
+                                "Reset",
+                                text_style.clone(),
+                            ));
+                        });
+                    parent
+                        .spawn_bundle(ButtonBundle {
+                            style: button_style.clone(),
+                            image: menu_assets.button.clone().into(),
+                            color: Color::rgb(0.15, 0.15, 0.15).into(),
+                            ..Default::default()
+                        })
+                        .insert(ControlsButton::Back)
+                        .insert(FocusIndex(Action::ALL.len() + 1))
+                        .with_children(|parent| {
+                            parent.spawn_bundle(TextBundle::from_section("Back", text_style));
+                        });
+                });
+        });
+}
+
+fn button_system(
+    interaction_query: Query<
+        (&ControlsButton, &FocusIndex, &Interaction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    buttons: Query<(&ControlsButton, &FocusIndex)>,
+    menu_actions: Res<Input<MenuAction>>,
+    mut focus: ResMut<MenuFocus>,
+    mut state: ResMut<bevy::prelude::State<State>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut key_bindings: ResMut<KeyBindings>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let mut pressed = None;
+    for (button, index, interaction) in &interaction_query {
+        match *interaction {
+            Interaction::Clicked => pressed = Some(*button),
+            Interaction::Hovered => focus.0 = index.0,
+            Interaction::None => {}
+        }
+    }
+
+    // Every key goes to the action being rebound
+    if rebinding.0.is_none() {
+        let num_buttons = buttons.iter().count();
+        if let Some(focused) = menu::navigate(
+            &menu_actions,
+            &mut focus,
+            num_buttons,
+            &sound_channel,
+            &audio_assets,
+        ) {
+            pressed = buttons
+                .iter()
+                .find(|(_, index)| index.0 == focused)
+                .map(|(button, _)| *button);
+        }
+        if menu_actions.just_pressed(MenuAction::Back) {
+            pressed = Some(ControlsButton::Back);
+        }
+    }
+
+    let button = match pressed {
+        Some(button) => button,
+        None => return,
+    };
+    sound_channel.play(audio_assets.blip1.clone());
+    match button {
+        ControlsButton::Rebind(action) => {
+            rebinding.0 = Some(action);
+        }
+        ControlsButton::Reset => {
+            *key_bindings = KeyBindings::default();
+            key_bindings.save();
+            rebinding.0 = None;
+        }
+        ControlsButton::Back => {
+            state.set(State::MainMenu).unwrap();
+        }
+    }
+}
+
+/// Waits for a key press while an action is selected and binds it. Escape cancels.
+fn rebind(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut key_bindings: ResMut<KeyBindings>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    // The key that selected the action isn't bound to it
+    if rebinding.is_changed() {
+        return;
+    }
+    if let Some(action) = rebinding.0 {
+        if let Some(key) = keyboard_input.get_just_pressed().next() {
+            if *key != KeyCode::Escape {
+                key_bindings.0.insert(action, vec![*key]);
+                key_bindings.save();
+                sound_channel.play(audio_assets.blip2.clone());
+            }
+            rebinding.0 = None;
+        }
+    }
+}
+
+fn update_text(
+    key_bindings: Res<KeyBindings>,
+    rebinding: Res<Rebinding>,
+    mut query: Query<(&BindingText, &mut Text)>,
+) {
+    if !key_bindings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (binding_text, mut text) in query.iter_mut() {
+        text.sections[0].value = if rebinding.0 == Some(binding_text.0) {
+            "Press a key...".to_string()
+        } else {
+            key_bindings.describe(binding_text.0)
+        };
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<ControlsMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Rebinding>();
+    commands.remove_resource::<MenuFocus>();
+}
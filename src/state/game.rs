@@ -1,12 +1,19 @@
-use bevy::prelude::{Commands, ParallelSystemDescriptorCoercion, Plugin, Res, SystemSet};
+use bevy::prelude::{
+    warn, Assets, BuildChildren, Camera, Commands, CoreStage, DespawnRecursiveExt, Entity, Handle,
+    Or, ParallelSystemDescriptorCoercion, Parent, Plugin, Query, Res, SystemSet, SystemStage,
+    Transform, With,
+};
 use bevy_asset_loader::prelude::{LoadingState, LoadingStateAppExt};
-use bevy_ecs_ldtk::LdtkWorldBundle;
+use bevy_ecs_ldtk::{LdtkAsset, LdtkWorldBundle, LevelSelection, Worldly};
 use bevy_kira_audio::{AudioChannel, AudioControl};
 
 use crate::{
-    abilities, animation,
+    abilities::{self, FireProjectile, StonePlatform, WaterProjectile, WindProjectile},
+    animation,
     audio::{AudioAssets, MusicChannel},
-    camera, damage, destruction, entity, input, physics,
+    aura, camera, collision, combinations, damage, destruction, entity, events, input, physics,
+    replay, speedrun,
+    world::CurrentWorld,
 };
 
 use super::{load_game::{GameAssets, self}, State};
@@ -22,7 +29,37 @@ impl Plugin for GamePlugin {
         )
         .add_system_set(SystemSet::on_enter(State::LoadGame).with_system(load_game::setup))
         .add_system_set(SystemSet::on_exit(State::LoadGame).with_system(load_game::cleanup))
-        .add_system_set(SystemSet::on_enter(State::InGame).with_system(setup))
+        .add_system_set(
+            SystemSet::on_enter(State::InGame)
+                .with_system(setup)
+                .with_system(combinations::insert_resource),
+        )
+        .add_system_set(SystemSet::on_exit(State::InGame).with_system(cleanup))
+        .add_stage_after(
+            CoreStage::Update,
+            physics::FixedUpdateStage,
+            SystemStage::parallel().with_run_criteria(physics::on_physics_step),
+        )
+        .add_system_set_to_stage(
+            physics::FixedUpdateStage,
+            SystemSet::on_update(State::InGame)
+                .with_system(replay::step.before(physics::PhysicsLabel::HandleControllables))
+                .with_system(
+                    physics::handle_controllables.label(physics::PhysicsLabel::HandleControllables),
+                )
+                .with_system(
+                    abilities::use_ability.after(physics::PhysicsLabel::HandleControllables),
+                )
+                .with_system(entity::wisp::fly),
+        )
+        .add_system_set_to_stage(
+            // Before Update so every system there sees this frame's collisions
+            CoreStage::PreUpdate,
+            SystemSet::on_update(State::InGame)
+                .with_system(collision::dispatch_player)
+                .with_system(collision::dispatch_projectiles)
+                .with_system(collision::dispatch_force_areas),
+        )
         .add_system_set(
             SystemSet::on_update(State::InGame)
                 .with_system(input::system.label(input::InputLabel::ControllableUpdate))
@@ -35,55 +72,123 @@ impl Plugin for GamePlugin {
                         .label(physics::PhysicsLabel::CheckCollision)
                         .after(input::InputLabel::ControllableUpdate),
                 )
-                .with_system(
-                    physics::handle_controllables
-                        .label(physics::PhysicsLabel::HandleControllables)
-                        .after(physics::PhysicsLabel::CheckCollision),
-                )
-                .with_system(abilities::use_ability)
                 .with_system(abilities::fire_projectile_collision)
                 .with_system(abilities::wind_projectile_collision)
                 .with_system(abilities::water_projectile_collision)
-                .with_system(damage::detect)
-                .with_system(damage::kill.after(physics::PhysicsLabel::HandleControllables))
+                .with_system(abilities::ground_pound_collision)
+                .with_system(aura::ignite_torches)
+                .with_system(aura::burn_flamables)
+                .with_system(aura::extinguish_lava)
+                .with_system(damage::detect.label(damage::DamageLabel::Detect))
+                .with_system(damage::apply.after(damage::DamageLabel::Detect))
+                .with_system(damage::invulnerability)
+                .with_system(damage::kill)
                 .with_system(damage::respawn)
-                .with_system(
-                    entity::player::animation_state_update
-                        .after(physics::PhysicsLabel::HandleControllables),
-                )
+                .with_system(entity::player::animation_state_update)
+                .with_system(spawn_world)
                 .with_system(entity::player::set_spawn)
+                .with_system(entity::player::move_to_start)
+                .with_system(entity::enemy::take_hits)
+                .with_system(entity::enemy::recover)
+                .with_system(entity::enemy::defeat)
+                .with_system(entity::goblin::think)
                 .with_system(entity::goblin::patrol)
                 .with_system(entity::goblin::init_animation_state)
                 .with_system(entity::goblin::animation_state_update)
                 .with_system(entity::goblin::face_direction)
+                .with_system(entity::fire_imp::tint)
+                .with_system(entity::fire_imp::throw)
+                .with_system(entity::fire_imp::fireball_collision)
+                .with_system(entity::wisp::tint)
                 .with_system(entity::ability::check_near)
                 .with_system(entity::ability::dont_spawn_if_collected)
                 .with_system(entity::signpost::spawn_text)
-                .with_system(entity::signpost::check_near)
-                .with_system(entity::lava::check_collision)
-                .with_system(entity::water::check_collision)
+                .with_system(entity::show_proximity_text)
                 .with_system(entity::checkpoint::check_near)
                 .with_system(entity::checkpoint::offset)
                 .with_system(entity::torch::offset)
+                .with_system(entity::torch::update_lit)
                 .with_system(
                     entity::fan::check_collision.label(physics::PhysicsLabel::CheckCollision),
                 )
                 .with_system(entity::fan::apply_force.after(physics::PhysicsLabel::CheckCollision))
                 .with_system(entity::fan::rotate)
-                .with_system(entity::trophy::check_near),
-        );
+                .with_system(entity::trophy::complete_world),
+        )
+        .add_event::<damage::DamageEvent>()
+        .add_event::<speedrun::SpeedrunEvent>()
+        .add_event::<events::GameEvent>()
+        .add_event::<collision::PlayerProximity>()
+        .add_event::<collision::PlayerEnteredCheckpoint>()
+        .add_event::<collision::PlayerLeftCheckpoint>()
+        .add_event::<collision::PlayerReachedTrophy>()
+        .add_event::<collision::PlayerTouchedAbility>()
+        .add_event::<collision::PlayerTouchedHazard>()
+        .add_event::<collision::PlayerTouched>()
+        .add_event::<collision::ProjectileHitEnemy>()
+        .add_event::<collision::ProjectileHit>()
+        .add_event::<collision::ForceAreaContact>()
+        .insert_resource(damage::Difficulty::from_args());
     }
 }
 
 fn setup(
-    mut commands: Commands,
-    game_assets: Res<GameAssets>,
     music_channel: Res<AudioChannel<MusicChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
+    music_channel.play(audio_assets.bgm.clone()).looped();
+}
+
+/// Waits for the world's project to load so the level holding the player can be selected first
+fn spawn_world(
+    mut commands: Commands,
+    current_world: Res<CurrentWorld>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    worlds: Query<(), With<Handle<LdtkAsset>>>,
+) {
+    if !worlds.is_empty() {
+        return;
+    }
+    let ldtk_asset = match ldtk_assets.get(&current_world.0.level) {
+        Some(ldtk_asset) => ldtk_asset,
+        None => return,
+    };
+    match entity::player::find_player(&ldtk_asset.project) {
+        Some((level, _)) => commands.insert_resource(LevelSelection::Iid(level.iid.clone())),
+        None => warn!("{} has no Player entity", current_world.0.path),
+    }
     commands.spawn_bundle(LdtkWorldBundle {
-        ldtk_handle: game_assets.level.clone(),
+        ldtk_handle: current_world.0.level.clone(),
         ..Default::default()
     });
-    music_channel.play(audio_assets.bgm.clone()).looped();
+}
+
+/// Despawns the world and everything left over from playing it, keeping the camera for the menus
+fn cleanup(
+    mut commands: Commands,
+    entities: Query<
+        Entity,
+        Or<(
+            With<Handle<LdtkAsset>>,
+            With<Worldly>,
+            With<FireProjectile>,
+            With<WindProjectile>,
+            With<WaterProjectile>,
+            With<StonePlatform>,
+        )>,
+    >,
+    mut cameras: Query<(Entity, &mut Transform, Option<&Parent>), With<Camera>>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+) {
+    for (camera, mut transform, parent) in cameras.iter_mut() {
+        // The camera follows the player as its child
+        if let Some(parent) = parent {
+            commands.entity(parent.get()).remove_children(&[camera]);
+        }
+        *transform = Transform::from_xyz(0.0, 0.0, 900.0);
+    }
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    music_channel.stop();
 }
@@ -0,0 +1,302 @@
+use bevy::{
+    prelude::{
+        Assets, BuildChildren, Button, ButtonBundle, Changed, ChildBuilder, Color, Commands,
+        Component, DespawnRecursiveExt, Entity, Input, NodeBundle, Plugin, Query, Res, ResMut,
+        SystemSet, TextBundle, With,
+    },
+    text::TextStyle,
+    ui::{AlignItems, FlexDirection, Interaction, JustifyContent, Size, Style, UiRect, Val},
+};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+
+use crate::{
+    audio::{AudioAssets, SoundChannel},
+    input::MenuAction,
+    save::{WorldProgress, WorldSave},
+    world::{CurrentWorld, WorldInfo, Worlds},
+};
+
+use super::{
+    load_menu::MenuAssets,
+    menu::{self, FocusIndex, MenuFocus},
+    State,
+};
+
+pub struct LevelSelectPlugin;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system_set(SystemSet::on_enter(State::LevelSelect).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(State::LevelSelect)
+                    .with_system(button_system)
+                    .with_system(menu::button_color_system),
+            )
+            .add_system_set(SystemSet::on_exit(State::LevelSelect).with_system(cleanup));
+    }
+}
+
+#[derive(Component)]
+struct LevelSelectMenu;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum LevelSelectButton {
+    /// Index into `Worlds::worlds`
+    Play(usize),
+    Back,
+}
+
+fn world_description(world: &WorldInfo, save: Option<&WorldSave>) -> String {
+    match save {
+        Some(save) if save.completed => format!("{}\nComplete", world.name),
+        Some(save) => format!(
+            "{}\nIn progress: {}",
+            world.name,
+            if save.level_identifier.is_empty() {
+                "Start"
+            } else {
+                save.level_identifier.as_str()
+            }
+        ),
+        None => format!("{}\nNot started", world.name),
+    }
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    menu_assets: &MenuAssets,
+    button: LevelSelectButton,
+    focus_index: usize,
+    text: &str,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(150.0), Val::Px(50.0)),
+                margin: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            image: menu_assets.button.clone().into(),
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..Default::default()
+        })
+        .insert(button)
+        .insert(FocusIndex(focus_index))
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: menu_assets.pixel_font.clone(),
+                    font_size: 15.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn setup(
+    mut commands: Commands,
+    menu_assets: Res<MenuAssets>,
+    worlds: Res<Assets<Worlds>>,
+    progress: Res<WorldProgress>,
+) {
+    let worlds = match worlds.get(&menu_assets.worlds) {
+        Some(worlds) => worlds,
+        None => return,
+    };
+    commands.insert_resource(MenuFocus::default());
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                padding: UiRect::all(Val::Px(20.)),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            image: menu_assets.background.clone().into(),
+            ..Default::default()
+        })
+        .insert(LevelSelectMenu)
+        .with_children(|parent| {
+            // header wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(900.0), Val::Px(80.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        "Select a world",
+                        TextStyle {
+                            font: menu_assets.pixel_font.clone(),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+            // Worlds wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(900.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for (index, world) in worlds.worlds.iter().enumerate() {
+                        let save = progress.0.get(&world.id);
+                        // World row
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(100.0), Val::Px(70.0)),
+                                    justify_content: JustifyContent::FlexEnd,
+                                    align_items: AlignItems::Center,
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    ..Default::default()
+                                },
+                                color: Color::NONE.into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(NodeBundle {
+                                        style: Style {
+                                            size: Size::new(Val::Px(500.0), Val::Auto),
+                                            margin: UiRect {
+                                                right: Val::Auto,
+                                                ..Default::default()
+                                            },
+                                            ..Default::default()
+                                        },
+                                        color: Color::NONE.into(),
+                                        ..Default::default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn_bundle(TextBundle::from_section(
+                                            world_description(world, save),
+                                            TextStyle {
+                                                font: menu_assets.pixel_font.clone(),
+                                                font_size: 13.0,
+                                                color: Color::WHITE,
+                                            },
+                                        ));
+                                    });
+                                spawn_button(
+                                    parent,
+                                    &menu_assets,
+                                    LevelSelectButton::Play(index),
+                                    index,
+                                    if save.is_some() { "Continue" } else { "Start" },
+                                );
+                            });
+                    }
+                });
+            // Back button wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(900.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    spawn_button(
+                        parent,
+                        &menu_assets,
+                        LevelSelectButton::Back,
+                        worlds.worlds.len(),
+                        "Back",
+                    );
+                });
+        });
+}
+
+fn button_system(
+    mut commands: Commands,
+    interaction_query: Query<
+        (&LevelSelectButton, &FocusIndex, &Interaction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    buttons: Query<(&LevelSelectButton, &FocusIndex)>,
+    menu_actions: Res<Input<MenuAction>>,
+    mut focus: ResMut<MenuFocus>,
+    mut state: ResMut<bevy::prelude::State<State>>,
+    menu_assets: Res<MenuAssets>,
+    worlds: Res<Assets<Worlds>>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let mut pressed = None;
+    for (button, index, interaction) in &interaction_query {
+        match *interaction {
+            Interaction::Clicked => pressed = Some(*button),
+            Interaction::Hovered => focus.0 = index.0,
+            Interaction::None => {}
+        }
+    }
+
+    let num_buttons = buttons.iter().count();
+    if let Some(focused) = menu::navigate(
+        &menu_actions,
+        &mut focus,
+        num_buttons,
+        &sound_channel,
+        &audio_assets,
+    ) {
+        pressed = buttons
+            .iter()
+            .find(|(_, index)| index.0 == focused)
+            .map(|(button, _)| *button);
+    }
+    if menu_actions.just_pressed(MenuAction::Back) {
+        pressed = Some(LevelSelectButton::Back);
+    }
+
+    let button = match pressed {
+        Some(button) => button,
+        None => return,
+    };
+    sound_channel.play(audio_assets.blip1.clone());
+    match button {
+        LevelSelectButton::Play(index) => {
+            if let Some(world) = worlds
+                .get(&menu_assets.worlds)
+                .and_then(|worlds| worlds.worlds.get(index))
+            {
+                commands.insert_resource(CurrentWorld(world.clone()));
+                state.set(State::LoadGame).unwrap();
+            }
+        }
+        LevelSelectButton::Back => {
+            state.set(State::SlotSelect).unwrap();
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<LevelSelectMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<MenuFocus>();
+}
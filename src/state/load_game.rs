@@ -4,14 +4,21 @@ use bevy::prelude::{Handle, Image, Commands, NodeBundle, BuildChildren, TextBund
 use bevy::text::{Font, TextStyle};
 use bevy::ui::{Style, UiRect, Val, Size, JustifyContent, AlignItems, FlexDirection};
 use bevy_asset_loader::prelude::AssetCollection;
-use bevy_ecs_ldtk::LdtkAsset;
+
+use crate::achievements::Achievements;
+use crate::boss::Bosses;
+use crate::combinations::Combinations;
 
 use super::load_menu::MenuAssets;
 
 #[derive(AssetCollection)]
 pub struct GameAssets {
-    #[asset(path = "levels/level.ldtk")]
-    pub level: Handle<LdtkAsset>,
+    #[asset(path = "combinations.combos.ron")]
+    pub combinations: Handle<Combinations>,
+    #[asset(path = "achievements.achievements.ron")]
+    pub achievements: Handle<Achievements>,
+    #[asset(path = "bosses.bosses.ron")]
+    pub bosses: Handle<Bosses>,
     #[asset(path = "fonts/prstartk.ttf")]
     pub pixel_font: Handle<Font>,
     #[asset(path = "sprites/menu_background.png")]
@@ -28,6 +35,11 @@ pub struct GameAssets {
     pub wind: Handle<Image>,
     #[asset(path = "sprites/droplet.png")]
     pub droplet: Handle<Image>,
+    /// The last frame is used for stone
+    #[asset(path = "sprites/lava.png")]
+    pub lava: Handle<Image>,
+    #[asset(path = "sprites/herochar_spritesheet.png")]
+    pub herochar: Handle<Image>,
 }
 
 #[derive(Component)]
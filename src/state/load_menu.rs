@@ -4,6 +4,8 @@ use bevy::prelude::{Handle, Image};
 use bevy::text::Font;
 use bevy_asset_loader::prelude::AssetCollection;
 
+use crate::world::Worlds;
+
 #[derive(AssetCollection)]
 pub struct MenuAssets {
     #[asset(path = "fonts/prstartk.ttf")]
@@ -12,4 +14,6 @@ pub struct MenuAssets {
     pub background: Handle<Image>,
     #[asset(path = "sprites/button.png")]
     pub button: Handle<Image>,
+    #[asset(path = "worlds.worlds.ron")]
+    pub worlds: Handle<Worlds>,
 }
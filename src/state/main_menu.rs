@@ -1,20 +1,29 @@
 use bevy::{
     prelude::{
-        BuildChildren, Button, ButtonBundle, Camera2dBundle, Changed, Color, Commands, Component,
-        DespawnRecursiveExt, Entity, NodeBundle, Plugin, Query, Res, ResMut, SystemSet, TextBundle,
-        Transform, With,
-    },
-    text::{TextSection, TextStyle},
-    ui::{
-        AlignItems, FlexDirection, Interaction, JustifyContent, Size, Style, UiColor, UiRect, Val,
+        Assets, BuildChildren, Button, ButtonBundle, Camera, Camera2dBundle, Changed, Color,
+        Commands, Component, DespawnRecursiveExt, Entity, Input, NodeBundle, Plugin, Query, Res,
+        ResMut, SystemSet, TextBundle, Transform, With,
     },
+    text::{Text, TextSection, TextStyle},
+    ui::{AlignItems, FlexDirection, Interaction, JustifyContent, Size, Style, UiRect, Val},
 };
 use bevy_asset_loader::prelude::{LoadingState, LoadingStateAppExt};
 use bevy_kira_audio::{AudioChannel, AudioControl};
 
-use crate::audio::{AudioAssets, MusicChannel, SoundChannel, VolumeSettings};
+use crate::{
+    audio::{AudioAssets, MusicChannel, SoundChannel, VolumeSettings},
+    damage::Difficulty,
+    input::MenuAction,
+    save,
+    speedrun::SpeedrunSettings,
+    world::{CurrentWorld, Worlds},
+};
 
-use super::{load_menu::MenuAssets, State};
+use super::{
+    load_menu::MenuAssets,
+    menu::{self, FocusIndex, MenuFocus},
+    State,
+};
 
 pub struct MainMenuPlugin;
 
@@ -27,7 +36,11 @@ impl Plugin for MainMenuPlugin {
                 .with_collection::<AudioAssets>(),
         )
         .add_system_set(SystemSet::on_enter(State::MainMenu).with_system(setup))
-        .add_system_set(SystemSet::on_update(State::MainMenu).with_system(button_system))
+        .add_system_set(
+            SystemSet::on_update(State::MainMenu)
+                .with_system(button_system)
+                .with_system(menu::button_color_system),
+        )
         .add_system_set(SystemSet::on_exit(State::MainMenu).with_system(cleanup));
     }
 }
@@ -35,31 +48,81 @@ impl Plugin for MainMenuPlugin {
 #[derive(Component)]
 struct MainMenu;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 enum MenuButton {
+    Continue,
     Start,
+    Controls,
     Sound,
     Music,
+    Speedrun,
+    Difficulty,
+}
+
+/// Text of the buttons that show the current value of a setting
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum SettingLabel {
+    Speedrun,
+    Difficulty,
+}
+
+fn speedrun_label(settings: &SpeedrunSettings) -> String {
+    format!(
+        "Speedrun Timer\n{}",
+        if settings.enabled { "On" } else { "Off" }
+    )
+}
+
+fn difficulty_label(difficulty: Difficulty) -> String {
+    format!(
+        "Difficulty\n{}",
+        match difficulty {
+            Difficulty::Normal => "Normal",
+            Difficulty::OneHitKill => "One Hit Kill",
+        }
+    )
 }
 
 fn setup(
     mut commands: Commands,
     menu_assets: Res<MenuAssets>,
+    cameras: Query<(), With<Camera>>,
     music_channel: Res<AudioChannel<MusicChannel>>,
     audio_assets: Res<AudioAssets>,
+    speedrun_settings: Res<SpeedrunSettings>,
+    difficulty: Res<Difficulty>,
 ) {
     commands.insert_resource(VolumeSettings::default());
+    commands.insert_resource(MenuFocus::default());
+
+    let mut buttons = Vec::new();
+    if save::last_slot().is_some() {
+        buttons.push((MenuButton::Continue, "Continue".to_string(), 20.0));
+    }
+    buttons.push((MenuButton::Start, "Start".to_string(), 20.0));
+    buttons.push((MenuButton::Controls, "Controls".to_string(), 20.0));
+    buttons.push((MenuButton::Sound, "Toggle\nSFX Volume".to_string(), 15.0));
+    buttons.push((MenuButton::Music, "Toggle\nMusic Volume".to_string(), 15.0));
+    buttons.push((
+        MenuButton::Speedrun,
+        speedrun_label(&speedrun_settings),
+        15.0,
+    ));
+    buttons.push((MenuButton::Difficulty, difficulty_label(*difficulty), 15.0));
 
     music_channel.play(audio_assets.menu.clone()).looped();
 
-    commands.spawn_bundle(Camera2dBundle {
-        transform: Transform::from_xyz(0.0, 0.0, 900.0),
-        ..Default::default()
-    });
+    // The camera is kept when coming back from other menus
+    if cameras.is_empty() {
+        commands.spawn_bundle(Camera2dBundle {
+            transform: Transform::from_xyz(0.0, 0.0, 900.0),
+            ..Default::default()
+        });
+    }
     let button_style = Style {
-        size: Size::new(Val::Px(195.0), Val::Px(65.0)),
-        // center button
-        margin: UiRect::all(Val::Px(20.0)),
+        size: Size::new(Val::Px(195.0), Val::Px(60.0)),
+        // center button, close enough together that all of them fit on screen
+        margin: UiRect::new(Val::Px(20.0), Val::Px(20.0), Val::Px(10.0), Val::Px(10.0)),
         // horizontally center child text
         justify_content: JustifyContent::Center,
         // vertically center child text
@@ -128,100 +191,138 @@ fn setup(
                     ..Default::default()
                 })
                 .with_children(|parent| {
-                    parent
-                        .spawn_bundle(ButtonBundle {
-                            style: button_style.clone(),
-                            image: menu_assets.button.clone().into(),
-                            color: Color::rgb(0.15, 0.15, 0.15).into(),
-                            ..Default::default()
-                        })
-                        .insert(MenuButton::Start)
-                        .with_children(|parent| {
-                            parent.spawn_bundle(TextBundle::from_section(
-                                "Start",
-                                TextStyle {
-                                    font: menu_assets.pixel_font.clone(),
-                                    font_size: 20.0,
-                                    color: Color::WHITE,
-                                },
-                            ));
-                        });
-                    parent
-                        .spawn_bundle(ButtonBundle {
-                            style: button_style.clone(),
-                            image: menu_assets.button.clone().into(),
-                            color: Color::rgb(0.15, 0.15, 0.15).into(),
-                            ..Default::default()
-                        })
-                        .insert(MenuButton::Sound)
-                        .with_children(|parent| {
-                            parent.spawn_bundle(TextBundle::from_section(
-                                "Toggle\nSFX Volume",
-                                TextStyle {
-                                    font: menu_assets.pixel_font.clone(),
-                                    font_size: 15.0,
-                                    color: Color::WHITE,
-                                },
-                            ));
-                        });
-                    parent
-                        .spawn_bundle(ButtonBundle {
-                            style: button_style.clone(),
-                            image: menu_assets.button.clone().into(),
-                            color: Color::rgb(0.15, 0.15, 0.15).into(),
-                            ..Default::default()
-                        })
-                        .insert(MenuButton::Music)
-                        .with_children(|parent| {
-                            parent.spawn_bundle(TextBundle::from_section(
-                                "Toggle\nMusic Volume",
-                                TextStyle {
-                                    font: menu_assets.pixel_font.clone(),
-                                    font_size: 15.0,
-                                    color: Color::WHITE,
-                                },
-                            ));
-                        });
+                    for (index, (button, label, font_size)) in buttons.into_iter().enumerate() {
+                        parent
+                            .spawn_bundle(ButtonBundle {
+                                style: button_style.clone(),
+                                image: menu_assets.button.clone().into(),
+                                color: Color::rgb(0.15, 0.15, 0.15).into(),
+                                ..Default::default()
+                            })
+                            .insert(button)
+                            .insert(FocusIndex(index))
+                            .with_children(|parent| {
+                                let mut text = parent.spawn_bundle(TextBundle::from_section(
+                                    label,
+                                    TextStyle {
+                                        font: menu_assets.pixel_font.clone(),
+                                        font_size,
+                                        color: Color::WHITE,
+                                    },
+                                ));
+                                match button {
+                                    MenuButton::Speedrun => {
+                                        text.insert(SettingLabel::Speedrun);
+                                    }
+                                    MenuButton::Difficulty => {
+                                        text.insert(SettingLabel::Difficulty);
+                                    }
+                                    _ => {}
+                                }
+                            });
+                    }
                 });
         });
 }
 
 fn button_system(
-    mut interaction_query: Query<
-        (&MenuButton, &Interaction, &mut UiColor),
+    mut commands: Commands,
+    interaction_query: Query<
+        (&MenuButton, &FocusIndex, &Interaction),
         (Changed<Interaction>, With<Button>),
     >,
+    buttons: Query<(&MenuButton, &FocusIndex)>,
+    mut labels: Query<(&mut Text, &SettingLabel)>,
+    menu_actions: Res<Input<MenuAction>>,
+    mut focus: ResMut<MenuFocus>,
     mut state: ResMut<bevy::prelude::State<State>>,
     mut volume_settings: ResMut<VolumeSettings>,
+    mut speedrun_settings: ResMut<SpeedrunSettings>,
+    mut difficulty: ResMut<Difficulty>,
     music_channel: Res<AudioChannel<MusicChannel>>,
     sound_channel: Res<AudioChannel<SoundChannel>>,
     audio_assets: Res<AudioAssets>,
+    menu_assets: Res<MenuAssets>,
+    worlds: Res<Assets<Worlds>>,
 ) {
-    for (button, interaction, mut color) in &mut interaction_query {
+    let mut pressed = None;
+    for (button, index, interaction) in &interaction_query {
         match *interaction {
-            Interaction::Clicked => {
-                *color = Color::rgb(0.55, 0.55, 0.55).into();
-                match button {
-                    MenuButton::Start => {
-                        state.set(State::LoadGame).unwrap();
-                    }
-                    MenuButton::Sound => {
-                        volume_settings.toggle_sfx_vol();
-                        sound_channel.set_volume(volume_settings.sfx_vol);
-                        sound_channel.play(audio_assets.blip1.clone());
-                    }
-                    MenuButton::Music => {
-                        volume_settings.toggle_music_vol();
-                        music_channel.set_volume(volume_settings.music_vol);
-                        sound_channel.play(audio_assets.blip1.clone());
+            Interaction::Clicked => pressed = Some(*button),
+            Interaction::Hovered => focus.0 = index.0,
+            Interaction::None => {}
+        }
+    }
+
+    let num_buttons = buttons.iter().count();
+    if let Some(focused) = menu::navigate(
+        &menu_actions,
+        &mut focus,
+        num_buttons,
+        &sound_channel,
+        &audio_assets,
+    ) {
+        pressed = buttons
+            .iter()
+            .find(|(_, index)| index.0 == focused)
+            .map(|(button, _)| *button);
+    }
+
+    if let Some(button) = pressed {
+        match button {
+            MenuButton::Continue => {
+                if let Some((slot, data)) = save::last_slot() {
+                    // Resume the last played world, or let the player pick if it no longer exists
+                    let world = worlds
+                        .get(&menu_assets.worlds)
+                        .and_then(|worlds| worlds.get(&data.world))
+                        .cloned();
+                    save::start_game(&mut commands, slot, Some(data));
+                    match world {
+                        Some(world) => {
+                            commands.insert_resource(CurrentWorld(world));
+                            state.set(State::LoadGame).unwrap();
+                        }
+                        None => state.set(State::LevelSelect).unwrap(),
                     }
                 }
             }
-            Interaction::Hovered => {
-                *color = Color::rgb(0.35, 0.35, 0.35).into();
+            MenuButton::Start => {
+                state.set(State::SlotSelect).unwrap();
+            }
+            MenuButton::Controls => {
+                state.set(State::Controls).unwrap();
+            }
+            MenuButton::Sound => {
+                volume_settings.toggle_sfx_vol();
+                sound_channel.set_volume(volume_settings.sfx_vol);
+                sound_channel.play(audio_assets.blip1.clone());
             }
-            Interaction::None => {
-                *color = Color::rgb(0.15, 0.15, 0.15).into();
+            MenuButton::Music => {
+                volume_settings.toggle_music_vol();
+                music_channel.set_volume(volume_settings.music_vol);
+                sound_channel.play(audio_assets.blip1.clone());
+            }
+            MenuButton::Speedrun => {
+                speedrun_settings.enabled = !speedrun_settings.enabled;
+                for (mut text, label) in &mut labels {
+                    if *label == SettingLabel::Speedrun {
+                        text.sections[0].value = speedrun_label(&speedrun_settings);
+                    }
+                }
+                sound_channel.play(audio_assets.blip1.clone());
+            }
+            MenuButton::Difficulty => {
+                *difficulty = match *difficulty {
+                    Difficulty::Normal => Difficulty::OneHitKill,
+                    Difficulty::OneHitKill => Difficulty::Normal,
+                };
+                for (mut text, label) in &mut labels {
+                    if *label == SettingLabel::Difficulty {
+                        text.sections[0].value = difficulty_label(*difficulty);
+                    }
+                }
+                sound_channel.play(audio_assets.blip1.clone());
             }
         }
     }
@@ -235,5 +336,6 @@ fn cleanup(
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
+    commands.remove_resource::<MenuFocus>();
     music_channel.stop();
 }
@@ -0,0 +1,59 @@
+use bevy::{
+    prelude::{Button, Color, Component, Input, Query, Res, With},
+    ui::{Interaction, UiColor},
+};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+
+use crate::{
+    audio::{AudioAssets, SoundChannel},
+    input::MenuAction,
+};
+
+/// Position of a button in a menu, used for keyboard and gamepad navigation
+#[derive(Component)]
+pub struct FocusIndex(pub usize);
+
+/// Index of the currently selected button
+#[derive(Default)]
+pub struct MenuFocus(pub usize);
+
+/// Moves the focus with the arrow keys or D-pad. Returns the focused index if it was confirmed.
+pub fn navigate(
+    menu_actions: &Input<MenuAction>,
+    focus: &mut MenuFocus,
+    num_buttons: usize,
+    sound_channel: &AudioChannel<SoundChannel>,
+    audio_assets: &AudioAssets,
+) -> Option<usize> {
+    if num_buttons == 0 {
+        return None;
+    }
+    if menu_actions.just_pressed(MenuAction::Down) || menu_actions.just_pressed(MenuAction::Right) {
+        focus.0 = (focus.0 + 1) % num_buttons;
+        sound_channel.play(audio_assets.blip1.clone());
+    }
+    if menu_actions.just_pressed(MenuAction::Up) || menu_actions.just_pressed(MenuAction::Left) {
+        focus.0 = (focus.0 + num_buttons - 1) % num_buttons;
+        sound_channel.play(audio_assets.blip1.clone());
+    }
+    if menu_actions.just_pressed(MenuAction::Confirm) {
+        Some(focus.0)
+    } else {
+        None
+    }
+}
+
+pub fn button_color_system(
+    mut query: Query<(&FocusIndex, &Interaction, &mut UiColor), With<Button>>,
+    focus: Res<MenuFocus>,
+) {
+    for (index, interaction, mut color) in &mut query {
+        *color = if *interaction == Interaction::Clicked {
+            Color::rgb(0.55, 0.55, 0.55).into()
+        } else if index.0 == focus.0 {
+            Color::rgb(0.35, 0.35, 0.35).into()
+        } else {
+            Color::rgb(0.15, 0.15, 0.15).into()
+        };
+    }
+}
@@ -1,15 +1,24 @@
 pub mod ability_menu;
+pub mod controls;
 pub mod game;
+pub mod level_select;
 pub mod load_game;
 pub mod load_menu;
 pub mod main_menu;
+pub mod menu;
+pub mod results;
+pub mod slot_select;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
 pub enum State {
     #[default]
     LoadMenu,
     MainMenu,
+    SlotSelect,
+    LevelSelect,
+    Controls,
     LoadGame,
     InGame,
     AbilityMenu,
+    Results,
 }
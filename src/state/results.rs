@@ -0,0 +1,297 @@
+use bevy::{
+    prelude::{
+        BuildChildren, Button, ButtonBundle, Changed, ChildBuilder, Color, Commands, Component,
+        DespawnRecursiveExt, Entity, Input, NodeBundle, Plugin, Query, Res, ResMut, SystemSet,
+        TextBundle, With,
+    },
+    text::TextStyle,
+    time::{Time, Timer},
+    ui::{AlignItems, FlexDirection, Interaction, JustifyContent, Size, Style, UiRect, Val},
+};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+
+use crate::{
+    audio::{AudioAssets, SoundChannel},
+    input::MenuAction,
+    stats::Stats,
+    world::CurrentWorld,
+};
+
+use super::{
+    load_menu::MenuAssets,
+    menu::{self, FocusIndex, MenuFocus},
+    State,
+};
+
+/// Seconds the trophy's text stays up before the results are shown
+const RESULTS_DELAY: f32 = 2.0;
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system_set(SystemSet::on_update(State::InGame).with_system(show_results))
+            .add_system_set(SystemSet::on_enter(State::Results).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(State::Results)
+                    .with_system(button_system)
+                    .with_system(menu::button_color_system),
+            )
+            .add_system_set(SystemSet::on_exit(State::Results).with_system(cleanup));
+    }
+}
+
+/// Inserted when a trophy is reached, the results are shown once it runs out
+pub struct ResultsTimer(pub Timer);
+
+impl Default for ResultsTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(RESULTS_DELAY, false))
+    }
+}
+
+#[derive(Component)]
+struct ResultsMenu;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ResultsButton {
+    MainMenu,
+    NewGame,
+}
+
+fn show_results(
+    mut commands: Commands,
+    timer: Option<ResMut<ResultsTimer>>,
+    time: Res<Time>,
+    mut state: ResMut<bevy::prelude::State<State>>,
+) {
+    if let Some(mut timer) = timer {
+        timer.0.tick(time.delta());
+        // Another transition, like opening the ability menu, may already be queued this frame.
+        // The timer is kept until the results are queued so they're retried on a later frame.
+        if timer.0.finished() && state.set(State::Results).is_ok() {
+            commands.remove_resource::<ResultsTimer>();
+        }
+    }
+}
+
+/// `m:ss`
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn stat_lines(stats: &Stats) -> Vec<(&'static str, String)> {
+    vec![
+        ("Time", format_time(stats.time)),
+        ("Deaths", stats.deaths.to_string()),
+        ("  Spikes", stats.spike_deaths.to_string()),
+        ("  Enemies", stats.enemy_deaths.to_string()),
+        ("  Lava", stats.lava_deaths.to_string()),
+        ("  Water", stats.water_deaths.to_string()),
+        ("  Fire", stats.fire_deaths.to_string()),
+        ("Fireballs cast", stats.fireballs_cast.to_string()),
+        ("Enemies defeated", stats.enemies_killed.to_string()),
+        ("Lava cooled", stats.lava_cooled.to_string()),
+        ("Blocks pushed", stats.blocks_pushed.to_string()),
+    ]
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    menu_assets: &MenuAssets,
+    button: ResultsButton,
+    focus_index: usize,
+    text: &str,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(195.0), Val::Px(50.0)),
+                margin: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            image: menu_assets.button.clone().into(),
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..Default::default()
+        })
+        .insert(button)
+        .insert(FocusIndex(focus_index))
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: menu_assets.pixel_font.clone(),
+                    font_size: 15.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn setup(
+    mut commands: Commands,
+    menu_assets: Res<MenuAssets>,
+    stats: Res<Stats>,
+    current_world: Res<CurrentWorld>,
+) {
+    let text_style = TextStyle {
+        font: menu_assets.pixel_font.clone(),
+        font_size: 15.0,
+        color: Color::WHITE,
+    };
+    commands.insert_resource(MenuFocus::default());
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                padding: UiRect::all(Val::Px(20.)),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            image: menu_assets.background.clone().into(),
+            ..Default::default()
+        })
+        .insert(ResultsMenu)
+        .with_children(|parent| {
+            // header wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(600.0), Val::Px(80.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        format!("{} complete!", current_world.0.name),
+                        TextStyle {
+                            font: menu_assets.pixel_font.clone(),
+                            font_size: 25.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+            // Stats wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(600.0), Val::Auto),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for (label, value) in stat_lines(&stats) {
+                        // Stat row
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(100.0), Val::Px(25.0)),
+                                    justify_content: JustifyContent::SpaceBetween,
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                color: Color::NONE.into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn_bundle(TextBundle::from_section(
+                                    label,
+                                    text_style.clone(),
+                                ));
+                                parent.spawn_bundle(TextBundle::from_section(
+                                    value,
+                                    text_style.clone(),
+                                ));
+                            });
+                    }
+                });
+            // Buttons wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(600.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    spawn_button(
+                        parent,
+                        &menu_assets,
+                        ResultsButton::MainMenu,
+                        0,
+                        "Main Menu",
+                    );
+                    spawn_button(parent, &menu_assets, ResultsButton::NewGame, 1, "New Game");
+                });
+        });
+}
+
+fn button_system(
+    interaction_query: Query<
+        (&ResultsButton, &FocusIndex, &Interaction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    buttons: Query<(&ResultsButton, &FocusIndex)>,
+    menu_actions: Res<Input<MenuAction>>,
+    mut focus: ResMut<MenuFocus>,
+    mut state: ResMut<bevy::prelude::State<State>>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let mut pressed = None;
+    for (button, index, interaction) in &interaction_query {
+        match *interaction {
+            Interaction::Clicked => pressed = Some(*button),
+            Interaction::Hovered => focus.0 = index.0,
+            Interaction::None => {}
+        }
+    }
+
+    let num_buttons = buttons.iter().count();
+    if let Some(focused) = menu::navigate(
+        &menu_actions,
+        &mut focus,
+        num_buttons,
+        &sound_channel,
+        &audio_assets,
+    ) {
+        pressed = buttons
+            .iter()
+            .find(|(_, index)| index.0 == focused)
+            .map(|(button, _)| *button);
+    }
+
+    if let Some(button) = pressed {
+        sound_channel.play(audio_assets.blip1.clone());
+        match button {
+            ResultsButton::MainMenu => state.set(State::MainMenu).unwrap(),
+            // Choosing a slot is how a new game is started from the main menu too
+            ResultsButton::NewGame => state.set(State::SlotSelect).unwrap(),
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<ResultsMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<MenuFocus>();
+}
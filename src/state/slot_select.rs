@@ -0,0 +1,417 @@
+use bevy::{
+    prelude::{
+        BuildChildren, Button, ButtonBundle, Changed, ChildBuilder, Children, Color, Commands,
+        Component, DespawnRecursiveExt, Entity, Input, NodeBundle,
+        ParallelSystemDescriptorCoercion, Plugin, Query, Res, ResMut, SystemSet, TextBundle, With,
+    },
+    text::{Text, TextStyle},
+    ui::{AlignItems, FlexDirection, Interaction, JustifyContent, Size, Style, UiRect, Val},
+};
+use bevy_kira_audio::{AudioChannel, AudioControl};
+
+use crate::{
+    audio::{AudioAssets, SoundChannel},
+    input::MenuAction,
+    save::{self, SaveData},
+};
+
+use super::{
+    load_menu::MenuAssets,
+    menu::{self, FocusIndex, MenuFocus},
+    State,
+};
+
+pub struct SlotSelectPlugin;
+
+impl Plugin for SlotSelectPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system_set(SystemSet::on_enter(State::SlotSelect).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(State::SlotSelect)
+                    .with_system(button_system)
+                    .with_system(reset_confirming.after(button_system))
+                    .with_system(menu::button_color_system),
+            )
+            .add_system_set(SystemSet::on_exit(State::SlotSelect).with_system(cleanup));
+    }
+}
+
+#[derive(Component)]
+struct SlotSelectMenu;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum SlotButton {
+    /// Load the slot, or start a new game if it is empty
+    Play(usize),
+    /// Start a new game in a slot that already has a save
+    Overwrite(usize),
+    Delete(usize),
+    Back,
+}
+
+/// Destructive buttons have to be clicked twice. This holds the button waiting for its second
+/// click.
+#[derive(Default)]
+struct Confirming(Option<SlotButton>);
+
+/// Label of a button that has to be confirmed, shown again once it no longer is
+fn confirm_label(button: SlotButton) -> Option<&'static str> {
+    match button {
+        SlotButton::Overwrite(_) => Some("Overwrite"),
+        SlotButton::Delete(_) => Some("Delete"),
+        SlotButton::Play(_) | SlotButton::Back => None,
+    }
+}
+
+fn setup(mut commands: Commands, menu_assets: Res<MenuAssets>) {
+    commands.insert_resource(Confirming::default());
+    commands.insert_resource(MenuFocus::default());
+    spawn_menu(&mut commands, &menu_assets);
+}
+
+fn format_play_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+fn slot_description(slot: usize, data: &Option<SaveData>) -> String {
+    match data {
+        Some(data) => {
+            let unlocked = data.unlocked_abilities();
+            let level = data
+                .worlds
+                .get(&data.world)
+                .map(|world| world.level_identifier.as_str())
+                .filter(|identifier| !identifier.is_empty())
+                .unwrap_or("Start");
+            let completed = data.worlds.values().filter(|world| world.completed).count();
+            format!(
+                "Slot {}  {}  Worlds complete: {}\nLevel: {}\nUnlocked: {}",
+                slot + 1,
+                format_play_time(data.play_time),
+                completed,
+                level,
+                if unlocked.is_empty() {
+                    "Nothing yet".to_string()
+                } else {
+                    unlocked.join(", ")
+                }
+            )
+        }
+        None => format!("Slot {}\nEmpty", slot + 1),
+    }
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    menu_assets: &MenuAssets,
+    button: SlotButton,
+    focus_index: &mut usize,
+    text: &str,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(150.0), Val::Px(50.0)),
+                margin: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            image: menu_assets.button.clone().into(),
+            color: Color::rgb(0.15, 0.15, 0.15).into(),
+            ..Default::default()
+        })
+        .insert(button)
+        .insert(FocusIndex(*focus_index))
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: menu_assets.pixel_font.clone(),
+                    font_size: 15.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+    *focus_index += 1;
+}
+
+fn spawn_menu(commands: &mut Commands, menu_assets: &MenuAssets) {
+    let mut focus_index = 0;
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                padding: UiRect::all(Val::Px(20.)),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            image: menu_assets.background.clone().into(),
+            ..Default::default()
+        })
+        .insert(SlotSelectMenu)
+        .with_children(|parent| {
+            // header wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(900.0), Val::Px(80.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        "Select a save slot",
+                        TextStyle {
+                            font: menu_assets.pixel_font.clone(),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+            // Slots wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(900.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for slot in 0..save::NUM_SLOTS {
+                        let data = save::load(slot);
+                        // Slot row
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Percent(100.0), Val::Px(90.0)),
+                                    justify_content: JustifyContent::FlexEnd,
+                                    align_items: AlignItems::Center,
+                                    padding: UiRect::all(Val::Px(10.0)),
+                                    ..Default::default()
+                                },
+                                color: Color::NONE.into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(NodeBundle {
+                                        style: Style {
+                                            size: Size::new(Val::Px(400.0), Val::Auto),
+                                            margin: UiRect {
+                                                right: Val::Auto,
+                                                ..Default::default()
+                                            },
+                                            ..Default::default()
+                                        },
+                                        color: Color::NONE.into(),
+                                        ..Default::default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn_bundle(TextBundle::from_section(
+                                            slot_description(slot, &data),
+                                            TextStyle {
+                                                font: menu_assets.pixel_font.clone(),
+                                                font_size: 13.0,
+                                                color: Color::WHITE,
+                                            },
+                                        ));
+                                    });
+                                if data.is_some() {
+                                    spawn_button(
+                                        parent,
+                                        menu_assets,
+                                        SlotButton::Play(slot),
+                                        &mut focus_index,
+                                        "Load",
+                                    );
+                                    spawn_button(
+                                        parent,
+                                        menu_assets,
+                                        SlotButton::Overwrite(slot),
+                                        &mut focus_index,
+                                        confirm_label(SlotButton::Overwrite(slot)).unwrap(),
+                                    );
+                                    spawn_button(
+                                        parent,
+                                        menu_assets,
+                                        SlotButton::Delete(slot),
+                                        &mut focus_index,
+                                        confirm_label(SlotButton::Delete(slot)).unwrap(),
+                                    );
+                                } else {
+                                    spawn_button(
+                                        parent,
+                                        menu_assets,
+                                        SlotButton::Play(slot),
+                                        &mut focus_index,
+                                        "New Game",
+                                    );
+                                }
+                            });
+                    }
+                });
+            // Back button wrapper
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(900.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    spawn_button(
+                        parent,
+                        menu_assets,
+                        SlotButton::Back,
+                        &mut focus_index,
+                        "Back",
+                    );
+                });
+        });
+}
+
+fn button_system(
+    mut commands: Commands,
+    interaction_query: Query<
+        (&SlotButton, &FocusIndex, &Interaction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    buttons: Query<(&SlotButton, &FocusIndex, &Children)>,
+    mut text_query: Query<&mut Text>,
+    menu: Query<Entity, With<SlotSelectMenu>>,
+    menu_actions: Res<Input<MenuAction>>,
+    mut focus: ResMut<MenuFocus>,
+    mut confirming: ResMut<Confirming>,
+    mut state: ResMut<bevy::prelude::State<State>>,
+    menu_assets: Res<MenuAssets>,
+    sound_channel: Res<AudioChannel<SoundChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let mut pressed = None;
+    for (button, index, interaction) in &interaction_query {
+        match *interaction {
+            Interaction::Clicked => pressed = Some(*button),
+            Interaction::Hovered => focus.0 = index.0,
+            Interaction::None => {}
+        }
+    }
+
+    let num_buttons = buttons.iter().count();
+    if let Some(focused) = menu::navigate(
+        &menu_actions,
+        &mut focus,
+        num_buttons,
+        &sound_channel,
+        &audio_assets,
+    ) {
+        pressed = buttons
+            .iter()
+            .find(|(_, index, _)| index.0 == focused)
+            .map(|(button, _, _)| *button);
+    }
+    if menu_actions.just_pressed(MenuAction::Back) {
+        pressed = Some(SlotButton::Back);
+    }
+
+    let button = match pressed {
+        Some(button) => button,
+        None => return,
+    };
+    sound_channel.play(audio_assets.blip1.clone());
+    match button {
+        SlotButton::Play(slot) => {
+            save::start_game(&mut commands, slot, save::load(slot));
+            state.set(State::LevelSelect).unwrap();
+        }
+        SlotButton::Overwrite(_) | SlotButton::Delete(_) if confirming.0 != Some(button) => {
+            confirming.0 = Some(button);
+            for (_, _, children) in buttons.iter().filter(|(other, _, _)| **other == button) {
+                for child in children.iter() {
+                    if let Ok(mut text) = text_query.get_mut(*child) {
+                        text.sections[0].value = "Sure?".to_string();
+                    }
+                }
+            }
+        }
+        SlotButton::Overwrite(slot) => {
+            save::start_game(&mut commands, slot, None);
+            state.set(State::LevelSelect).unwrap();
+        }
+        SlotButton::Delete(slot) => {
+            save::delete(slot);
+            confirming.0 = None;
+            // Rebuild the menu so the slot shows as empty
+            for entity in menu.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            spawn_menu(&mut commands, &menu_assets);
+            focus.0 = 0;
+        }
+        SlotButton::Back => {
+            state.set(State::MainMenu).unwrap();
+        }
+    }
+}
+
+/// Cancels the confirmation once the focus moves to another button, putting back the label of
+/// every button that isn't waiting for its second click
+fn reset_confirming(
+    focus: Res<MenuFocus>,
+    mut confirming: ResMut<Confirming>,
+    buttons: Query<(&SlotButton, &FocusIndex, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !focus.is_changed() && !confirming.is_changed() {
+        return;
+    }
+    let moved_off = buttons
+        .iter()
+        .any(|(button, index, _)| confirming.0 == Some(*button) && index.0 != focus.0);
+    if moved_off {
+        confirming.0 = None;
+    }
+    for (button, _, children) in buttons.iter() {
+        let label = match confirm_label(*button) {
+            Some(label) if confirming.0 != Some(*button) => label,
+            _ => continue,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.to_string();
+                }
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<SlotSelectMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Confirming>();
+    commands.remove_resource::<MenuFocus>();
+}
@@ -0,0 +1,69 @@
+use bevy::{
+    prelude::{EventReader, Plugin, Res, ResMut, SystemSet},
+    time::Time,
+};
+
+use crate::{damage::DamageSource, events::GameEvent, state::State};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Stats>()
+            .add_system_set(SystemSet::on_enter(State::InGame).with_system(reset))
+            .add_system_set(
+                // Keeps counting while the ability menu is open on top of the game
+                SystemSet::on_in_stack_update(State::InGame).with_system(record),
+            );
+    }
+}
+
+/// Statistics about the current visit to a world, shown on the results screen
+#[derive(Default, Clone, Debug)]
+pub struct Stats {
+    pub deaths: u32,
+    pub spike_deaths: u32,
+    pub enemy_deaths: u32,
+    pub lava_deaths: u32,
+    pub water_deaths: u32,
+    pub fire_deaths: u32,
+    pub fireballs_cast: u32,
+    pub enemies_killed: u32,
+    pub lava_cooled: u32,
+    pub blocks_pushed: u32,
+    /// Seconds since the world was entered
+    pub time: f64,
+}
+
+impl Stats {
+    pub fn add(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Death(source) => {
+                self.deaths += 1;
+                match source {
+                    DamageSource::Spikes => self.spike_deaths += 1,
+                    DamageSource::Enemy => self.enemy_deaths += 1,
+                    DamageSource::Lava => self.lava_deaths += 1,
+                    DamageSource::Water => self.water_deaths += 1,
+                    DamageSource::Fire => self.fire_deaths += 1,
+                }
+            }
+            GameEvent::FireballCast => self.fireballs_cast += 1,
+            GameEvent::EnemyKilled(_) => self.enemies_killed += 1,
+            GameEvent::LavaCooled(_) => self.lava_cooled += 1,
+            GameEvent::BlockPushed => self.blocks_pushed += 1,
+            GameEvent::Equipped(_) | GameEvent::WorldCompleted => {}
+        }
+    }
+}
+
+fn reset(mut stats: ResMut<Stats>) {
+    *stats = Stats::default();
+}
+
+fn record(time: Res<Time>, mut stats: ResMut<Stats>, mut events: EventReader<GameEvent>) {
+    stats.time += time.delta_seconds_f64();
+    for event in events.iter() {
+        stats.add(event);
+    }
+}
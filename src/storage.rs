@@ -0,0 +1,57 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::{fs, path::PathBuf};
+
+    fn path(key: &str) -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "Elemental Sorcerer")
+            .map(|dirs| dirs.data_dir().join(key))
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        fs::read_to_string(path(key)?).ok()
+    }
+
+    pub fn write(key: &str, contents: &str) -> Result<(), String> {
+        let path = path(key).ok_or("Could not find user data directory")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    pub fn remove(key: &str) -> Result<(), String> {
+        match path(key) {
+            Some(path) if path.exists() => fs::remove_file(path).map_err(|e| e.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    const PREFIX: &str = "elemental-sorcerer/";
+
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        storage()?.get_item(&format!("{}{}", PREFIX, key)).ok()?
+    }
+
+    pub fn write(key: &str, contents: &str) -> Result<(), String> {
+        storage()
+            .ok_or("Local storage is not available")?
+            .set_item(&format!("{}{}", PREFIX, key), contents)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn remove(key: &str) -> Result<(), String> {
+        storage()
+            .ok_or("Local storage is not available")?
+            .remove_item(&format!("{}{}", PREFIX, key))
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+pub use backend::{read, remove, write};
@@ -0,0 +1,128 @@
+use bevy::prelude::{KeyCode, Vec3, With};
+
+use crate::{
+    abilities::{Element, Equipment, StonePlatform},
+    combinations::Combinations,
+    damage::{Hurtbox, Killed},
+    entity::{enemy::EnemyStats, goblin::GoblinAi, lava::Lava},
+    input::{Action, KeyBindings},
+};
+
+use super::Harness;
+
+/// A lava tile with more lava to its left, so the player can be placed over the pool without
+/// ending up inside a wall
+fn lava_pool(harness: &mut Harness) -> Vec3 {
+    let positions: Vec<Vec3> = harness
+        .entities::<With<Lava>>()
+        .into_iter()
+        .map(|lava| harness.position(lava))
+        .collect();
+    *positions
+        .iter()
+        .find(|position| {
+            positions
+                .iter()
+                .any(|other| (**position - *other - Vec3::new(16.0, 0.0, 0.0)).length() < 1.0)
+        })
+        .or_else(|| positions.first())
+        .expect("Level should contain lava")
+}
+
+#[test]
+fn descriptions_name_the_bound_ability_key() {
+    let combinations =
+        ron::from_str::<Combinations>(include_str!("../../assets/combinations.combos.ron"))
+            .unwrap();
+    let mut key_bindings = KeyBindings::default();
+    key_bindings.0.insert(Action::Ability, vec![KeyCode::C]);
+
+    assert_eq!(
+        combinations.description((Some(Equipment::Staff), Some(Element::Fire)), &key_bindings),
+        "<c> to cast Fireball"
+    );
+    assert_eq!(
+        combinations.description((None, None), &key_bindings),
+        "No effect"
+    );
+}
+
+#[test]
+fn fireball_kills_goblin() {
+    let mut harness = Harness::new();
+    harness.debug_settings().imortality = true;
+    let goblin = harness.entities::<(With<GoblinAi>, With<Hurtbox>)>()[0];
+    harness.equip(Equipment::Staff, Element::Fire);
+    let health = |harness: &Harness| harness.app.world.get::<EnemyStats>(goblin).unwrap().health;
+    let max = health(&harness);
+
+    // Goblins take two fireballs by default
+    let position = harness.position(goblin);
+    harness.teleport_player(position - Vec3::new(24.0, 0.0, 0.0));
+    harness.cast();
+    assert!(harness.step_until(60, |harness| health(harness) < max));
+    assert!(harness.has::<Hurtbox>(goblin));
+
+    let position = harness.position(goblin);
+    harness.teleport_player(position - Vec3::new(24.0, 0.0, 0.0));
+    harness.cast();
+    assert!(harness.step_until(60, |harness| !harness.has::<Hurtbox>(goblin)));
+}
+
+#[test]
+fn water_turns_lava_to_stone() {
+    let mut harness = Harness::new();
+    harness.debug_settings().imortality = true;
+    let lava_before = harness.count::<With<Lava>>();
+    let position = lava_pool(&mut harness);
+    harness.teleport_player(position + Vec3::new(-10.0, 12.0, 0.0));
+    harness.equip(Equipment::Staff, Element::Water);
+    harness.cast();
+
+    assert!(harness.step_until(60, |harness| harness.count::<With<Lava>>() < lava_before));
+}
+
+#[test]
+fn lava_kills_player() {
+    let mut harness = Harness::new();
+    let position = lava_pool(&mut harness);
+    harness.teleport_player(position + Vec3::new(0.0, 12.0, 0.0));
+    let player = harness.player();
+
+    assert!(harness.step_until(60, |harness| harness.has::<Killed>(player)));
+}
+
+#[test]
+fn cloak_of_fire_survives_lava() {
+    let mut harness = Harness::new();
+    let position = lava_pool(&mut harness);
+    harness.teleport_player(position + Vec3::new(0.0, 12.0, 0.0));
+    harness.equip(Equipment::Cloak, Element::Fire);
+    let player = harness.player();
+
+    assert!(!harness.step_until(60, |harness| harness.has::<Killed>(player)));
+}
+
+#[test]
+fn earth_staff_raises_platform() {
+    let mut harness = Harness::new();
+    harness.debug_settings().imortality = true;
+    harness.equip(Equipment::Staff, Element::Earth);
+    harness.cast();
+
+    assert!(harness.step_until(10, |harness| harness.count::<With<StonePlatform>>() == 1));
+    // Crumbles after its lifetime
+    assert!(harness.step_until(300, |harness| harness.count::<With<StonePlatform>>() == 0));
+}
+
+#[test]
+fn water_amulet_cools_nearby_lava() {
+    let mut harness = Harness::new();
+    harness.debug_settings().imortality = true;
+    let lava_before = harness.count::<With<Lava>>();
+    let position = lava_pool(&mut harness);
+    harness.teleport_player(position + Vec3::new(0.0, 16.0, 0.0));
+    harness.equip(Equipment::Amulet, Element::Water);
+
+    assert!(harness.step_until(10, |harness| harness.count::<With<Lava>>() < lava_before));
+}
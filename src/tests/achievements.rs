@@ -0,0 +1,39 @@
+use crate::{
+    abilities::Equipment,
+    achievements::{AchievementProgress, Achievements, Condition, WorldTotals},
+};
+
+#[test]
+fn achievement_definitions_parse() {
+    let achievements =
+        ron::from_str::<Achievements>(include_str!("../../assets/achievements.achievements.ron"))
+            .expect("Achievements should parse");
+    let mut ids: Vec<_> = achievements.achievements.iter().map(|a| &a.id).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), achievements.achievements.len());
+}
+
+#[test]
+fn completion_conditions_depend_on_the_run() {
+    let totals = WorldTotals {
+        goblins: 2,
+        lava: 0,
+    };
+    let mut progress = AchievementProgress::default();
+    progress.goblins.insert("a".to_string());
+    assert!(!progress.is_met(Condition::DefeatAllGoblins, totals));
+    progress.goblins.insert("b".to_string());
+    assert!(progress.is_met(Condition::DefeatAllGoblins, totals));
+    // A world without lava can't be cooled
+    assert!(!progress.is_met(Condition::CoolAllLava, totals));
+
+    progress.equipped.push(Equipment::Cloak);
+    progress.died = true;
+    assert!(!progress.is_met(Condition::CompleteWorld, totals));
+    progress.completed = true;
+    assert!(progress.is_met(Condition::CompleteWorld, totals));
+    assert!(!progress.is_met(Condition::CompleteWithoutDying, totals));
+    assert!(!progress.is_met(Condition::CompleteWithout(Equipment::Cloak), totals));
+    assert!(progress.is_met(Condition::CompleteWithout(Equipment::Amulet), totals));
+}
@@ -0,0 +1,121 @@
+use bevy::{
+    prelude::{Entity, Transform, TransformBundle, Vec2, Vec3, With},
+    sprite::TextureAtlasSprite,
+};
+use bevy_ecs_ldtk::EntityInstance;
+
+use crate::{
+    abilities::{Cast, Element, Equipment},
+    boss::{ArenaSeal, BossFight, BossHealth, Bosses, Hit},
+    damage::Hurtbox,
+    entity::{
+        ability::Ability,
+        boss::{Boss, BossArena},
+        goblin::{AnimationState, Enemy},
+    },
+    physics::PhysicsObjectBundle,
+};
+
+use super::Harness;
+
+#[test]
+fn armor_must_be_broken_before_the_boss_is_hurt() {
+    let bosses = ron::from_str::<Bosses>(include_str!("../../assets/bosses.bosses.ron"))
+        .expect("Bosses should parse");
+    let golem = bosses.get("magma_golem").expect("Golem should be defined");
+    let fire = Cast(Equipment::Staff, Element::Fire);
+    let water = Cast(Equipment::Staff, Element::Water);
+    let mut health = BossHealth::new(golem);
+
+    assert_eq!(health.hit(golem, water), Hit::Immune);
+    for _ in 0..3 {
+        assert_eq!(health.hit(golem, fire), Hit::Damaged);
+    }
+    assert_eq!(health.hit(golem, fire), Hit::PhaseCleared);
+    assert_eq!(health.phase, 1);
+
+    // Armored until cooled twice
+    assert_eq!(health.hit(golem, fire), Hit::Immune);
+    assert_eq!(health.hit(golem, water), Hit::ArmorCracked);
+    assert_eq!(health.hit(golem, water), Hit::ArmorBroken);
+    assert_eq!(health.hit(golem, water), Hit::Immune);
+    let before = health.fraction(golem);
+    assert_eq!(health.hit(golem, fire), Hit::Damaged);
+    assert!(health.fraction(golem) < before);
+}
+
+/// The first level's open air, where an arena and its boss are set up around the player
+fn spawn_fight(harness: &mut Harness) -> Entity {
+    let player = harness.player();
+    let position = harness.position(player) + Vec3::new(18.0 * 16.0, 16.0, 0.0);
+    harness.teleport_player(position);
+    let arena = EntityInstance {
+        identifier: "BossArena".to_string(),
+        width: 160,
+        height: 96,
+        ..Default::default()
+    };
+    harness
+        .app
+        .world
+        .spawn()
+        .insert_bundle(TransformBundle::from_transform(
+            Transform::from_translation(position),
+        ))
+        .insert(BossArena {
+            boss: "magma_golem".to_string(),
+            size: Vec2::new(160.0, 96.0),
+        })
+        .insert_bundle(PhysicsObjectBundle::from(arena));
+    harness
+        .app
+        .world
+        .spawn()
+        .insert_bundle(TransformBundle::from_transform(
+            Transform::from_translation(position + Vec3::new(48.0, 0.0, 0.0)),
+        ))
+        .insert_bundle(PhysicsObjectBundle::from(EntityInstance {
+            identifier: "Boss".to_string(),
+            ..Default::default()
+        }))
+        .insert(TextureAtlasSprite::default())
+        .insert(AnimationState::default())
+        .insert(Enemy)
+        .insert(Hurtbox)
+        .insert(Boss {
+            id: "magma_golem".to_string(),
+        })
+        .id()
+}
+
+#[test]
+fn entering_the_arena_seals_it_until_the_boss_is_defeated() {
+    let mut harness = Harness::new();
+    harness.debug_settings().imortality = true;
+    let boss = spawn_fight(&mut harness);
+
+    assert!(harness.step_until(30, |harness| harness
+        .app
+        .world
+        .get_resource::<BossFight>()
+        .is_some()));
+    assert_eq!(harness.count::<With<ArenaSeal>>(), 2);
+
+    // One hit away from the end of the last phase
+    harness.app.world.resource_mut::<BossFight>().health = BossHealth {
+        phase: 2,
+        health: 1,
+        armor: 0,
+    };
+    harness.equip(Equipment::Staff, Element::Fire);
+    harness.cast();
+    assert!(harness.step_until(60, |harness| !harness.has::<Hurtbox>(boss)));
+
+    harness.step(2);
+    let mut orbs = harness.app.world.query::<&Ability>();
+    assert!(orbs
+        .iter(&harness.app.world)
+        .any(|ability| *ability == Ability::Amulet));
+    assert!(harness.app.world.get_resource::<BossFight>().is_none());
+    assert_eq!(harness.count::<With<ArenaSeal>>(), 0);
+}
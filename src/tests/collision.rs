@@ -0,0 +1,25 @@
+use bevy::prelude::With;
+
+use crate::entity::{checkpoint::Checkpoint, player::Player};
+
+use super::Harness;
+
+#[test]
+fn touching_a_checkpoint_sets_it() {
+    let mut harness = Harness::new();
+    let checkpoint = harness.entities::<With<Checkpoint>>()[0];
+    let position = harness.position(checkpoint);
+    harness.teleport_player(position);
+    let player = harness.player();
+
+    assert!(harness.step_until(30, |harness| {
+        harness
+            .app
+            .world
+            .get::<Player>(player)
+            .unwrap()
+            .near_checkpoint
+    }));
+    let set = harness.app.world.get::<Player>(player).unwrap().checkpoint;
+    assert_eq!(set.x, position.x);
+}
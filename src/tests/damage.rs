@@ -0,0 +1,68 @@
+use bevy::prelude::With;
+
+use crate::{
+    damage::{Difficulty, Health, Hurtbox, Invulnerable, Killed},
+    entity::goblin::GoblinAi,
+    stats::Stats,
+};
+
+use super::Harness;
+
+#[test]
+fn enemy_contact_costs_health() {
+    let mut harness = Harness::new();
+    let goblin = harness.entities::<(With<GoblinAi>, With<Hurtbox>)>()[0];
+    let position = harness.position(goblin);
+    harness.teleport_player(position);
+    let player = harness.player();
+
+    assert!(harness.step_until(30, |harness| harness.has::<Invulnerable>(player)));
+    assert!(!harness.has::<Killed>(player));
+    let health = *harness.app.world.get::<Health>(player).unwrap();
+    assert_eq!(health.current, health.max - 1);
+}
+
+#[test]
+fn one_hit_kill_difficulty() {
+    let mut harness = Harness::new();
+    harness.app.insert_resource(Difficulty::OneHitKill);
+    let goblin = harness.entities::<(With<GoblinAi>, With<Hurtbox>)>()[0];
+    let position = harness.position(goblin);
+    harness.teleport_player(position);
+    let player = harness.player();
+
+    assert!(harness.step_until(30, |harness| harness.has::<Killed>(player)));
+}
+
+#[test]
+fn deaths_are_counted_by_cause() {
+    let mut harness = Harness::new();
+    harness.app.insert_resource(Difficulty::OneHitKill);
+    let goblin = harness.entities::<(With<GoblinAi>, With<Hurtbox>)>()[0];
+    let position = harness.position(goblin);
+    harness.teleport_player(position);
+
+    let stats = |harness: &Harness| harness.app.world.resource::<Stats>().clone();
+
+    assert!(harness.step_until(30, |harness| stats(harness).deaths == 1));
+    assert_eq!(stats(&harness).enemy_deaths, 1);
+    assert_eq!(stats(&harness).spike_deaths, 0);
+}
+
+#[test]
+fn staying_on_a_hazard_hurts_again_after_invulnerability() {
+    let mut harness = Harness::new();
+    let goblin = harness.entities::<(With<GoblinAi>, With<Hurtbox>)>()[0];
+    let player = harness.player();
+    let health = |harness: &Harness| harness.app.world.get::<Health>(player).unwrap().current;
+    let max = health(&harness);
+
+    // Held on the goblin so the contact never stops
+    let hurt_twice = harness.step_until(120, |harness| {
+        let position = harness.position(goblin);
+        harness.teleport_player(position);
+        health(harness) <= max - 2
+    });
+    assert!(hurt_twice);
+    assert!(!harness.has::<Killed>(player));
+}
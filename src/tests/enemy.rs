@@ -0,0 +1,90 @@
+use bevy::prelude::{Entity, Vec3, With};
+use heron::Velocity;
+
+use crate::{
+    abilities::{Element, Equipment},
+    damage::Hurtbox,
+    entity::{
+        enemy::{EnemyStats, Slowed},
+        goblin::GoblinAi,
+    },
+};
+
+use super::Harness;
+
+#[test]
+fn multipliers_scale_fire_damage_and_slowing() {
+    let mut goblin = EnemyStats::default();
+    goblin.burn();
+    assert!(!goblin.is_dead());
+    goblin.burn();
+    assert!(goblin.is_dead());
+
+    let mut weak = EnemyStats {
+        fire: 2.0,
+        ..Default::default()
+    };
+    weak.burn();
+    assert!(weak.is_dead());
+
+    let mut immune = EnemyStats {
+        fire: 0.0,
+        water: 0.0,
+        ..Default::default()
+    };
+    immune.burn();
+    assert!(!immune.is_dead());
+    assert_eq!(immune.slowed_speed(), 1.0);
+    assert_eq!(EnemyStats::default().slowed_speed(), 0.5);
+}
+
+#[test]
+fn imps_are_put_out_by_water_and_their_own_fireballs() {
+    let mut doused = EnemyStats::base("FireImp");
+    doused.burn();
+    assert!(!doused.is_dead());
+    doused.douse();
+    assert!(doused.is_dead());
+
+    let mut deflected = EnemyStats::base("FireImp");
+    deflected.burn_deflected();
+    assert!(deflected.is_dead());
+
+    // Only slowed by water
+    let mut goblin = EnemyStats::default();
+    goblin.douse();
+    assert_eq!(goblin.health, goblin.max_health);
+}
+
+/// Casts at a goblin from just to its left
+fn cast_at(harness: &mut Harness, goblin: Entity, element: Element) {
+    let position = harness.position(goblin);
+    harness.teleport_player(position - Vec3::new(24.0, 0.0, 0.0));
+    harness.equip(Equipment::Staff, element);
+    harness.cast();
+}
+
+#[test]
+fn each_element_affects_a_goblin() {
+    let mut harness = Harness::new();
+    harness.debug_settings().imortality = true;
+    let goblin = harness.entities::<(With<GoblinAi>, With<Hurtbox>)>()[0];
+    let stats = |harness: &Harness| harness.app.world.get::<EnemyStats>(goblin).unwrap().clone();
+
+    cast_at(&mut harness, goblin, Element::Air);
+    assert!(
+        harness.step_until(60, |harness| stats(harness).is_flashing()
+            && harness.app.world.get::<Velocity>(goblin).unwrap().linear.x > 0.0)
+    );
+
+    cast_at(&mut harness, goblin, Element::Water);
+    assert!(harness.step_until(60, |harness| harness.has::<Slowed>(goblin)));
+    assert_eq!(stats(&harness).health, stats(&harness).max_health);
+
+    cast_at(&mut harness, goblin, Element::Fire);
+    assert!(harness.step_until(60, |harness| stats(harness).health
+        < stats(harness).max_health));
+    assert!(harness.has::<Hurtbox>(goblin));
+    cast_at(&mut harness, goblin, Element::Fire);
+    assert!(harness.step_until(60, |harness| !harness.has::<Hurtbox>(goblin)));
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::{Entity, Transform, TransformBundle, Vec3};
+use heron::{CollisionShape, RigidBody, Velocity};
+
+use crate::{
+    abilities::{Element, Equipment},
+    damage::Invulnerable,
+    entity::fire_imp::{self, ImpFireball},
+    physics::PhysicsObjectBundle,
+};
+
+use super::Harness;
+
+/// Throws an imp's fireball at the player from the right
+fn throw_at_player(harness: &mut Harness) -> Entity {
+    let player = harness.player();
+    let position = harness.position(player) + Vec3::new(24.0, 0.0, 0.0);
+    harness
+        .app
+        .world
+        .spawn()
+        .insert_bundle(TransformBundle::from_transform(
+            Transform::from_translation(position),
+        ))
+        .insert_bundle(PhysicsObjectBundle {
+            collider: CollisionShape::Sphere { radius: 4.0 },
+            rb: RigidBody::KinematicVelocityBased,
+            velocity: Velocity::from_linear(Vec3::new(-90.0, 0.0, 0.0)),
+            layer: fire_imp::fireball_layers(),
+            ..Default::default()
+        })
+        .insert(ImpFireball)
+        .id()
+}
+
+#[test]
+fn imp_fireball_hurts_player() {
+    let mut harness = Harness::new();
+    let player = harness.player();
+    let fireball = throw_at_player(&mut harness);
+
+    assert!(harness.step_until(30, |harness| harness.has::<Invulnerable>(player)));
+    harness.step(1);
+    assert!(harness.app.world.get_entity(fireball).is_none());
+}
+
+#[test]
+fn fire_cloak_resists_imp_fireballs() {
+    let mut harness = Harness::new();
+    harness.equip(Equipment::Cloak, Element::Fire);
+    let player = harness.player();
+    let fireball = throw_at_player(&mut harness);
+
+    assert!(harness.step_until(30, |harness| harness
+        .app
+        .world
+        .get_entity(fireball)
+        .is_none()));
+    assert!(!harness.has::<Invulnerable>(player));
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::Vec2;
+
+use crate::{
+    entity::player::AnimationState,
+    ghost::{Ghost, GhostFrame},
+};
+
+fn frame(time: f64, x: f32) -> GhostFrame {
+    GhostFrame {
+        time,
+        position: [x, 0.0],
+        flip_x: false,
+        animation: AnimationState::Walking,
+    }
+}
+
+#[test]
+fn ghost_follows_recorded_frames() {
+    let ghost = Ghost {
+        version: 1,
+        world: "main".to_string(),
+        total: 0.5,
+        frames: vec![frame(0.0, 0.0), frame(0.25, 4.0), frame(0.5, 8.0)],
+    };
+    let ghost = Ghost::parse(&ron::to_string(&ghost).unwrap()).expect("Ghost should round trip");
+
+    assert_eq!(
+        ghost.frame_at(0.3).map(|frame| frame.position[0]),
+        Some(4.0)
+    );
+    assert_eq!(
+        ghost.frame_at(0.5).map(|frame| frame.position[0]),
+        Some(8.0)
+    );
+    // Stays on the last frame once the run is over
+    assert_eq!(
+        ghost.frame_at(9.0).map(|frame| frame.position[0]),
+        Some(8.0)
+    );
+    assert!(ghost.frame_at(-1.0).is_none());
+}
+
+#[test]
+fn ghost_position_is_interpolated_between_samples() {
+    let ghost = Ghost {
+        version: 1,
+        world: "main".to_string(),
+        total: 0.5,
+        frames: vec![frame(0.0, 0.0), frame(0.25, 4.0), frame(0.5, 8.0)],
+    };
+
+    assert_eq!(ghost.position_at(0.125), Some(Vec2::new(2.0, 0.0)));
+    assert_eq!(ghost.position_at(0.25), Some(Vec2::new(4.0, 0.0)));
+    assert_eq!(ghost.position_at(9.0), Some(Vec2::new(8.0, 0.0)));
+    assert!(ghost.position_at(-1.0).is_none());
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::{Entity, IVec2, Vec2, Vec3, With};
+use bevy_ecs_ldtk::EntityInstance;
+use heron::Velocity;
+
+use crate::{
+    entity::goblin::{GoblinAi, GoblinState},
+    level::{WallTiles, GRID_SIZE},
+};
+
+use super::Harness;
+
+#[test]
+fn walls_block_line_of_sight() {
+    // A wall one tile tall between x 32 and 48
+    let walls = WallTiles([IVec2::new(2, 0)].into_iter().collect());
+
+    assert!(walls.is_wall(Vec2::new(40.0, 8.0)));
+    assert!(!walls.line_of_sight(Vec2::new(8.0, 8.0), Vec2::new(72.0, 8.0)));
+    assert!(walls.line_of_sight(Vec2::new(8.0, 24.0), Vec2::new(72.0, 24.0)));
+    assert!(walls.line_of_sight(Vec2::new(8.0, 8.0), Vec2::new(24.0, 8.0)));
+}
+
+#[test]
+fn goblin_chases_visible_player_and_stops_at_ledge() {
+    let mut harness = Harness::new();
+    // The goblin in the first level facing left on the platform that ends at tile 40
+    let goblin = {
+        let world = &mut harness.app.world;
+        world
+            .query_filtered::<(Entity, &EntityInstance), With<GoblinAi>>()
+            .iter(world)
+            .find(|(_, entity_instance)| {
+                entity_instance.iid == "fd6950c0-02f0-11ed-9260-27ee0133cc3b"
+            })
+            .map(|(entity, _)| entity)
+            .expect("Goblin should be spawned")
+    };
+    let start = harness.position(goblin);
+    let ledge = (start.x / GRID_SIZE).floor() * GRID_SIZE - 5.0 * GRID_SIZE;
+    // Below the ledge, in sight but out of reach
+    harness.teleport_player(start + Vec3::new(-8.0 * GRID_SIZE, 0.0, 0.0));
+
+    let chasing = harness.step_until(120, |harness| {
+        harness.app.world.get::<GoblinAi>(goblin).unwrap().state == GoblinState::Chase
+    });
+    assert!(chasing, "Goblin should chase the player");
+    harness.step(180);
+
+    let position = harness.position(goblin);
+    assert!(position.x < start.x - GRID_SIZE, "Goblin should move");
+    assert!(position.x > ledge, "Goblin should stop at the ledge");
+    assert!((position.y - start.y).abs() < 1.0, "Goblin shouldn't fall");
+    assert_eq!(
+        harness.app.world.get::<GoblinAi>(goblin).unwrap().state,
+        GoblinState::Chase
+    );
+    assert_eq!(
+        harness.app.world.get::<Velocity>(goblin).unwrap().linear.x,
+        0.0
+    );
+}
@@ -0,0 +1,170 @@
+use std::{collections::HashSet, path::Path};
+
+use bevy::prelude::{
+    Assets, BuildChildren, Children, GlobalTransform, Handle, Transform, TransformBundle, With,
+};
+use bevy_ecs_ldtk::{ldtk::LdtkJson, prelude::FieldValue, EntityInstance, LdtkLevel};
+
+use crate::{
+    entity::{
+        fields::{FieldError, FieldErrorKind, FieldErrors, InvalidEntity},
+        player::Player,
+        registered::REGISTERED_ENTITIES,
+    },
+    world::Worlds,
+};
+
+use super::Harness;
+
+#[test]
+fn level_has_no_invalid_fields() {
+    let mut harness = Harness::new();
+
+    assert_eq!(harness.count::<With<InvalidEntity>>(), 0);
+}
+
+#[test]
+fn invalid_entity_is_reported_and_replaced() {
+    let mut harness = Harness::new();
+    // A fan without its required `Direction`
+    let field_errors = FieldErrors::from(EntityInstance {
+        identifier: "Fan".to_string(),
+        iid: "broken-fan".to_string(),
+        ..Default::default()
+    });
+    assert_eq!(
+        field_errors.errors,
+        vec![FieldError {
+            field: "Direction",
+            kind: FieldErrorKind::Missing,
+        }]
+    );
+
+    // Placed on a layer of the first level, the same as LDtk would
+    let layer = {
+        let world = &mut harness.app.world;
+        let levels = world
+            .query::<(&Handle<LdtkLevel>, &Children)>()
+            .iter(world)
+            .map(|(handle, children)| (handle.clone(), children[0]))
+            .collect::<Vec<_>>();
+        let ldtk_levels = world.resource::<Assets<LdtkLevel>>();
+        levels
+            .into_iter()
+            .find(|(handle, _)| {
+                ldtk_levels
+                    .get(handle)
+                    .map_or(false, |level| level.level.identifier == "Level_1")
+            })
+            .map(|(_, layer)| layer)
+            .expect("Level_1 should be spawned")
+    };
+    let transform = Transform::from_xyz(24.0, 40.0, 0.0);
+    let fan = harness
+        .app
+        .world
+        .spawn()
+        .insert_bundle(TransformBundle::from_transform(transform))
+        .insert(field_errors)
+        .id();
+    harness.app.world.entity_mut(layer).push_children(&[fan]);
+    harness.step(1);
+
+    assert!(harness.app.world.get_entity(fan).is_none());
+    let placeholders = harness.entities::<With<InvalidEntity>>();
+    assert_eq!(placeholders.len(), 1);
+    let placeholder = placeholders[0];
+    let report = &harness
+        .app
+        .world
+        .get::<InvalidEntity>(placeholder)
+        .unwrap()
+        .0;
+    assert!(report.contains("Level_1 Fan (broken-fan) field Direction: missing or null"));
+    assert_eq!(
+        harness
+            .app
+            .world
+            .get::<Transform>(placeholder)
+            .unwrap()
+            .translation,
+        transform.translation
+    );
+    assert!(harness.has::<GlobalTransform>(placeholder));
+}
+
+/// The first entity called `identifier` in the level project
+fn level_entity(identifier: &str) -> EntityInstance {
+    let project =
+        serde_json::from_str::<LdtkJson>(include_str!("../../assets/levels/level.ldtk")).unwrap();
+    project
+        .levels
+        .iter()
+        .flat_map(|level| level.layer_instances.iter().flatten())
+        .flat_map(|layer| layer.entity_instances.iter())
+        .find(|entity| entity.identifier == identifier)
+        .cloned()
+        .unwrap()
+}
+
+#[test]
+fn out_of_range_fields_are_errors_and_optional_fields_can_be_left_out() {
+    let mut fire_imp = level_entity("FireImp");
+    let cooldown = fire_imp
+        .field_instances
+        .iter_mut()
+        .find(|field| field.identifier == "Cooldown")
+        .unwrap();
+    cooldown.value = FieldValue::Float(Some(0.0));
+    assert_eq!(
+        FieldErrors::from(fire_imp).errors,
+        vec![FieldError {
+            field: "Cooldown",
+            kind: FieldErrorKind::InvalidValue("0".to_string()),
+        }]
+    );
+
+    // Goblins without a patrol point stand still facing right
+    let mut goblin = level_entity("Goblin");
+    goblin
+        .field_instances
+        .retain(|field| field.identifier != "Point" && field.identifier != "FaceLeft");
+    assert_eq!(FieldErrors::from(goblin).errors, vec![]);
+}
+
+#[test]
+fn new_game_starts_at_player_entity() {
+    let mut harness = Harness::new();
+    let player = harness.player();
+
+    // The Player entity is 2 tiles into Level_1, which starts at x = -1104
+    let checkpoint = harness.app.world.get::<Player>(player).unwrap().checkpoint;
+    assert_eq!(checkpoint.x, -1064.0);
+}
+
+#[test]
+fn registered_entities_match_level_plugin() {
+    let mut registered = include_str!("../level.rs")
+        .split(".register_ldtk_entity::<")
+        .skip(1)
+        .filter_map(|call| call.split('"').nth(1))
+        .collect::<Vec<_>>();
+    registered.sort_unstable();
+    let mut listed = REGISTERED_ENTITIES.to_vec();
+    listed.sort_unstable();
+    assert_eq!(registered, listed);
+}
+
+#[test]
+fn worlds_have_unique_ids_and_existing_projects() {
+    let worlds = ron::from_str::<Worlds>(include_str!("../../assets/worlds.worlds.ron")).unwrap();
+    let mut ids = HashSet::new();
+    for world in &worlds.worlds {
+        assert!(ids.insert(&world.id), "duplicate world id {}", world.id);
+        assert!(
+            Path::new("assets").join(&world.path).exists(),
+            "{} is missing",
+            world.path
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    asset::AssetPlugin,
+    core::CorePlugin,
+    ecs::{component::Component, query::ReadOnlyWorldQuery},
+    hierarchy::HierarchyPlugin,
+    input::{mouse::MouseWheel, Input},
+    prelude::{
+        App, AssetServer, Assets, CoreStage, Entity, GlobalTransform, Image, Mesh, Mut, ResMut,
+        Shader, Transform, Vec3, With, Without,
+    },
+    sprite::{ColorMaterial, TextureAtlas},
+    time::Time,
+    transform::TransformPlugin,
+};
+use bevy_ecs_ldtk::LdtkPlugin;
+use bevy_kira_audio::{Audio, AudioChannel};
+use heron::{PhysicsPlugin, PhysicsSteps};
+
+use crate::{
+    abilities::{Element, Equipment},
+    audio::{AudioAssets, MusicChannel, SoundChannel},
+    boss::{BossPlugin, Bosses},
+    combinations::{Combinations, CombinationsPlugin},
+    debug::DebugSettings,
+    entity::player::{AwaitingStart, Player},
+    input::{Action, Controllable},
+    level::LevelPlugin,
+    physics,
+    replay::ReplayState,
+    save::{SaveEvent, WorldProgress},
+    speedrun::SpeedrunPlugin,
+    state::{game::GamePlugin, load_game::GameAssets, State},
+    stats::StatsPlugin,
+    world::{CurrentWorld, Worlds},
+};
+
+mod abilities;
+mod achievements;
+mod boss;
+mod collision;
+mod damage;
+mod enemy;
+mod fire_imp;
+mod ghost;
+mod goblin;
+mod level;
+mod replay;
+mod speedrun;
+mod wisp;
+
+/// Frames to wait for the level to load before giving up
+const LOAD_TIMEOUT: usize = 5000;
+
+/// Clock that `Time` is driven from instead of the system clock
+struct SimulatedClock(Instant);
+
+fn advance_time(mut time: ResMut<Time>, mut clock: ResMut<SimulatedClock>) {
+    clock.0 += Duration::from_secs_f64(1.0 / physics::STEPS_PER_SECOND);
+    time.update_with_instant(clock.0);
+}
+
+/// The game without a window, renderer or audio, advanced one physics step per frame
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    /// Builds the app, loads the first world and waits until the player has spawned at the start
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin::default())
+            .insert_resource(Time::default())
+            .insert_resource(SimulatedClock(Instant::now()))
+            .add_system_to_stage(CoreStage::First, advance_time)
+            .add_plugin(AssetPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(TransformPlugin)
+            // Asset types the render plugins would normally register
+            .add_asset::<Image>()
+            .add_asset::<Shader>()
+            .add_asset::<Mesh>()
+            .add_asset::<ColorMaterial>()
+            .add_asset::<TextureAtlas>()
+            .add_event::<MouseWheel>()
+            .add_event::<SaveEvent>()
+            .insert_resource(Input::<Action>::default())
+            .insert_resource(DebugSettings::default())
+            .insert_resource(ReplayState::Idle)
+            // Sounds are queued on the channels but never played
+            .insert_resource(Audio::default())
+            .insert_resource(AudioChannel::<MusicChannel>::default())
+            .insert_resource(AudioChannel::<SoundChannel>::default())
+            .add_state(State::InGame)
+            .add_plugin(LdtkPlugin)
+            .add_plugin(PhysicsPlugin::default())
+            .insert_resource(PhysicsSteps::from_steps_per_seconds(
+                physics::STEPS_PER_SECOND as f32,
+            ))
+            .add_plugin(LevelPlugin)
+            .add_plugin(GamePlugin)
+            .add_plugin(StatsPlugin)
+            .add_plugin(SpeedrunPlugin)
+            .add_plugin(CombinationsPlugin)
+            .add_plugin(BossPlugin)
+            .insert_resource(
+                ron::from_str::<Combinations>(include_str!("../../assets/combinations.combos.ron"))
+                    .expect("Combinations should parse"),
+            );
+        let bosses = app.world.resource_mut::<Assets<Bosses>>().add(
+            ron::from_str::<Bosses>(include_str!("../../assets/bosses.bosses.ron"))
+                .expect("Bosses should parse"),
+        );
+
+        // Only the first world's level is needed, the other assets are never drawn or played
+        let mut world = ron::from_str::<Worlds>(include_str!("../../assets/worlds.worlds.ron"))
+            .expect("Worlds should parse")
+            .worlds
+            .remove(0);
+        world.level = app.world.resource::<AssetServer>().load(&world.path);
+        app.insert_resource(CurrentWorld(world))
+            .insert_resource(WorldProgress::default());
+        app.insert_resource(GameAssets {
+            combinations: Default::default(),
+            achievements: Default::default(),
+            bosses,
+            pixel_font: Default::default(),
+            menu_background: Default::default(),
+            button: Default::default(),
+            explosion: Default::default(),
+            poof: Default::default(),
+            fireball: Default::default(),
+            wind: Default::default(),
+            droplet: Default::default(),
+            lava: Default::default(),
+            herochar: Default::default(),
+        })
+        .insert_resource(AudioAssets {
+            menu: Default::default(),
+            bgm: Default::default(),
+            jump: Default::default(),
+            death: Default::default(),
+            explosion: Default::default(),
+            collect: Default::default(),
+            fireball: Default::default(),
+            air: Default::default(),
+            steam: Default::default(),
+            pew: Default::default(),
+            hurt: Default::default(),
+            ping: Default::default(),
+            blip1: Default::default(),
+            blip2: Default::default(),
+        });
+
+        let mut harness = Self { app };
+        for _ in 0..LOAD_TIMEOUT {
+            if harness.count::<(With<Player>, Without<AwaitingStart>)>() > 0 {
+                // Let the level finish spawning and the physics settle
+                harness.step(30);
+                return harness;
+            }
+            harness.step(1);
+            // Assets are loaded on another thread
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        panic!("Level did not load within {} frames", LOAD_TIMEOUT);
+    }
+
+    /// Runs the app for a number of frames, each one physics step long
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+            // Just pressed only lasts one frame, the same as the real input system
+            self.app.world.resource_mut::<Input<Action>>().clear();
+        }
+    }
+
+    /// Steps until `condition` holds, returning false if it doesn't within `frames`
+    pub fn step_until(
+        &mut self,
+        frames: usize,
+        mut condition: impl FnMut(&mut Self) -> bool,
+    ) -> bool {
+        for _ in 0..frames {
+            if condition(self) {
+                return true;
+            }
+            self.step(1);
+        }
+        condition(self)
+    }
+
+    pub fn press(&mut self, action: Action) {
+        self.app.world.resource_mut::<Input<Action>>().press(action);
+    }
+
+    /// Presses the ability button once the cooldown from spawning or the last cast is over
+    pub fn cast(&mut self) {
+        let player = self.player();
+        let ready = self.step_until(60, |harness| {
+            harness
+                .app
+                .world
+                .get::<Controllable>(player)
+                .expect("Player should be controllable")
+                .ability_timer
+                .finished()
+        });
+        assert!(ready, "Ability should come off cooldown");
+        self.press(Action::Ability);
+    }
+
+    pub fn debug_settings(&mut self) -> Mut<DebugSettings> {
+        self.app.world.resource_mut::<DebugSettings>()
+    }
+
+    pub fn player(&mut self) -> Entity {
+        self.entities::<With<Player>>()[0]
+    }
+
+    /// Equips a combination without going through the ability menu
+    pub fn equip(&mut self, equipment: Equipment, element: Element) {
+        let player = self.player();
+        self.app
+            .world
+            .get_mut::<Player>(player)
+            .expect("Player should exist")
+            .combination = (Some(equipment), Some(element));
+    }
+
+    pub fn entities<F: ReadOnlyWorldQuery>(&mut self) -> Vec<Entity> {
+        let mut query = self.app.world.query_filtered::<Entity, F>();
+        query.iter(&self.app.world).collect()
+    }
+
+    pub fn count<F: ReadOnlyWorldQuery>(&mut self) -> usize {
+        self.entities::<F>().len()
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.app.world.get::<T>(entity).is_some()
+    }
+
+    pub fn position(&self, entity: Entity) -> Vec3 {
+        self.app
+            .world
+            .get::<GlobalTransform>(entity)
+            .expect("Entity should have a transform")
+            .translation()
+    }
+
+    /// Moves the player to a position in world space, facing right and standing still
+    pub fn teleport_player(&mut self, position: Vec3) {
+        let player = self.player();
+        let offset = position - self.position(player);
+        let world = &mut self.app.world;
+        let mut transform = world
+            .get_mut::<Transform>(player)
+            .expect("Player should have a transform");
+        transform.translation.x += offset.x;
+        transform.translation.y += offset.y;
+        world
+            .get_mut::<heron::Velocity>(player)
+            .expect("Player should have a velocity")
+            .linear = Vec3::ZERO;
+        world
+            .get_mut::<bevy::sprite::TextureAtlasSprite>(player)
+            .expect("Player should have a sprite")
+            .flip_x = false;
+        let mut controllable = world
+            .get_mut::<Controllable>(player)
+            .expect("Player should be controllable");
+        // Moving the player doesn't restart the ability cooldown
+        let ability_timer = controllable.ability_timer.clone();
+        *controllable = Controllable::new();
+        controllable.ability_timer = ability_timer;
+    }
+}
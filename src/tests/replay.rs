@@ -0,0 +1,87 @@
+use bevy::prelude::Vec3;
+use heron::Velocity;
+
+use crate::{
+    input::Action,
+    replay::{Replay, ReplayState},
+};
+
+use super::Harness;
+
+/// Runs right and jumps for a second while recording, returning the recording along with where
+/// the player ended up and how fast
+fn record() -> (Replay, Vec3, Vec3) {
+    let mut harness = Harness::new();
+    harness.debug_settings().record_replay = true;
+    harness.step(1);
+    for frame in 0..60 {
+        harness.press(Action::MoveRight);
+        if frame % 20 == 0 {
+            harness.press(Action::Jump);
+        }
+        harness.step(1);
+    }
+    // Taken straight from the resource so nothing is written to the save directory
+    harness.debug_settings().record_replay = false;
+    let state = std::mem::replace(
+        &mut *harness.app.world.resource_mut::<ReplayState>(),
+        ReplayState::Idle,
+    );
+    let replay = match state {
+        ReplayState::Recording { replay, .. } => replay,
+        _ => panic!("Should be recording"),
+    };
+    let (position, velocity) = player_motion(&mut harness);
+    (replay, position, velocity)
+}
+
+fn player_motion(harness: &mut Harness) -> (Vec3, Vec3) {
+    let player = harness.player();
+    let velocity = harness
+        .app
+        .world
+        .get::<Velocity>(player)
+        .expect("Player should have a velocity")
+        .linear;
+    (harness.position(player), velocity)
+}
+
+/// Plays a recording back in a fresh game, returning where the player ends up and how fast
+fn play(replay: Replay) -> (Vec3, Vec3) {
+    let mut harness = Harness::new();
+    *harness.app.world.resource_mut::<ReplayState>() = ReplayState::playing(replay);
+    assert!(
+        harness.step_until(600, |harness| !harness
+            .app
+            .world
+            .resource::<ReplayState>()
+            .is_playing()),
+        "Replay should finish"
+    );
+    player_motion(&mut harness)
+}
+
+#[test]
+fn replays_are_deterministic() {
+    let (replay, position, velocity) = record();
+    assert!(replay.length >= 60);
+    let start = replay.start[0];
+
+    let first = play(replay.clone());
+    let second = play(replay);
+    assert!(first.0.x > start, "The replay should move the player");
+    assert_eq!(first, second);
+    // Playback ends where the recording did
+    assert!(
+        (first.0 - position).length() < 0.01,
+        "{} != {}",
+        first.0,
+        position
+    );
+    assert!(
+        (first.1 - velocity).length() < 0.01,
+        "{} != {}",
+        first.1,
+        velocity
+    );
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::{Assets, Handle, Transform, Vec3};
+use bevy_ecs_ldtk::LdtkLevel;
+
+use crate::speedrun::{Run, Speedrun, Split};
+
+use super::Harness;
+
+fn split(name: &str, time: f64) -> Split {
+    Split {
+        name: name.to_string(),
+        time,
+    }
+}
+
+#[test]
+fn splits_are_compared_against_personal_best() {
+    let speedrun = Speedrun {
+        elapsed: 30.0,
+        splits: vec![
+            split("Level_2", 10.0),
+            split("Fire", 18.0),
+            split("Level_3", 25.0),
+        ],
+        finished: false,
+        best: Some(Run {
+            splits: vec![split("Level_2", 12.0), split("Level_3", 20.0)],
+            total: 60.0,
+        }),
+    };
+
+    assert_eq!(speedrun.delta(0), Some(-2.0));
+    // The best run didn't pick up Fire
+    assert_eq!(speedrun.delta(1), None);
+    assert_eq!(speedrun.delta(2), Some(5.0));
+    // Only entered Level_2 once in the best run
+    assert_eq!(speedrun.delta(3), None);
+}
+
+#[test]
+fn entering_a_level_records_a_split() {
+    let mut harness = Harness::new();
+    // Started directly instead of through the settings so no personal best is read from disk
+    harness.app.world.insert_resource(Speedrun {
+        elapsed: 0.0,
+        splits: Vec::new(),
+        finished: false,
+        best: None,
+    });
+
+    let center = {
+        let world = &mut harness.app.world;
+        let levels = world
+            .query::<(&Handle<LdtkLevel>, &Transform)>()
+            .iter(world)
+            .map(|(handle, transform)| (handle.clone(), transform.translation))
+            .collect::<Vec<_>>();
+        let ldtk_levels = world.resource::<Assets<LdtkLevel>>();
+        levels
+            .into_iter()
+            .find_map(|(handle, translation)| {
+                let level = &ldtk_levels.get(&handle)?.level;
+                (level.identifier == "Level_2").then(|| {
+                    translation + Vec3::new(level.px_wid as f32, level.px_hei as f32, 0.0) / 2.0
+                })
+            })
+            .expect("Level_2 should be spawned next to the first level")
+    };
+    harness.teleport_player(center);
+    harness.step(2);
+
+    let speedrun = harness.app.world.resource::<Speedrun>();
+    assert_eq!(
+        speedrun
+            .splits
+            .iter()
+            .map(|split| split.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Level_2"]
+    );
+    assert!(speedrun.splits[0].time > 0.0);
+}
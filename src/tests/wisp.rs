@@ -0,0 +1,160 @@
+use bevy::{
+    prelude::{Entity, Transform, TransformBundle, Vec2, Vec3},
+    sprite::TextureAtlasSprite,
+};
+use bevy_ecs_ldtk::EntityInstance;
+use heron::Acceleration;
+
+use crate::{
+    abilities::{Element, Equipment},
+    damage::Hurtbox,
+    entity::{
+        enemy::EnemyStats,
+        fan::{AirCurrent, ForceArea},
+        goblin::Enemy,
+        wisp::Wisp,
+    },
+    physics::{Direction, Dynamic, PhysicsObjectBundle},
+    stats::Stats,
+};
+
+use super::Harness;
+
+/// Open air in the first level, with a floor for the player just below
+fn open_air(harness: &mut Harness) -> Vec3 {
+    let player = harness.player();
+    harness.position(player) + Vec3::new(18.0 * 16.0, 16.0, 0.0)
+}
+
+/// A wisp with a slow enough path for it to keep up with, or hovering in place if `range` and
+/// `height` are 0
+fn spawn_wisp(harness: &mut Harness, position: Vec3, range: f32, height: f32) -> Entity {
+    harness
+        .app
+        .world
+        .spawn()
+        .insert_bundle(TransformBundle::from_transform(
+            Transform::from_translation(position),
+        ))
+        .insert_bundle(PhysicsObjectBundle::from(EntityInstance {
+            identifier: "Wisp".to_string(),
+            ..Default::default()
+        }))
+        .insert(TextureAtlasSprite::default())
+        .insert(Acceleration::default())
+        .insert(Dynamic::default())
+        .insert(Enemy)
+        .insert(Hurtbox)
+        .insert(EnemyStats::base("Wisp"))
+        .insert(Wisp {
+            range,
+            height,
+            period: 8.0,
+            home: None,
+            time: 0.0,
+        })
+        .id()
+}
+
+fn spawn_air_current(harness: &mut Harness, position: Vec3, direction: Direction) {
+    harness
+        .app
+        .world
+        .spawn()
+        .insert_bundle(TransformBundle::from_transform(
+            Transform::from_translation(position),
+        ))
+        .insert_bundle(PhysicsObjectBundle::from(EntityInstance {
+            identifier: "AirCurrent".to_string(),
+            ..Default::default()
+        }))
+        .insert(AirCurrent)
+        .insert(ForceArea {
+            direction,
+            strength: 1000.0,
+        });
+}
+
+#[test]
+fn wisp_path_is_a_figure_of_eight() {
+    let wisp = Wisp {
+        range: 32.0,
+        height: 8.0,
+        period: 4.0,
+        home: None,
+        time: 0.0,
+    };
+
+    assert!(wisp.path(0.0).length() < 0.001);
+    // Furthest to the side a quarter of the way round, level with home
+    assert!((wisp.path(1.0) - Vec2::new(32.0, 0.0)).length() < 0.001);
+    assert!((wisp.path(3.0) - Vec2::new(-32.0, 0.0)).length() < 0.001);
+    // Highest on the way out
+    assert!((wisp.path(0.5).y - 8.0).abs() < 0.001);
+    assert!((wisp.path(4.0)).length() < 0.001);
+}
+
+#[test]
+fn wisp_follows_its_path() {
+    let mut harness = Harness::new();
+    let home = open_air(&mut harness) + Vec3::new(0.0, 6.0 * 16.0, 0.0);
+    let wisp = spawn_wisp(&mut harness, home, 24.0, 6.0);
+
+    // One full figure of eight, lagging a little behind but swinging out to both sides
+    let (mut left, mut right) = (0.0_f32, 0.0_f32);
+    for _ in 0..8 * 60 {
+        harness.step(1);
+        let position = harness.position(wisp).truncate();
+        let component = harness.app.world.get::<Wisp>(wisp).unwrap();
+        let target = component.home.unwrap() + component.path(component.time);
+        assert!(
+            (position - target).length() < 12.0,
+            "Wisp at {} should be close to {} on its path",
+            position,
+            target
+        );
+        left = left.min(position.x - home.x);
+        right = right.max(position.x - home.x);
+    }
+    assert!(left < -16.0 && right > 16.0);
+}
+
+#[test]
+fn wisp_hovers_until_blown_away() {
+    let mut harness = Harness::new();
+    let home = open_air(&mut harness) + Vec3::new(0.0, 6.0 * 16.0, 0.0);
+    let wisp = spawn_wisp(&mut harness, home, 0.0, 0.0);
+
+    harness.step(60);
+    assert!(
+        (harness.position(wisp) - home).length() < 4.0,
+        "Wisp should cancel out gravity"
+    );
+
+    // A column of air blowing upwards, starting where the wisp hovers
+    for tile in 0..5 {
+        spawn_air_current(
+            &mut harness,
+            home + Vec3::new(0.0, tile as f32 * 16.0, 0.0),
+            Direction::North,
+        );
+    }
+    harness.step(20);
+    assert!(harness.app.world.get::<Dynamic>(wisp).unwrap().counter > 0);
+    assert!(harness.position(wisp).y > home.y + 16.0);
+}
+
+#[test]
+fn fireball_burns_wisp() {
+    let mut harness = Harness::new();
+    harness.debug_settings().imortality = true;
+    let position = open_air(&mut harness);
+    let wisp = spawn_wisp(&mut harness, position + Vec3::new(32.0, 0.0, 0.0), 0.0, 0.0);
+    harness.teleport_player(position);
+    harness.equip(Equipment::Staff, Element::Fire);
+    harness.cast();
+
+    assert!(harness.step_until(60, |harness| !harness.has::<Hurtbox>(wisp)));
+    harness.step(1);
+    assert_eq!(harness.app.world.resource::<Stats>().enemies_killed, 1);
+}
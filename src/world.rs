@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use bevy::{
+    asset::{AddAsset, AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::{Handle, Plugin},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_ecs_ldtk::LdtkAsset;
+use serde::Deserialize;
+
+/// Worlds are separate LDtk projects, each with its own checkpoint and completion in the save
+pub struct WorldsPlugin;
+
+impl Plugin for WorldsPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_asset::<Worlds>()
+            .init_asset_loader::<WorldsLoader>();
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WorldInfo {
+    /// Key for the world's progress in save files, so it must never change once shipped
+    pub id: String,
+    pub name: String,
+    /// Path of the LDtk project, relative to the assets folder
+    pub path: String,
+    #[serde(skip)]
+    pub level: Handle<LdtkAsset>,
+}
+
+/// Every world in the order they are shown in the level select menu. Loading this also loads
+/// each world's LDtk project.
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "0c7e2f4a-9d3b-4e61-8a5f-2b1d7c9e4f03"]
+pub struct Worlds {
+    pub worlds: Vec<WorldInfo>,
+}
+
+impl Worlds {
+    pub fn get(&self, id: &str) -> Option<&WorldInfo> {
+        self.worlds.iter().find(|world| world.id == id)
+    }
+}
+
+/// The world being played
+#[derive(Clone, Debug)]
+pub struct CurrentWorld(pub WorldInfo);
+
+#[derive(Default)]
+pub struct WorldsLoader;
+
+impl AssetLoader for WorldsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let mut worlds = ron::de::from_bytes::<Worlds>(bytes)?;
+            let mut dependencies = Vec::new();
+            for world in worlds.worlds.iter_mut() {
+                let path = AssetPath::new(PathBuf::from(&world.path), None);
+                world.level = load_context.get_handle(path.clone());
+                dependencies.push(path);
+            }
+            load_context
+                .set_default_asset(LoadedAsset::new(worlds).with_dependencies(dependencies));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["worlds.ron"]
+    }
+}